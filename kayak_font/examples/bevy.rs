@@ -88,7 +88,8 @@ fn control_text(
             let next = match text.horz_alignment {
                 Alignment::Start => Alignment::Middle,
                 Alignment::Middle => Alignment::End,
-                Alignment::End => Alignment::Start,
+                Alignment::End => Alignment::Justify,
+                Alignment::Justify => Alignment::Start,
             };
             text.horz_alignment = next;
         }
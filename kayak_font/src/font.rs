@@ -88,6 +88,14 @@ impl KayakFont {
         self.char_ids.get(&c).and_then(|id| Some(*id))
     }
 
+    /// Checks if this font has a glyph for every non-whitespace character of the given content.
+    pub fn covers(&self, content: &str) -> bool {
+        content
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .all(|c| self.char_ids.contains_key(&c))
+    }
+
     pub fn get_word_width(&self, word: &str, properties: TextProperties) -> f32 {
         let space_width = self.get_space_width(properties);
         let tab_width = self.get_tab_width(properties);
@@ -261,9 +269,52 @@ impl KayakFont {
         size.1 = properties.line_height * lines.len() as f32;
 
         // === Shift Lines & Glyphs === //
+        if properties.alignment == Alignment::Justify {
+            let chars = content.chars().collect::<Vec<_>>();
+            let last_line = lines.len() - 1;
+            for (line_index, line) in lines.iter().enumerate() {
+                let ends_in_hard_break = line
+                    .total_chars()
+                    .checked_sub(1)
+                    .and_then(|offset| chars.get(line.char_index() + offset))
+                    .map(|c| utility::is_newline(*c))
+                    .unwrap_or_default();
+                if line_index == last_line || ends_in_hard_break {
+                    continue;
+                }
+
+                // Whitespace graphemes have a width but no glyphs
+                let is_gap =
+                    |grapheme: &Grapheme| grapheme.glyph_total == 0 && grapheme.size.0 > 0.0;
+                let gaps = line.graphemes().iter().filter(|g| is_gap(g)).count();
+                if gaps == 0 {
+                    continue;
+                }
+
+                let gap_width = (properties.max_size.0 - line.width()).max(0.0) / gaps as f32;
+                let mut shift_x = 0.0;
+                for grapheme in line.graphemes() {
+                    if is_gap(grapheme) {
+                        shift_x += gap_width;
+                    }
+                    let glyphs = grapheme.glyph_index..grapheme.glyph_index + grapheme.glyph_total;
+                    for rect in glyph_rects[glyphs].iter_mut() {
+                        rect.position.0 += shift_x;
+                    }
+                }
+            }
+
+            // Justified text that wraps always fills the full width
+            if lines.len() > 1 {
+                size.0 = size.0.max(properties.max_size.0);
+            }
+
+            return TextLayout::new(glyph_rects, lines, size, properties);
+        }
+
         for line in lines.iter() {
             let shift_x = match properties.alignment {
-                Alignment::Start => 0.0,
+                Alignment::Start | Alignment::Justify => 0.0,
                 Alignment::Middle => (properties.max_size.0 - line.width()) / 2.0,
                 Alignment::End => properties.max_size.0 - line.width(),
             };
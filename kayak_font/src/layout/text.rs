@@ -9,6 +9,16 @@ pub enum Alignment {
     Start,
     Middle,
     End,
+    /// Stretches the spaces of every wrapped line so that it fills the full width.
+    ///
+    /// The last line, and any line ending in a hard break, is aligned to the start.
+    Justify,
+}
+
+impl Default for Alignment {
+    fn default() -> Self {
+        Self::Start
+    }
 }
 
 /// Properties to control text layout.
@@ -46,6 +46,14 @@ mod tests {
         }
     }
 
+    #[test]
+    fn should_cover_only_known_glyphs() {
+        let font = make_font();
+
+        assert!(font.covers("Hello world!\n\tHow are you?"));
+        assert!(!font.covers("Hello 漢字"));
+    }
+
     #[test]
     fn should_contain_correct_number_of_chars() {
         let content = "Hello world!\nHow is everyone on this super-awesome rock doing today?";
@@ -1,4 +1,8 @@
-use crate::{atlas::Atlas, glyph::Glyph, metrics::Metrics};
+use crate::{
+    atlas::Atlas,
+    glyph::{Glyph, Rect},
+    metrics::Metrics,
+};
 use nanoserde::DeJson;
 
 #[derive(DeJson, Default, Debug, Clone, PartialEq)]
@@ -26,6 +30,32 @@ impl Sdf {
         }
     }
 
+    /// Creates an SDF without an atlas where every printable ASCII glyph has the same advance
+    ///
+    /// The advance is relative to the font size, so an advance of `0.5` makes each glyph half as
+    /// wide as the font size. This is useful for measuring text deterministically, such as in
+    /// tests, since it can't be rendered.
+    pub fn monospace(advance: f32) -> Self {
+        let glyphs = (' '..='~')
+            .map(|unicode| Glyph {
+                unicode,
+                advance,
+                atlas_bounds: None,
+                plane_bounds: Some(Rect {
+                    left: 0.0,
+                    bottom: -0.25,
+                    right: advance,
+                    top: 0.75,
+                }),
+            })
+            .collect();
+
+        Self {
+            glyphs,
+            ..Self::new()
+        }
+    }
+
     pub fn from_string(data: String) -> Sdf {
         let value: Sdf = match DeJson::deserialize_json(data.as_str()) {
             Ok(v) => v,
@@ -76,3 +106,10 @@ fn test_sdf_loader() {
     assert!(sdf.atlas.height == 212);
     assert!(matches!(sdf.atlas.sdf_type, SDFType::Msdf));
 }
+
+#[test]
+fn test_monospace_sdf() {
+    let sdf = Sdf::monospace(0.5);
+    assert_eq!(95, sdf.glyphs.len());
+    assert!(sdf.glyphs.iter().all(|glyph| glyph.advance == 0.5));
+}
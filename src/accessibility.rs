@@ -0,0 +1,65 @@
+use bevy::prelude::Entity;
+
+use crate::{layout::Rect, styles::Role};
+
+/// A tree describing the widgets on screen to assistive technologies
+///
+/// Created using [`KayakRootContext::build_accessibility_tree`](crate::prelude::KayakRootContext::build_accessibility_tree).
+/// Its structure mirrors the widget tree, but only contains visible widgets with a meaningful
+/// role, which makes it suitable for feeding an adapter such as AccessKit.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AccessNodeTree {
+    /// The topmost nodes of the tree
+    pub roots: Vec<AccessNode>,
+}
+
+/// A single widget in an [`AccessNodeTree`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessNode {
+    /// The widget this node describes
+    pub id: Entity,
+    pub role: Role,
+    /// The text describing this widget
+    ///
+    /// Widgets without text of their own use the text of their direct text children.
+    pub label: Option<String>,
+    /// The rendered layout of the widget
+    pub bounds: Rect,
+    pub focusable: bool,
+    pub focused: bool,
+    pub children: Vec<AccessNode>,
+}
+
+impl AccessNodeTree {
+    /// Visits every node in depth-first order
+    pub fn iter(&self) -> impl Iterator<Item = &AccessNode> {
+        let mut stack = self.roots.iter().rev().collect::<Vec<_>>();
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// Finds the node describing the given widget
+    pub fn get(&self, id: Entity) -> Option<&AccessNode> {
+        self.iter().find(|node| node.id == id)
+    }
+}
+
+/// Combines the labels of the text nodes among the given children
+pub(crate) fn label_from_children(children: &[AccessNode]) -> Option<String> {
+    let label = children
+        .iter()
+        .filter(|child| child.role == Role::Text)
+        .filter_map(|child| child.label.as_deref())
+        .map(str::trim)
+        .filter(|label| !label.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if label.is_empty() {
+        None
+    } else {
+        Some(label)
+    }
+}
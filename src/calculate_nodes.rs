@@ -1,45 +1,148 @@
 use bevy::{
     prelude::{Assets, Commands, Entity, In, Query, Res, With},
-    utils::HashMap,
+    tasks::{ComputeTaskPool, TaskPool},
+    time::Time,
+    utils::{HashMap, HashSet},
 };
-use kayak_font::KayakFont;
+use kayak_font::{Alignment, KayakFont, TextProperties};
 use morphorm::Hierarchy;
 
 use crate::{
-    layout::{DataCache, Rect},
+    context::{is_within, WidgetName},
+    layout::{align_baselines, relayout_roots, IntrinsicSize, Rect},
     node::{DirtyNode, Node, NodeBuilder, WrappedIndex},
     prelude::{KStyle, KayakRootContext, Tree},
-    render::font::FontMapping,
+    render::font::{FontMapping, FontProvider, HeadlessAssets, LoadedFonts},
     render_primitive::RenderPrimitive,
-    styles::{ComputedStyles, RenderCommand, StyleProp, Units},
+    styles::{
+        ActiveTransition, ComputedStyles, KPositionType, PseudoStyles, RenderCommand, StyleProp,
+        TextWrap, Units,
+    },
+    tree::UpwardIterator,
 };
 
+/// Appended to text that is cut off by [`TextWrap::Truncate`]
+const ELLIPSIS: &str = "…";
+
+/// Appended to cut off text instead of [`ELLIPSIS`] when the font has no glyph for it
+const ASCII_ELLIPSIS: &str = "...";
+
+/// Returns the ellipsis that the given font can draw
+fn ellipsis_for(font: &KayakFont) -> &'static str {
+    if font.covers(ELLIPSIS) {
+        ELLIPSIS
+    } else {
+        ASCII_ELLIPSIS
+    }
+}
+
+/// A dirty node whose styles have been resolved, waiting on its text to be measured
+struct PendingNode {
+    id: WrappedIndex,
+    styles: KStyle,
+    raw_styles: KStyle,
+    primitive: RenderPrimitive,
+    measurement: Option<(WrappedIndex, (f32, f32))>,
+    intrinsic_size: Option<(f32, f32)>,
+    z: f32,
+    needs_layout: bool,
+}
+
 pub fn calculate_nodes(
     In(mut context): In<KayakRootContext>,
     mut commands: Commands,
-    fonts: Res<Assets<KayakFont>>,
-    font_mapping: Res<FontMapping>,
+    fonts: Option<Res<Assets<KayakFont>>>,
+    font_mapping: Option<Res<FontMapping>>,
+    headless_assets: Option<Res<HeadlessAssets>>,
+    time: Res<Time>,
     query: Query<Entity, With<DirtyNode>>,
     all_styles_query: Query<&ComputedStyles>,
+    pseudo_styles_query: Query<&PseudoStyles>,
     node_query: Query<(Entity, &Node)>,
+    widget_names: Query<&WidgetName>,
+    intrinsic_sizes: Query<&IntrinsicSize>,
 ) -> KayakRootContext {
     let mut new_nodes = HashMap::<Entity, (Node, bool)>::default();
 
-    context.current_z = 0.0;
+    context.current_z = context.z_base;
 
-    let initial_styles = KStyle::initial();
-    let default_styles = KStyle::new_default();
+    let default_styles = context.default_styles.clone();
+
+    // Headless fonts take precedence, since fonts might never be loaded without a renderer
+    let loaded_fonts = match (&fonts, &font_mapping) {
+        (Some(fonts), Some(font_mapping)) => Some(LoadedFonts {
+            font_mapping: &**font_mapping,
+            fonts: &**fonts,
+        }),
+        _ => None,
+    };
+    let font_provider: Option<&dyn FontProvider> = match &headless_assets {
+        Some(headless_assets) => Some(&**headless_assets),
+        None => loaded_fonts
+            .as_ref()
+            .map(|loaded_fonts| loaded_fonts as &dyn FontProvider),
+    };
 
     if let Ok(tree) = context.tree.clone().try_read() {
         if tree.root_node.is_none() {
             return context;
         }
 
-        for dirty_entity in query.iter() {
+        // New default styles can change what any node inherits, so everything is re-resolved
+        // (parents first, so children inherit the updated values).
+        let mut dirty_entities = if context.default_styles_changed {
+            context.default_styles_changed = false;
+            tree.down_iter().map(|index| index.0).collect::<Vec<_>>()
+        } else {
+            query.iter().collect::<Vec<_>>()
+        };
+
+        // Widgets that gained or lost focus may need to swap their focus styles.
+        let focus = context.get_current_focus().map(WrappedIndex);
+        if focus != context.styled_focus {
+            for index in context.styled_focus.iter().chain(focus.iter()) {
+                if !dirty_entities.contains(&index.0) {
+                    dirty_entities.push(index.0);
+                }
+            }
+            context.styled_focus = focus;
+        }
+
+        let dirty_entities = match context.node_budget {
+            Some(max_nodes) => {
+                let remaining = max_nodes.saturating_sub(context.budget_used);
+                let (dirty_entities, deferred) =
+                    take_within_budget(&tree, dirty_entities, remaining);
+                context.budget_used += dirty_entities.len();
+                // Nodes dirtied by the context itself aren't marked, so mark them to be picked up later.
+                for entity in deferred.iter() {
+                    if let Some(mut entity_commands) = commands.get_entity(*entity) {
+                        entity_commands.insert(DirtyNode);
+                    }
+                }
+                context.pending_nodes = !deferred.is_empty();
+                dirty_entities
+            }
+            None => {
+                context.pending_nodes = false;
+                dirty_entities
+            }
+        };
+
+        // Styles are resolved first, one node at a time, since children inherit from their
+        // parents. Text measurement only depends on the resolved styles and the previous
+        // layout, so it can then run in parallel before the nodes are assembled.
+        let mut pending_nodes = Vec::<PendingNode>::with_capacity(dirty_entities.len());
+        let mut pending_index = HashMap::<Entity, usize>::default();
+        for dirty_entity in dirty_entities {
             let dirty_entity = WrappedIndex(dirty_entity);
             if !tree.contains(dirty_entity) {
                 continue;
             }
+            // Hidden nodes stay dirty until they're shown again
+            if is_within(&tree, &tree.hidden, dirty_entity) {
+                continue;
+            }
 
             let styles = all_styles_query
                 .get(dirty_entity.0)
@@ -49,52 +152,101 @@ pub fn calculate_nodes(
             // 1. Already-resolved node styles (best)
             // 2. Unresolved widget prop styles
             // 3. Unresolved default styles
+            //
+            // These are only borrowed since inheriting clones just the inherited values.
             let parent_styles = if let Some(parent_widget_id) = tree.parents.get(&dirty_entity) {
-                if let Some(parent_node) = new_nodes.get(&parent_widget_id.0) {
-                    parent_node.0.resolved_styles.clone()
+                if let Some(parent_index) = pending_index.get(&parent_widget_id.0) {
+                    &pending_nodes[*parent_index].styles
                 } else if let Ok((_, parent_node)) = node_query.get(parent_widget_id.0) {
-                    parent_node.resolved_styles.clone()
+                    &parent_node.resolved_styles
                 } else if let Ok(parent_styles) = all_styles_query.get(parent_widget_id.0) {
-                    parent_styles.0.clone()
+                    &parent_styles.0
                 } else {
-                    default_styles.clone()
+                    if context.warn_missing_parent_styles {
+                        let name = widget_names
+                            .get(dirty_entity.0)
+                            .map(|name| name.0.as_str())
+                            .unwrap_or("Widget");
+                        log::warn!(
+                            "{} {:?}: the styles of its parent {:?} couldn't be resolved, so the default styles are inherited instead",
+                            name,
+                            dirty_entity.0,
+                            parent_widget_id.0
+                        );
+                    }
+                    &default_styles
                 }
             } else {
-                default_styles.clone()
+                &default_styles
             };
 
-            // let parent_z = if let Some(parent_widget_id) = tree.parents.get(&dirty_entity) {
-            //     if let Some(parent_node) = new_nodes.get(&parent_widget_id.0) {
-            //         parent_node.0.z
-            //     } else if let Ok((_, parent_node)) = node_query.get(parent_widget_id.0) {
-            //         parent_node.z
-            //     } else {
-            //         if let Ok(parent_styles) = all_styles_query.get(parent_widget_id.0) {
-            //             parent_styles.z_index.resolve() as f32
-            //         } else {
-            //             -1.0
-            //         }
-            //     }
-            // } else {
-            //     -1.0
-            // };
-
             let raw_styles = styles.clone();
-            let mut styles = raw_styles.clone();
-            // Fill in all `initial` values for any unset property
-            styles.apply(&initial_styles);
-            // Fill in all `inherited` values for any `inherit` property
-            styles.inherit(&parent_styles);
-
-            // let mut current_z = {
-            //     if parent_z > -1.0 {
-            //         parent_z + 1.0
-            //     } else {
-            //         let z = context.current_z;
-            //         context.current_z += 1.0;
-            //         z
-            //     }
-            // };
+            let (mut styles, has_calc_size) = resolve_styles(
+                &context,
+                dirty_entity,
+                &raw_styles,
+                pseudo_styles_query.get(dirty_entity.0).ok(),
+                tree.get_parent(dirty_entity),
+                parent_styles,
+            );
+
+            if context.validate_styles {
+                let parent_styles = tree.parents.get(&dirty_entity).map(|_| parent_styles);
+                for warning in styles.validate(parent_styles) {
+                    let name = widget_names
+                        .get(dirty_entity.0)
+                        .map(|name| name.0.as_str())
+                        .unwrap_or("Widget");
+                    log::warn!("{} {:?}: {}", name, dirty_entity.0, warning);
+                }
+            }
+
+            // Blend any transitioned properties towards their new values
+            let mut in_transition = false;
+            if let StyleProp::Value(transition) = styles.transition.clone() {
+                let now = time.elapsed_seconds();
+                let previous_styles = node_query
+                    .get(dirty_entity.0)
+                    .ok()
+                    .map(|(_, node)| &node.resolved_styles);
+                let target_changed = match context.style_transitions.get(&dirty_entity.0) {
+                    Some(active) => transition.differs(&active.to, &styles),
+                    None => previous_styles
+                        .map(|previous| transition.differs(previous, &styles))
+                        .unwrap_or_default(),
+                };
+
+                if target_changed {
+                    if let Some(previous_styles) = previous_styles {
+                        // Start from what is currently displayed so interrupting an
+                        // in-flight transition doesn't cause a jump.
+                        context.style_transitions.insert(
+                            dirty_entity.0,
+                            ActiveTransition {
+                                from: previous_styles.clone(),
+                                to: styles.clone(),
+                                started: now,
+                            },
+                        );
+                    }
+                }
+
+                if let Some(active) = context.style_transitions.get(&dirty_entity.0) {
+                    let progress = if transition.duration > 0.0 {
+                        (now - active.started) / transition.duration
+                    } else {
+                        1.0
+                    };
+                    if progress < 1.0 {
+                        transition.blend(&active.from, &active.to, progress, &mut styles);
+                        in_transition = true;
+                    } else {
+                        context.style_transitions.remove(&dirty_entity.0);
+                    }
+                }
+            } else {
+                context.style_transitions.remove(&dirty_entity.0);
+            }
 
             let current_z = if matches!(styles.z_index, StyleProp::Value(..)) {
                 styles.z_index.resolve() as f32
@@ -102,21 +254,113 @@ pub fn calculate_nodes(
                 -1.0
             };
 
-            let (primitive, needs_layout) = create_primitive(
+            // Nodes without a parent fill the window, which might not be known yet. They won't
+            // get a parent layout to wait on, so they aren't kept dirty for it.
+            let has_parent = tree.get_parent(dirty_entity).is_some();
+            let (parent_width, parent_padding) = if has_parent {
+                (
+                    parent_layout.map(|layout| layout.width),
+                    horizontal_padding(parent_styles),
+                )
+            } else {
+                (
+                    Some(context.viewport_size.0).filter(|width| *width > 0.0),
+                    0.0,
+                )
+            };
+            let has_aspect_size =
+                apply_aspect_ratio(&mut styles, parent_width, parent_padding) || !has_parent;
+            if let Some(frozen_layout) = context.frozen_layouts.get(&dirty_entity) {
+                apply_frozen_layout(&mut styles, frozen_layout);
+            }
+
+            let primitive = RenderPrimitive::from(&styles);
+            if let RenderPrimitive::Text { font, .. } = &primitive {
+                let is_loading = font_provider
+                    .map(|font_provider| font_provider.is_loading(font))
+                    .unwrap_or(true);
+                context.set_asset_pending(dirty_entity, is_loading);
+            } else {
+                context.set_asset_pending(dirty_entity, false);
+            }
+
+            if tree.is_root(dirty_entity) {
+                let width = styles.width.resolve().value_or(0.0, 0.0);
+                let height = styles.height.resolve().value_or(0.0, 0.0);
+                if let Ok(mut cache) = context.layout_cache.try_write() {
+                    cache.rect.insert(
+                        dirty_entity,
+                        Rect {
+                            posx: 0.0,
+                            posy: 0.0,
+                            width,
+                            height,
+                            z_index: 0.0,
+                            paint_order: 0,
+                        },
+                    );
+                }
+            }
+
+            pending_index.insert(dirty_entity.0, pending_nodes.len());
+            pending_nodes.push(PendingNode {
+                id: dirty_entity,
+                styles,
+                raw_styles,
+                primitive,
+                measurement: None,
+                intrinsic_size: intrinsic_sizes.get(dirty_entity.0).ok().map(|size| size.0),
+                z: current_z,
+                // Keep the node dirty until its transition completes.
+                needs_layout: in_transition || !has_aspect_size || !has_calc_size,
+            });
+        }
+
+        if let Some(font_provider) = font_provider {
+            let context = &context;
+            let all_styles_query = &all_styles_query;
+            for_each_in_batches(&mut pending_nodes, |pending| {
+                pending.measurement = measure_text(
+                    context,
+                    font_provider,
+                    all_styles_query,
+                    pending.id,
+                    &pending.styles,
+                    &mut pending.primitive,
+                );
+                if pending.intrinsic_size.is_none() {
+                    pending.intrinsic_size = intrinsic_text_size(
+                        &pending.styles,
+                        &pending.primitive,
+                        pending.measurement,
+                    );
+                }
+            });
+        }
+
+        for pending in pending_nodes {
+            let PendingNode {
+                id: dirty_entity,
+                mut styles,
+                raw_styles,
+                primitive,
+                measurement,
+                intrinsic_size,
+                z,
+                needs_layout,
+            } = pending;
+
+            apply_intrinsic_size(&mut styles, intrinsic_size);
+
+            let needs_layout = apply_measurement(
                 &mut commands,
                 &context,
-                &fonts,
-                &font_mapping,
                 &query,
-                // &node_query,
                 dirty_entity,
+                &primitive,
+                measurement,
                 &mut styles,
-                node_query
-                    .get(dirty_entity.0)
-                    .map(|(_, node)| node.raw_styles.clone().unwrap_or_default())
-                    .unwrap_or_default(),
-                &all_styles_query,
-            );
+            ) || needs_layout;
 
             let children = tree
                 .children
@@ -124,9 +368,6 @@ pub fn calculate_nodes(
                 .cloned()
                 .unwrap_or_default();
 
-            let width = styles.width.resolve().value_or(0.0, 0.0);
-            let height = styles.height.resolve().value_or(0.0, 0.0);
-
             let mut node = NodeBuilder::empty()
                 .with_id(dirty_entity)
                 .with_styles(styles, Some(raw_styles))
@@ -134,29 +375,46 @@ pub fn calculate_nodes(
                 .with_primitive(primitive)
                 .build();
 
-            if dirty_entity == tree.root_node.unwrap() {
-                if let Ok(mut cache) = context.layout_cache.try_write() {
-                    cache.rect.insert(
-                        dirty_entity,
-                        Rect {
-                            posx: 0.0,
-                            posy: 0.0,
-                            width,
-                            height,
-                            z_index: 0.0,
-                        },
-                    );
-                }
-            }
             node.old_z = node_query
                 .get(dirty_entity.0)
                 .map(|old_node| old_node.1.z)
                 .unwrap_or(0.0);
-            node.z = current_z;
+            node.z = z;
+
+            // Only changes that can move or resize something need a new layout pass.
+            let layout_changed = match node_query.get(dirty_entity.0) {
+                Ok((_, old_node)) => {
+                    old_node.children != node.children
+                        || old_node
+                            .resolved_styles
+                            .layout_differs(&node.resolved_styles)
+                }
+                Err(_) => true,
+            };
+            if layout_changed {
+                context.dirty_layout_nodes.insert(dirty_entity);
+            }
+
+            // Nodes waiting on a layout are rebuilt every pass, often without any change
+            let rebuilt = match node_query.get(dirty_entity.0) {
+                Ok((_, old_node)) => {
+                    old_node.children != node.children
+                        || old_node.resolved_styles != node.resolved_styles
+                        || old_node.raw_styles != node.raw_styles
+                        || old_node.primitive != node.primitive
+                        || old_node.z != node.z
+                }
+                Err(_) => true,
+            };
+            if rebuilt {
+                context.bump_generation(dirty_entity);
+            }
             new_nodes.insert(dirty_entity.0, (node, needs_layout));
         }
 
-        // let has_new_nodes = new_nodes.len() > 0;
+        if let Ok(mut stats) = context.stats.try_write() {
+            stats.nodes_rebuilt += new_nodes.len();
+        }
 
         for (entity, (node, needs_layout)) in new_nodes.drain() {
             if !needs_layout {
@@ -167,30 +425,114 @@ pub fn calculate_nodes(
 
             commands.entity(entity).insert(node);
         }
+
+        context.notify_assets_ready(&tree);
     }
 
     context
 }
 
+/// Splits the dirty nodes into those to rebuild now, at most `max_nodes` of them, and those
+/// left for later
+///
+/// Nodes are taken in tree order so that parents are always resolved before their children,
+/// which also keeps the split the same from run to run.
+fn take_within_budget(
+    tree: &Tree,
+    dirty_entities: Vec<Entity>,
+    max_nodes: usize,
+) -> (Vec<Entity>, Vec<Entity>) {
+    if dirty_entities.len() <= max_nodes {
+        return (dirty_entities, Vec::new());
+    }
+
+    let dirty_entities = dirty_entities.into_iter().collect::<HashSet<_>>();
+    let mut ordered = tree
+        .down_iter()
+        .map(|index| index.0)
+        .filter(|entity| dirty_entities.contains(entity))
+        .collect::<Vec<_>>();
+    let deferred = ordered.split_off(max_nodes.min(ordered.len()));
+    (ordered, deferred)
+}
+
 pub fn calculate_layout(
-    In(context): In<KayakRootContext>,
+    In(mut context): In<KayakRootContext>,
     mut commands: Commands,
     nodes_no_entity_query: Query<&'static Node>,
 ) -> KayakRootContext {
+    // Repaints that don't touch layout can skip the layout pass entirely.
+    if context.dirty_layout_nodes.is_empty() {
+        return context;
+    }
+
     if let Ok(tree) = context.tree.try_read() {
-        // tree.dump();
         let node_tree = &*tree;
+        let roots = relayout_roots(
+            node_tree,
+            &context.dirty_layout_nodes,
+            &nodes_no_entity_query,
+        );
         if let Ok(mut cache) = context.layout_cache.try_write() {
-            let mut data_cache = DataCache {
-                cache: &mut cache,
-                query: &nodes_no_entity_query,
+            let layout_nodes = if roots.iter().any(|root| Some(*root) == node_tree.root_node) {
+                if node_tree.layers.is_empty() {
+                    context
+                        .layout_engine
+                        .layout(&mut cache, node_tree, &nodes_no_entity_query);
+                } else {
+                    // Each layer is laid out on its own, like the root
+                    for root in node_tree.visible_roots() {
+                        context.layout_engine.layout_subtree(
+                            &mut cache,
+                            node_tree,
+                            root,
+                            &nodes_no_entity_query,
+                        );
+                    }
+                }
+                let roots = node_tree.visible_roots().collect::<Vec<_>>();
+                align_baselines(&mut cache, node_tree, &roots, &nodes_no_entity_query);
+                cache.recomputed = roots
+                    .iter()
+                    .flat_map(|root| node_tree.visible_nodes_at(*root))
+                    .collect();
+                cache.recomputed.len()
+            } else {
+                // Nothing outside of the relaid subtrees has moved since the last layout.
+                cache.geometry_changed.clear();
+                let layout_nodes = roots
+                    .iter()
+                    .map(|root| {
+                        context.layout_engine.layout_subtree(
+                            &mut cache,
+                            node_tree,
+                            *root,
+                            &nodes_no_entity_query,
+                        );
+                        node_tree.subtree(*root).len()
+                    })
+                    .sum();
+                align_baselines(&mut cache, node_tree, &roots, &nodes_no_entity_query);
+                cache.recomputed = roots
+                    .iter()
+                    .flat_map(|root| node_tree.visible_nodes_at(*root))
+                    .collect();
+                layout_nodes
             };
-            morphorm::layout(&mut data_cache, node_tree, &nodes_no_entity_query);
+
+            if let Ok(mut stats) = context.stats.try_write() {
+                stats.layout_nodes = layout_nodes;
+            }
+
+            if context.pixel_snapping {
+                for rect in cache.rect.values_mut() {
+                    *rect = rect.snapped();
+                }
+            }
 
             for (entity, change) in cache.geometry_changed.iter() {
                 if !change.is_empty() {
                     for child in tree.child_iter(*entity) {
-                        // log::info!("Layout changed for: {:?}", child.0.id());
                         if let Some(mut entity_commands) = commands.get_entity(child.0) {
                             entity_commands.insert(DirtyNode);
                         }
@@ -199,122 +541,630 @@ pub fn calculate_layout(
             }
         }
     }
+    context.dirty_layout_nodes.clear();
+    context.notify_layout_changed();
 
     context
 }
 
-fn create_primitive(
+/// Applies the measured size of a text node to its styles
+///
+/// Returns true if the node has to stay dirty, so that it is measured again once its parent
+/// has been laid out.
+fn apply_measurement(
     commands: &mut Commands,
     context: &KayakRootContext,
-    fonts: &Assets<KayakFont>,
-    font_mapping: &FontMapping,
-    // query: &Query<(Entity, &Node)>,
     dirty: &Query<Entity, With<DirtyNode>>,
     id: WrappedIndex,
+    render_primitive: &RenderPrimitive,
+    measurement: Option<(WrappedIndex, (f32, f32))>,
     styles: &mut KStyle,
-    _prev_styles: KStyle,
-    all_styles_query: &Query<&ComputedStyles>,
-) -> (RenderPrimitive, bool) {
-    let mut render_primitive = RenderPrimitive::from(&styles.clone());
-    let mut needs_layout = true;
+) -> bool {
+    let properties = match render_primitive {
+        RenderPrimitive::Text { properties, .. } => properties,
+        _ => return false,
+    };
+    let needs_layout = match measurement {
+        Some((parent_id, measurement)) => {
+            // --- Apply Layout --- //
+            if matches!(styles.width, StyleProp::Default) {
+                styles.width = StyleProp::Value(Units::Pixels(measurement.0));
+            }
+            if matches!(styles.height, StyleProp::Default) {
+                styles.height = StyleProp::Value(Units::Pixels(measurement.1));
+            }
+
+            properties.max_size.0 == 0.0
+                || properties.max_size.1 == 0.0
+                || context.get_geometry_changed(&parent_id)
+                || dirty.contains(parent_id.0)
+        }
+        None => true,
+    };
+
+    if needs_layout {
+        commands.entity(id.0).insert(DirtyNode);
+    }
 
-    match &mut render_primitive {
+    needs_layout
+}
+
+/// The number of nodes measured by each task of [`for_each_in_batches`]
+const MEASURE_BATCH_SIZE: usize = 32;
+
+/// Runs `f` on every item, splitting the items into batches that run on the compute task pool
+///
+/// Small workloads run on the current thread, since spawning tasks would cost more than it saves.
+fn for_each_in_batches<T: Send>(items: &mut [T], f: impl Fn(&mut T) + Sync) {
+    if items.len() <= MEASURE_BATCH_SIZE {
+        items.iter_mut().for_each(f);
+        return;
+    }
+
+    let f = &f;
+    ComputeTaskPool::init(TaskPool::default).scope(|scope| {
+        for batch in items.chunks_mut(MEASURE_BATCH_SIZE) {
+            scope.spawn(async move {
+                batch.iter_mut().for_each(f);
+            });
+        }
+    });
+}
+
+/// Lays out the given text primitive within the current layout of its closest non-empty parent
+///
+/// Returns the parent used along with the measured size, or `None` if the primitive isn't text
+/// or if the font or the parent's layout isn't available yet.
+pub(crate) fn measure_text(
+    context: &KayakRootContext,
+    fonts: &dyn FontProvider,
+    all_styles_query: &Query<&ComputedStyles>,
+    id: WrappedIndex,
+    styles: &KStyle,
+    render_primitive: &mut RenderPrimitive,
+) -> Option<(WrappedIndex, (f32, f32))> {
+    let (content, font, properties, text_layout, word_wrap, outline_width) = match render_primitive
+    {
         RenderPrimitive::Text {
             content,
             font,
             properties,
             text_layout,
             word_wrap,
+            outline,
+            outline_in_bounds,
             ..
         } => {
-            // --- Bind to Font Asset --- //
-            let font_handle = font_mapping.get_handle(font.clone()).unwrap();
-            if let Some(font) = fonts.get(&font_handle) {
-                if let Ok(node_tree) = context.tree.try_read() {
-                    if let Some(parent_id) =
-                        find_not_empty_parent(&node_tree, all_styles_query, &id)
-                    {
-                        if let Some(parent_layout) = context.get_layout(&parent_id) {
-                            let border_x = if let Ok(style) = all_styles_query.get(parent_id.0) {
-                                let border = style.0.border.resolve();
-                                border.left + border.right
-                            } else {
-                                0.0
-                            };
-                            let border_y = if let Ok(style) = all_styles_query.get(parent_id.0) {
-                                let border = style.0.border.resolve();
-                                border.top + border.bottom
-                            } else {
-                                0.0
-                            };
-                            properties.max_size = (
-                                parent_layout.width - border_x,
-                                parent_layout.height - border_y,
-                            );
-
-                            // TODO: Fix this hack.
-                            if !*word_wrap {
-                                properties.max_size.0 = 100000.0;
-                            }
-
-                            needs_layout = false;
-
-                            if properties.max_size.0 == 0.0 || properties.max_size.1 == 0.0 {
-                                needs_layout = true;
-                            }
-
-                            if context.get_geometry_changed(&parent_id) {
-                                needs_layout = true;
-                            }
-
-                            if dirty.contains(parent_id.0) {
-                                needs_layout = true;
-                            }
-
-                            // --- Calculate Text Layout --- //
-                            *text_layout = font.measure(content, *properties);
-                            let measurement = text_layout.size();
-
-                            log::trace!(
-                                "Text Node: {}, has a measurement of: {:?}, it's parent takes up: {:?}",
-                                &content,
-                                measurement,
-                                properties.max_size
-                            );
-
-                            // --- Apply Layout --- //
-                            if matches!(styles.width, StyleProp::Default) {
-                                styles.width = StyleProp::Value(Units::Pixels(measurement.0));
-                            }
-                            if matches!(styles.height, StyleProp::Default) {
-                                styles.height = StyleProp::Value(Units::Pixels(measurement.1));
-                            }
-                        } else {
-                            log::trace!("no layout for: {:?}", parent_id.0.index());
-                        }
-                    } else {
-                        log::trace!("No parent found for: {:?}", id.0.index());
-                    }
+            // Outlines that are part of the bounds take up room on every side of the text
+            let outline_width = match (*outline_in_bounds, *outline) {
+                (true, Some((_, width))) => width.max(0.0),
+                _ => 0.0,
+            };
+            (
+                content,
+                font,
+                properties,
+                text_layout,
+                word_wrap,
+                outline_width,
+            )
+        }
+        _ => return None,
+    };
+
+    // --- Bind to Font Asset --- //
+    if let StyleProp::Value(fallbacks) = &styles.font_fallback {
+        if let Some(covering_font) = fonts.find_covering_font(content, font, fallbacks) {
+            *font = covering_font;
+        }
+    }
+    let font = fonts.font(font)?;
+    let node_tree = context.tree.try_read().ok()?;
+    let parent_id = match find_not_empty_parent(&node_tree, all_styles_query, &id) {
+        Some(parent_id) => parent_id,
+        None => {
+            log::trace!("No parent found for: {:?}", id.0.index());
+            return None;
+        }
+    };
+    let parent_layout = match context.get_layout(&parent_id) {
+        Some(parent_layout) => parent_layout,
+        None => {
+            log::trace!("no layout for: {:?}", parent_id.0.index());
+            return None;
+        }
+    };
+
+    let (border_x, border_y) = if let Ok(style) = all_styles_query.get(parent_id.0) {
+        let border = style.0.border.resolve();
+        (border.left + border.right, border.top + border.bottom)
+    } else {
+        (0.0, 0.0)
+    };
+    // Parents sized to their content don't have a size of their own yet
+    let ancestors = UpwardIterator::new(&node_tree, Some(parent_id), true).map(|ancestor| {
+        let (width, height) = all_styles_query
+            .get(ancestor.0)
+            .map(|style| (style.0.width.clone(), style.0.height.clone()))
+            .unwrap_or_default();
+        let layout = if ancestor == parent_id {
+            Some(parent_layout)
+        } else {
+            context.get_layout(&ancestor)
+        };
+        (width, height, layout)
+    });
+    let (bounds_width, bounds_height) = measure_bounds(ancestors);
+    let available_width = (text_measure_width(&styles.max_width, bounds_width - border_x)
+        - outline_width * 2.0)
+        .max(0.0);
+    properties.max_size = (
+        available_width,
+        bounds_height - border_y - outline_width * 2.0,
+    );
+
+    // TODO: Fix this hack.
+    if !*word_wrap {
+        properties.max_size.0 = 100000.0;
+    }
+
+    // --- Calculate Text Layout --- //
+    if let StyleProp::Value(TextWrap::Truncate { ellipsis }) = styles.text_wrap {
+        *content = truncate_text(font, content, *properties, available_width, ellipsis);
+        // The truncated content always fits on a single line
+        properties.max_size.0 = available_width;
+    }
+    if let StyleProp::Value(max_lines) = styles.text_max_lines {
+        *content = clamp_lines(font, content, *properties, max_lines);
+    }
+    *text_layout = font.measure(content, *properties);
+    let mut measurement = text_layout.size();
+    measurement.0 += outline_width * 2.0;
+    measurement.1 += outline_width * 2.0;
+    if context.pixel_snapping {
+        measurement = (measurement.0.round(), measurement.1.round());
+    }
+
+    log::trace!(
+        "Text Node: {}, has a measurement of: {:?}, it's parent takes up: {:?}",
+        &content,
+        measurement,
+        properties.max_size
+    );
+
+    Some((parent_id, measurement))
+}
+
+/// Returns the smallest size text can be laid out at without overflowing
+///
+/// Wrapped and truncated text fit any width, so only text kept on a single line has one. As that
+/// text is measured without a width limit, its measurement is already the size it needs.
+fn intrinsic_text_size(
+    styles: &KStyle,
+    render_primitive: &RenderPrimitive,
+    measurement: Option<(WrappedIndex, (f32, f32))>,
+) -> Option<(f32, f32)> {
+    match (render_primitive, &styles.text_wrap) {
+        (_, StyleProp::Value(TextWrap::Truncate { .. })) => None,
+        (
+            RenderPrimitive::Text {
+                word_wrap: false, ..
+            },
+            _,
+        ) => measurement.map(|(_, size)| size),
+        _ => None,
+    }
+}
+
+/// Raises the minimum size of a widget to at least its intrinsic size
+///
+/// Minimums relative to the parent can't be compared until layout, so they're left alone.
+fn apply_intrinsic_size(styles: &mut KStyle, intrinsic_size: Option<(f32, f32)>) {
+    let (width, height) = match intrinsic_size {
+        Some(intrinsic_size) => intrinsic_size,
+        None => return,
+    };
+    for (min, size) in [
+        (&mut styles.min_width, width),
+        (&mut styles.min_height, height),
+    ] {
+        match min {
+            StyleProp::Value(Units::Pixels(min)) => *min = min.max(size),
+            StyleProp::Default | StyleProp::Unset => *min = StyleProp::Value(Units::Pixels(size)),
+            _ => {}
+        }
+    }
+}
+
+/// Resolves the styles of a widget the same way they're resolved when building its node
+///
+/// The pseudo styles are layered over the widget's own styles, any `initial` and `inherit`
+/// properties are filled in, and relative sizes are converted to pixels where they can be.
+/// Returns false if a calculated size depends on a layout that isn't known yet.
+pub(crate) fn resolve_styles(
+    context: &KayakRootContext,
+    id: WrappedIndex,
+    raw_styles: &KStyle,
+    pseudo_styles: Option<&PseudoStyles>,
+    parent: Option<WrappedIndex>,
+    parent_styles: &KStyle,
+) -> (KStyle, bool) {
+    let mut styles = match pseudo_styles {
+        Some(pseudo_styles) => pseudo_styles.layer(raw_styles, context.pseudo_state(id.0)),
+        None => raw_styles.clone(),
+    };
+    // Fill in all `initial` values for any unset property
+    styles.apply(&KStyle::initial());
+    // Reset any `initial` property to the default styles, before it could be inherited
+    styles.reset(&context.default_styles);
+    // Fill in all `inherited` values for any `inherit` property
+    styles.inherit(parent_styles);
+    apply_font_size_em(&mut styles, parent_styles);
+    apply_rem(&mut styles, context.root_font_size);
+    apply_viewport(&mut styles, context.viewport_size);
+    let parent_layout = match parent {
+        Some(parent) => context.get_layout(&parent),
+        // The root has nothing to be relative to
+        None => Some(Rect::default()),
+    };
+    let has_calc_size = apply_calc(&mut styles, parent_layout, context.get_layout(&id));
+    (styles, has_calc_size)
+}
+
+/// Resolves an `em` font size against the font size of the parent
+fn apply_font_size_em(styles: &mut KStyle, parent_styles: &KStyle) {
+    if let StyleProp::Value(em) = styles.font_size_em {
+        let parent_font_size = parent_styles
+            .font_size
+            .resolve_or(crate::DEFAULT_ROOT_FONT_SIZE);
+        styles.font_size = StyleProp::Value(parent_font_size * em);
+    }
+}
+
+/// Converts every size given in [`Units::Rem`] to pixels, using the given root font size
+fn apply_rem(styles: &mut KStyle, root_font_size: f32) {
+    if let StyleProp::Value(rem) = styles.font_size_rem {
+        styles.font_size = StyleProp::Value(rem * root_font_size);
+    }
+
+    resolve_units(styles, |units| units.resolve_rem(root_font_size));
+}
+
+/// Converts every size given in [`Units::ViewportWidth`] or [`Units::ViewportHeight`] to pixels,
+/// using the given viewport size
+///
+/// Until the viewport size is known they're left alone, so they're relative to the parent instead.
+fn apply_viewport(styles: &mut KStyle, viewport_size: (f32, f32)) {
+    if viewport_size == (0.0, 0.0) {
+        return;
+    }
+    resolve_units(styles, |units| units.resolve_viewport(viewport_size));
+}
+
+/// Replaces every size and spacing in the styles with the result of `resolve`
+fn resolve_units(styles: &mut KStyle, resolve: impl Fn(Units) -> Units) {
+    for units in [
+        &mut styles.bottom,
+        &mut styles.col_between,
+        &mut styles.height,
+        &mut styles.left,
+        &mut styles.max_height,
+        &mut styles.max_width,
+        &mut styles.min_height,
+        &mut styles.min_width,
+        &mut styles.padding_bottom,
+        &mut styles.padding_left,
+        &mut styles.padding_right,
+        &mut styles.padding_top,
+        &mut styles.right,
+        &mut styles.row_between,
+        &mut styles.top,
+        &mut styles.width,
+    ] {
+        if let StyleProp::Value(value) = units {
+            *value = resolve(*value);
+        }
+    }
+
+    for edge in [&mut styles.offset, &mut styles.padding] {
+        if let StyleProp::Value(edge) = edge {
+            edge.top = resolve(edge.top);
+            edge.right = resolve(edge.right);
+            edge.bottom = resolve(edge.bottom);
+            edge.left = resolve(edge.left);
+        }
+    }
+
+    for tracks in [&mut styles.grid_rows, &mut styles.grid_cols] {
+        if let StyleProp::Value(tracks) = tracks {
+            for track in tracks.iter_mut() {
+                *track = resolve(*track);
+            }
+        }
+    }
+}
+
+/// Converts every size given in [`Units::Calc`] to pixels, using the layout of the parent
+///
+/// Grid tracks divide up the widget itself, so they're converted using its own layout instead.
+/// Returns false if there are calculated sizes but the layout they depend on isn't known yet, in
+/// which case those sizes fall back to just their percentage until the next layout.
+fn apply_calc(styles: &mut KStyle, parent_layout: Option<Rect>, layout: Option<Rect>) -> bool {
+    let horizontal = [
+        &mut styles.col_between,
+        &mut styles.left,
+        &mut styles.max_width,
+        &mut styles.min_width,
+        &mut styles.padding_left,
+        &mut styles.padding_right,
+        &mut styles.right,
+        &mut styles.width,
+    ];
+    let vertical = [
+        &mut styles.bottom,
+        &mut styles.height,
+        &mut styles.max_height,
+        &mut styles.min_height,
+        &mut styles.padding_bottom,
+        &mut styles.padding_top,
+        &mut styles.row_between,
+        &mut styles.top,
+    ];
+    let mut resolved = true;
+    let mut resolve = |units: &mut Units, size: Option<f32>| {
+        if let Units::Calc(calc) = *units {
+            match size {
+                Some(size) => *units = units.resolve_calc(size),
+                None => {
+                    *units = Units::Percentage(calc.percentage);
+                    resolved = false;
                 }
             }
         }
-        _ => {
-            needs_layout = false;
+    };
+
+    let (parent_width, parent_height) = (
+        parent_layout.map(|layout| layout.width),
+        parent_layout.map(|layout| layout.height),
+    );
+    for units in horizontal {
+        if let StyleProp::Value(units) = units {
+            resolve(units, parent_width);
+        }
+    }
+    for units in vertical {
+        if let StyleProp::Value(units) = units {
+            resolve(units, parent_height);
+        }
+    }
+    for edge in [&mut styles.offset, &mut styles.padding] {
+        if let StyleProp::Value(edge) = edge {
+            resolve(&mut edge.top, parent_height);
+            resolve(&mut edge.right, parent_width);
+            resolve(&mut edge.bottom, parent_height);
+            resolve(&mut edge.left, parent_width);
         }
     }
 
-    if needs_layout {
-        commands.entity(id.0).insert(DirtyNode);
+    let (width, height) = (
+        layout.map(|layout| layout.width),
+        layout.map(|layout| layout.height),
+    );
+    for (tracks, size) in [
+        (&mut styles.grid_cols, width),
+        (&mut styles.grid_rows, height),
+    ] {
+        if let StyleProp::Value(tracks) = tracks {
+            for units in tracks.iter_mut() {
+                resolve(units, size);
+            }
+        }
+    }
+
+    resolved
+}
+
+/// Replaces the size and position of the given styles with a layout relative to the parent
+fn apply_frozen_layout(styles: &mut KStyle, layout: &Rect) {
+    styles.position_type = StyleProp::Value(KPositionType::SelfDirected);
+    styles.width = StyleProp::Value(Units::Pixels(layout.width));
+    styles.height = StyleProp::Value(Units::Pixels(layout.height));
+    styles.left = StyleProp::Value(Units::Pixels(layout.posx));
+    styles.top = StyleProp::Value(Units::Pixels(layout.posy));
+    styles.right = StyleProp::Value(Units::Auto);
+    styles.bottom = StyleProp::Value(Units::Auto);
+}
+
+/// Derives an unset width or height from the aspect ratio of the given styles
+///
+/// Without a width or height, the node fills the width of its parent within its padding.
+/// Returns false if the size depends on the width of the parent, which isn't known yet.
+fn apply_aspect_ratio(styles: &mut KStyle, parent_width: Option<f32>, parent_padding: f32) -> bool {
+    let ratio = match styles.aspect_ratio {
+        StyleProp::Value(ratio) if ratio > 0.0 => ratio,
+        _ => return true,
+    };
+
+    match (&styles.width, &styles.height) {
+        (StyleProp::Value(width), StyleProp::Default) => {
+            let width = match width {
+                Units::Pixels(width) => *width,
+                Units::Percentage(percentage) => match parent_width {
+                    Some(parent_width) => parent_width * percentage / 100.0,
+                    None => return false,
+                },
+                // Stretched sizes aren't known until layout
+                _ => return true,
+            };
+            styles.height = StyleProp::Value(Units::Pixels(width / ratio));
+        }
+        (StyleProp::Default, StyleProp::Value(Units::Pixels(height))) => {
+            styles.width = StyleProp::Value(Units::Pixels(height * ratio));
+        }
+        (StyleProp::Default, StyleProp::Default) => match parent_width {
+            Some(parent_width) => {
+                let width = (parent_width - parent_padding).max(0.0);
+                styles.width = StyleProp::Value(Units::Pixels(width));
+                styles.height = StyleProp::Value(Units::Pixels(width / ratio));
+            }
+            None => return false,
+        },
+        _ => {}
     }
 
-    // If we have data from the previous frame no need to do anything here!
-    // if matches!(prev_styles.width, StyleProp::Value(..)) {
-    //     styles.width = prev_styles.width;
-    //     styles.height = prev_styles.height;
-    //     needs_layout = false;
-    // }
+    true
+}
 
-    (render_primitive, needs_layout)
+/// Returns the sum of the left and right padding of the given styles, if they're in pixels
+fn horizontal_padding(styles: &KStyle) -> f32 {
+    let padding = match &styles.padding {
+        StyleProp::Value(padding) => Some(*padding),
+        _ => None,
+    };
+    let pixels = |side: &StyleProp<Units>, fallback: Option<Units>| match (side, fallback) {
+        (StyleProp::Value(Units::Pixels(value)), _) => *value,
+        (StyleProp::Default, Some(Units::Pixels(value))) => value,
+        _ => 0.0,
+    };
+    pixels(&styles.padding_left, padding.map(|padding| padding.left))
+        + pixels(&styles.padding_right, padding.map(|padding| padding.right))
+}
+
+/// Returns the size text should be measured within, given its ancestors from the closest up
+///
+/// Containers with a size of [`Units::Auto`] are sized to fit their content, so they don't have
+/// a size of their own until their children are measured. For these the size of the closest
+/// ancestor that isn't sized by its content is used instead. This makes shrink-to-fit take two
+/// passes: the text is first measured against that ancestor and given a fixed size, then the
+/// layout pass sizes the container around it (along with the container's padding).
+fn measure_bounds(
+    ancestors: impl Iterator<Item = (StyleProp<Units>, StyleProp<Units>, Option<Rect>)>,
+) -> (f32, f32) {
+    let mut width = None;
+    let mut height = None;
+    for (width_style, height_style, layout) in ancestors {
+        let layout = match layout {
+            Some(layout) => layout,
+            None => continue,
+        };
+        if width.is_none() && !matches!(width_style, StyleProp::Value(Units::Auto)) {
+            width = Some(layout.width);
+        }
+        if height.is_none() && !matches!(height_style, StyleProp::Value(Units::Auto)) {
+            height = Some(layout.height);
+        }
+        if width.is_some() && height.is_some() {
+            break;
+        }
+    }
+    (width.unwrap_or_default(), height.unwrap_or_default())
+}
+
+/// Returns the width text should be measured (and wrapped) at
+///
+/// This is the width available within the parent, limited by the text's own `max_width`.
+fn text_measure_width(max_width: &StyleProp<Units>, available_width: f32) -> f32 {
+    match max_width {
+        StyleProp::Value(Units::Pixels(max_width)) => available_width.min(*max_width),
+        StyleProp::Value(Units::Percentage(percentage)) => {
+            available_width.min(available_width * percentage / 100.0)
+        }
+        _ => available_width,
+    }
+}
+
+/// Cuts the given content down to what fits on a single line of the given width
+///
+/// If `ellipsis` is true, cut off content ends with an ellipsis ("…", or "..." if the font
+/// doesn't have it).
+fn truncate_text(
+    font: &KayakFont,
+    content: &str,
+    properties: TextProperties,
+    max_width: f32,
+    ellipsis: bool,
+) -> String {
+    let properties = TextProperties {
+        max_size: (f32::MAX, f32::MAX),
+        alignment: Alignment::Start,
+        ..properties
+    };
+    let layout = font.measure(content, properties);
+    if layout.size().0 <= max_width && layout.lines().len() <= 1 {
+        return content.to_string();
+    }
+
+    let ellipsis = ellipsis.then(|| ellipsis_for(font));
+    let ellipsis_width = ellipsis
+        .map(|ellipsis| font.measure(ellipsis, properties).size().0)
+        .unwrap_or_default();
+    let limit = max_width - ellipsis_width;
+
+    let char_count = layout
+        .lines()
+        .first()
+        .and_then(|line| {
+            line.graphemes()
+                .iter()
+                .take_while(|grapheme| grapheme.position.0 + grapheme.size.0 <= limit)
+                .last()
+        })
+        .map(|grapheme| grapheme.char_index + grapheme.char_total)
+        .unwrap_or_default();
+
+    let mut truncated = content
+        .chars()
+        .take(char_count)
+        .collect::<String>()
+        .trim_end()
+        .to_string();
+    if let Some(ellipsis) = ellipsis {
+        truncated.push_str(ellipsis);
+    }
+    truncated
+}
+
+/// Cuts the given content down to what wraps onto at most `max_lines` lines
+///
+/// Cut off content ends with an ellipsis ("…", or "..." if the font doesn't have it) on the last
+/// line that is kept.
+fn clamp_lines(
+    font: &KayakFont,
+    content: &str,
+    properties: TextProperties,
+    max_lines: usize,
+) -> String {
+    let properties = TextProperties {
+        alignment: Alignment::Start,
+        ..properties
+    };
+    let layout = font.measure(content, properties);
+    if layout.lines().len() <= max_lines {
+        return content.to_string();
+    }
+    let last_line = match max_lines.checked_sub(1).map(|index| &layout.lines()[index]) {
+        Some(last_line) => last_line,
+        None => return String::new(),
+    };
+
+    let ellipsis = ellipsis_for(font);
+    let limit = properties.max_size.0 - font.measure(ellipsis, properties).size().0;
+    let char_count = last_line
+        .graphemes()
+        .iter()
+        .take_while(|grapheme| grapheme.position.0 + grapheme.size.0 <= limit)
+        .last()
+        .map(|grapheme| grapheme.char_index + grapheme.char_total)
+        .unwrap_or_else(|| last_line.char_index());
+
+    let mut clamped = content
+        .chars()
+        .take(char_count)
+        .collect::<String>()
+        .trim_end()
+        .to_string();
+    clamped.push_str(ellipsis);
+    clamped
 }
 
 pub fn find_not_empty_parent(
@@ -322,105 +1172,619 @@ pub fn find_not_empty_parent(
     all_styles_query: &Query<&ComputedStyles>,
     node: &WrappedIndex,
 ) -> Option<WrappedIndex> {
-    if let Some(parent) = tree.parent(*node) {
+    // The upward iterator stops (and logs an error) if the parents form a cycle.
+    UpwardIterator::new(tree, Some(*node), false).find(|parent| {
         if let Ok(styles) = all_styles_query.get(parent.0) {
-            if matches!(styles.0.render_command.resolve(), RenderCommand::Empty)
-                || matches!(styles.0.render_command.resolve(), RenderCommand::Layout)
-            {
-                find_not_empty_parent(tree, all_styles_query, &parent)
-            } else {
-                Some(parent)
-            }
+            !matches!(
+                styles.0.render_command.resolve(),
+                RenderCommand::Empty | RenderCommand::Layout
+            )
         } else {
-            find_not_empty_parent(tree, all_styles_query, &parent)
+            false
         }
-    } else {
-        None
-    }
+    })
 }
 
-// pub fn build_nodes_tree(context: &mut Context, tree: &Tree, node_query: &Query<(Entity, &Node)>) {
-//     if tree.root_node.is_none() {
-//         return;
-//     }
-//     let mut node_tree = Tree::default();
-//     node_tree.root_node = tree.root_node;
-//     node_tree.children.insert(
-//         tree.root_node.unwrap(),
-//         get_valid_node_children(&tree, &node_query, tree.root_node.unwrap()),
-//     );
-
-//     // let old_focus = self.focus_tree.current();
-//     // self.focus_tree.clear();
-//     // self.focus_tree.add(root_node_id, &self.tree);
-
-//     for (node_id, node) in node_query.iter() {
-//         let node_id = WrappedIndex(node_id);
-//         if let Some(widget_styles) = node.raw_styles.as_ref() {
-//             // Only add widgets who have renderable nodes.
-//             // if widget_styles.render_command.resolve() != RenderCommand::Empty {
-//                 let valid_children = get_valid_node_children(&tree, &node_query, node_id);
-//                 node_tree.children.insert(node_id, valid_children);
-//                 let valid_parent = get_valid_parent(&tree, &node_query, node_id);
-//                 if let Some(valid_parent) = valid_parent {
-//                     node_tree.parents.insert(node_id, valid_parent);
-//                 }
-//             // }
-//         }
-
-//         // let focusable = self.get_focusable(widget_id).unwrap_or_default();
-//         // if focusable {
-//         //     self.focus_tree.add(widget_id, &self.tree);
-//         // }
-//     }
-
-//     // if let Some(old_focus) = old_focus {
-//     //     if self.focus_tree.contains(old_focus) {
-//     //         self.focus_tree.focus(old_focus);
-//     //     }
-//     // }
-
-//     // dbg!(&node_tree);
-
-//     // context.node_tree = node_tree;
-// }
-
-// pub fn get_valid_node_children(
-//     tree: &Tree,
-//     query: &Query<(Entity, &Node)>,
-//     node_id: WrappedIndex,
-// ) -> Vec<WrappedIndex> {
-//     let mut children = Vec::new();
-//     if let Some(node_children) = tree.children.get(&node_id) {
-//         for child_id in node_children {
-//             if let Ok((_, _child_node)) = query.get(child_id.0) {
-//                 // if child_node.resolved_styles.render_command.resolve() != RenderCommand::Empty {
-//                     children.push(*child_id);
-//                 // } else {
-//                     // children.extend(get_valid_node_children(tree, query, *child_id));
-//                 // }
-//             } else {
-//                 // children.extend(get_valid_node_children(tree, query, *child_id));
-//             }
-//         }
-//     }
-
-//     children
-// }
-
-// pub fn get_valid_parent(
-//     tree: &Tree,
-//     query: &Query<(Entity, &Node)>,
-//     node_id: WrappedIndex,
-// ) -> Option<WrappedIndex> {
-//     if let Some(parent_id) = tree.parents.get(&node_id) {
-//         if let Ok((_, parent_node)) = query.get(parent_id.0) {
-//             // if parent_node.resolved_styles.render_command.resolve() != RenderCommand::Empty {
-//                 return Some(*parent_id);
-//             // }
-//         }
-//         // return get_valid_parent(tree, query, *parent_id);
-//     }
-
-//     None
-// }
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use bevy::prelude::{Color, Entity, Handle};
+    use kayak_font::{Alignment, ImageType, KayakFont, Sdf, TextLayout, TextProperties};
+
+    use super::{
+        apply_aspect_ratio, apply_calc, apply_font_size_em, apply_rem, apply_viewport, clamp_lines,
+        ellipsis_for, for_each_in_batches, horizontal_padding, measure_bounds, take_within_budget,
+        text_measure_width, truncate_text, ASCII_ELLIPSIS, ELLIPSIS, MEASURE_BATCH_SIZE,
+    };
+    use crate::{
+        layout::Rect,
+        node::{DirtyNode, Node, WrappedIndex},
+        render_primitive::RenderPrimitive,
+        styles::{Calc, Edge, KStyle, LineHeight, RenderCommand, StyleProp, Units},
+        test_utils::{TestTree, TreeBuilder},
+        tree::Tree,
+    };
+
+    fn roboto() -> KayakFont {
+        let sdf = Sdf::from_bytes(include_bytes!("../assets/roboto.kayak_font"));
+        KayakFont::new(sdf, ImageType::Atlas(Handle::default()))
+    }
+
+    #[test]
+    fn text_should_wrap_at_max_width() {
+        let font = roboto();
+        let content = "The quick brown fox jumps over the lazy dog";
+
+        let unbounded = font.measure(
+            content,
+            TextProperties {
+                max_size: (text_measure_width(&StyleProp::Default, 1000.0), 1000.0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(1, unbounded.lines().len());
+        assert!(unbounded.size().0 > 100.0);
+
+        let max_width = StyleProp::Value(Units::Pixels(100.0));
+        let bounded = font.measure(
+            content,
+            TextProperties {
+                max_size: (text_measure_width(&max_width, 1000.0), 1000.0),
+                ..Default::default()
+            },
+        );
+        assert!(bounded.lines().len() > 1);
+        assert!(bounded.size().0 <= 100.0);
+    }
+
+    #[test]
+    fn centered_text_should_keep_its_width() {
+        let font = roboto();
+        let measure = |alignment| {
+            font.measure(
+                "Label",
+                TextProperties {
+                    max_size: (200.0, 100.0),
+                    alignment,
+                    ..Default::default()
+                },
+            )
+        };
+
+        let start = measure(Alignment::Start);
+        let centered = measure(Alignment::Middle);
+        assert_eq!(start.size(), centered.size());
+        assert!(centered.size().0 < 200.0);
+
+        let shift = (200.0 - start.size().0) / 2.0;
+        let offset = centered.glyphs()[0].position.0 - start.glyphs()[0].position.0;
+        assert!((offset - shift).abs() < 0.001);
+    }
+
+    #[test]
+    fn justified_text_should_fill_max_width() {
+        let font = roboto();
+        let layout = font.measure(
+            "The quick brown fox jumps over the lazy dog",
+            TextProperties {
+                max_size: (150.0, 1000.0),
+                alignment: Alignment::Justify,
+                ..Default::default()
+            },
+        );
+        assert!(layout.lines().len() > 1);
+        assert_eq!(150.0, layout.size().0);
+    }
+
+    #[test]
+    fn truncated_text_should_fit_on_one_line() {
+        let font = roboto();
+        let content = "The quick brown fox jumps over the lazy dog";
+
+        let truncated = truncate_text(&font, content, TextProperties::default(), 100.0, true);
+        // Roboto has no glyph for "…"
+        assert!(!font.covers(ELLIPSIS));
+        assert_eq!(ASCII_ELLIPSIS, ellipsis_for(&font));
+        assert!(truncated.ends_with(ASCII_ELLIPSIS));
+        assert!(font.covers(&truncated));
+        assert!(truncated.len() < content.len());
+
+        let layout = font.measure(
+            &truncated,
+            TextProperties {
+                max_size: (100.0, 1000.0),
+                ..Default::default()
+            },
+        );
+        assert_eq!(1, layout.lines().len());
+        assert!(layout.size().0 <= 100.0);
+    }
+
+    #[test]
+    fn clamped_text_should_keep_max_lines() {
+        let font = roboto();
+        let content = "The quick brown fox jumps over the lazy dog while the cat sleeps in the sun";
+        let properties = TextProperties {
+            max_size: (100.0, 1000.0),
+            ..Default::default()
+        };
+        let full = font.measure(content, properties);
+        assert!(full.lines().len() >= 5);
+
+        let clamped = clamp_lines(&font, content, properties, 2);
+        assert!(clamped.ends_with(ASCII_ELLIPSIS));
+        assert!(font.covers(&clamped));
+        let layout = font.measure(&clamped, properties);
+        assert_eq!(2, layout.lines().len());
+        assert_eq!(2.0 * properties.line_height, layout.size().1);
+
+        // Text that already fits is left alone
+        assert_eq!(content, clamp_lines(&font, content, properties, 10));
+    }
+
+    #[test]
+    fn line_height_multiple_should_scale_with_font_size() {
+        let font = roboto();
+        let style = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Hello\nworld"),
+                alignment: Alignment::Start,
+                word_wrap: true,
+                subpixel: false,
+            }),
+            font_size: StyleProp::Value(20.0),
+            line_height: StyleProp::Value(LineHeight::Multiple(1.5)),
+            ..Default::default()
+        };
+
+        let (content, properties) = match RenderPrimitive::from(&style) {
+            RenderPrimitive::Text {
+                content,
+                properties,
+                ..
+            } => (content, properties),
+            primitive => panic!("Expected a text primitive, got: {}", primitive.to_string()),
+        };
+        assert_eq!(30.0, properties.line_height);
+        let layout = font.measure(&content, properties);
+        assert_eq!(2, layout.lines().len());
+        assert_eq!(60.0, layout.size().1);
+    }
+
+    #[test]
+    fn text_that_fits_should_not_be_truncated() {
+        let font = roboto();
+        let truncated = truncate_text(&font, "Label", TextProperties::default(), 200.0, true);
+        assert_eq!("Label", truncated);
+    }
+
+    #[test]
+    fn aspect_ratio_should_derive_height_from_parent() {
+        let mut styles = KStyle {
+            aspect_ratio: StyleProp::Value(16.0 / 9.0),
+            ..KStyle::initial()
+        };
+
+        assert!(!apply_aspect_ratio(&mut styles.clone(), None, 0.0));
+        assert!(apply_aspect_ratio(&mut styles, Some(320.0), 0.0));
+        assert_eq!(StyleProp::Value(Units::Pixels(320.0)), styles.width);
+        match styles.height {
+            StyleProp::Value(Units::Pixels(height)) => assert!((height - 180.0).abs() < 0.001),
+            height => panic!("Expected a height in pixels, got: {:?}", height),
+        }
+    }
+
+    #[test]
+    fn aspect_ratio_should_derive_width_from_height() {
+        let mut styles = KStyle {
+            aspect_ratio: StyleProp::Value(2.0),
+            height: StyleProp::Value(Units::Pixels(50.0)),
+            ..KStyle::initial()
+        };
+
+        assert!(apply_aspect_ratio(&mut styles, None, 0.0));
+        assert_eq!(StyleProp::Value(Units::Pixels(100.0)), styles.width);
+    }
+
+    #[test]
+    fn aspect_ratio_should_fill_the_parent_within_its_padding() {
+        let parent = KStyle {
+            padding: StyleProp::Value(Edge::all(Units::Pixels(10.0))),
+            padding_right: StyleProp::Value(Units::Pixels(30.0)),
+            ..KStyle::initial()
+        };
+        let mut styles = KStyle {
+            aspect_ratio: StyleProp::Value(2.0),
+            ..KStyle::initial()
+        };
+
+        assert_eq!(40.0, horizontal_padding(&parent));
+        assert!(apply_aspect_ratio(
+            &mut styles,
+            Some(240.0),
+            horizontal_padding(&parent)
+        ));
+        assert_eq!(StyleProp::Value(Units::Pixels(200.0)), styles.width);
+        assert_eq!(StyleProp::Value(Units::Pixels(100.0)), styles.height);
+    }
+
+    #[test]
+    fn aspect_ratio_roots_should_not_stay_dirty() {
+        let mut tree = TreeBuilder::new()
+            .node(
+                "root",
+                KStyle {
+                    aspect_ratio: StyleProp::Value(2.0),
+                    ..KStyle::initial()
+                },
+            )
+            .build();
+
+        // The window size isn't known, so the root is left for layout to size
+        let root = tree.entity("root");
+        tree.world.entity_mut(root).insert(DirtyNode);
+        tree.calculate_nodes();
+        assert!(tree.world.get::<DirtyNode>(root).is_none());
+
+        tree.context.set_viewport_size((400.0, 300.0));
+        tree.calculate_nodes();
+        let node = tree.world.get::<Node>(root).unwrap();
+        assert_eq!(
+            StyleProp::Value(Units::Pixels(400.0)),
+            node.resolved_styles.width
+        );
+        assert_eq!(
+            StyleProp::Value(Units::Pixels(200.0)),
+            node.resolved_styles.height
+        );
+    }
+
+    #[test]
+    fn em_font_size_should_scale_parent_font_size() {
+        let font = roboto();
+        let parent = KStyle {
+            font_size: StyleProp::Value(20.0),
+            ..Default::default()
+        };
+        let mut label = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Label"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            font_size: StyleProp::Inherit,
+            font_size_em: StyleProp::Value(0.5),
+            ..Default::default()
+        };
+        label.inherit(&parent);
+        apply_font_size_em(&mut label, &parent);
+        assert_eq!(StyleProp::Value(10.0), label.font_size);
+
+        let properties = match RenderPrimitive::from(&label) {
+            RenderPrimitive::Text { properties, .. } => properties,
+            primitive => panic!("Expected a text primitive, got: {}", primitive.to_string()),
+        };
+        assert_eq!(10.0, properties.font_size);
+
+        let measure = |font_size| {
+            font.measure(
+                "Label",
+                TextProperties {
+                    font_size,
+                    line_height: font_size * 1.2,
+                    max_size: (1000.0, 1000.0),
+                    ..Default::default()
+                },
+            )
+            .size()
+        };
+        assert_eq!(measure(10.0), font.measure("Label", properties).size());
+        assert!(measure(10.0).0 < measure(20.0).0);
+    }
+
+    #[test]
+    fn text_in_auto_sized_button_should_measure_against_ancestor() {
+        let font = roboto();
+        let layout = |width, height| {
+            Some(Rect {
+                width,
+                height,
+                ..Default::default()
+            })
+        };
+
+        // A button sized to its content hasn't been laid out yet, while its panel has
+        let button = (
+            StyleProp::Value(Units::Auto),
+            StyleProp::Value(Units::Auto),
+            layout(0.0, 0.0),
+        );
+        let panel = (StyleProp::Default, StyleProp::Default, layout(400.0, 300.0));
+        let bounds = measure_bounds(vec![button, panel].into_iter());
+        assert_eq!((400.0, 300.0), bounds);
+
+        let label = font.measure(
+            "Click me",
+            TextProperties {
+                max_size: bounds,
+                ..Default::default()
+            },
+        );
+        assert_eq!(1, label.lines().len());
+        assert!(label.size().0 > 0.0 && label.size().0 < 400.0);
+
+        // Fixed size parents are measured against directly
+        let fixed = (
+            StyleProp::Value(Units::Pixels(120.0)),
+            StyleProp::Value(Units::Auto),
+            layout(120.0, 0.0),
+        );
+        let panel = (StyleProp::Default, StyleProp::Default, layout(400.0, 300.0));
+        assert_eq!(
+            (120.0, 300.0),
+            measure_bounds(vec![fixed, panel].into_iter())
+        );
+    }
+
+    #[test]
+    fn budget_should_take_parents_first() {
+        let entity = |index: u32| Entity::from_raw(index);
+        let mut tree = Tree::default();
+        tree.add(WrappedIndex(entity(0)), None);
+        tree.add(WrappedIndex(entity(1)), Some(WrappedIndex(entity(0))));
+        tree.add(WrappedIndex(entity(2)), Some(WrappedIndex(entity(1))));
+        tree.add(WrappedIndex(entity(3)), Some(WrappedIndex(entity(0))));
+
+        let dirty = vec![entity(3), entity(2), entity(1), entity(0)];
+        let (now, later) = take_within_budget(&tree, dirty.clone(), 2);
+        assert_eq!(vec![entity(0), entity(1)], now);
+        assert_eq!(vec![entity(2), entity(3)], later);
+
+        // Everything fits, so nothing is reordered or deferred
+        let (now, later) = take_within_budget(&tree, dirty.clone(), 4);
+        assert_eq!(dirty, now);
+        assert!(later.is_empty());
+    }
+
+    #[test]
+    fn budget_should_be_shared_by_every_pass_of_a_frame() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("panel", KStyle::initial(), |panel| {
+                panel.leaf("label", KStyle::initial())
+            })
+            .leaf("footer", KStyle::initial())
+            .build();
+        tree.context.set_node_budget(Some(2));
+        let rebuilt = |tree: &TestTree| tree.context.last_stats().nodes_rebuilt;
+
+        tree.calculate_nodes();
+        assert_eq!(2, rebuilt(&tree));
+        assert!(tree.context.has_pending_nodes());
+
+        // Later passes of the same frame have no budget left
+        tree.calculate_nodes();
+        assert_eq!(2, rebuilt(&tree));
+        assert!(tree.context.has_pending_nodes());
+
+        // The next frame gets a new budget
+        tree.context.budget_used = 0;
+        tree.calculate_nodes();
+        assert_eq!(4, rebuilt(&tree));
+    }
+
+    #[test]
+    fn rem_should_resolve_against_root_font_size() {
+        let mut styles = KStyle {
+            font_size_rem: StyleProp::Value(1.5),
+            width: StyleProp::Value(Units::Rem(10.0)),
+            height: StyleProp::Value(Units::Percentage(50.0)),
+            padding: StyleProp::Value(Edge::all(Units::Rem(0.5))),
+            padding_left: StyleProp::Value(Units::Rem(2.0)),
+            ..Default::default()
+        };
+        apply_rem(&mut styles, 20.0);
+
+        assert_eq!(StyleProp::Value(30.0), styles.font_size);
+        assert_eq!(StyleProp::Value(Units::Pixels(200.0)), styles.width);
+        assert_eq!(StyleProp::Value(Units::Percentage(50.0)), styles.height);
+        assert_eq!(
+            StyleProp::Value(Edge::all(Units::Pixels(10.0))),
+            styles.padding
+        );
+        assert_eq!(StyleProp::Value(Units::Pixels(40.0)), styles.padding_left);
+    }
+
+    #[test]
+    fn viewport_units_should_resolve_against_viewport_size() {
+        let mut styles = KStyle {
+            width: StyleProp::Value(Units::ViewportWidth(50.0)),
+            height: StyleProp::Value(Units::ViewportHeight(100.0)),
+            padding: StyleProp::Value(Edge::all(Units::ViewportWidth(25.0))),
+            left: StyleProp::Value(Units::Percentage(50.0)),
+            ..Default::default()
+        };
+        let unresolved = styles.clone();
+        apply_viewport(&mut styles, (0.0, 0.0));
+        assert_eq!(unresolved, styles);
+
+        apply_viewport(&mut styles, (1280.0, 720.0));
+
+        assert_eq!(StyleProp::Value(Units::Pixels(640.0)), styles.width);
+        assert_eq!(StyleProp::Value(Units::Pixels(720.0)), styles.height);
+        assert_eq!(
+            StyleProp::Value(Edge::all(Units::Pixels(320.0))),
+            styles.padding
+        );
+        assert_eq!(StyleProp::Value(Units::Percentage(50.0)), styles.left);
+    }
+
+    fn labels(count: usize) -> Vec<(String, Option<TextLayout>)> {
+        (0..count)
+            .map(|i| (format!("Label number {} of the list", i), None))
+            .collect()
+    }
+
+    #[test]
+    fn batches_should_measure_every_label() {
+        let font = roboto();
+        let mut labels = labels(MEASURE_BATCH_SIZE * 3 + 1);
+        for_each_in_batches(&mut labels, |(content, layout)| {
+            *layout = Some(font.measure(content, TextProperties::default()));
+        });
+
+        for (content, layout) in labels.iter() {
+            let expected = font.measure(content, TextProperties::default()).size();
+            assert_eq!(Some(expected), layout.as_ref().map(|layout| layout.size()));
+        }
+    }
+
+    /// Compares measuring text on the current thread and on the compute task pool.
+    ///
+    /// Run with `cargo test --release text_measurement_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn text_measurement_benchmark() {
+        const ITERATIONS: u32 = 20;
+        let font = roboto();
+        let properties = TextProperties {
+            max_size: (120.0, 100000.0),
+            ..Default::default()
+        };
+        let mut labels = labels(1000);
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            for (content, layout) in labels.iter_mut() {
+                *layout = Some(font.measure(content, properties));
+            }
+        }
+        let serial = start.elapsed() / ITERATIONS;
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            for_each_in_batches(&mut labels, |(content, layout)| {
+                *layout = Some(font.measure(content, properties));
+            });
+        }
+        let batched = start.elapsed() / ITERATIONS;
+
+        println!(
+            "{} labels: serial {:?}, batched {:?}",
+            labels.len(),
+            serial,
+            batched
+        );
+    }
+
+    #[test]
+    fn outline_in_bounds_should_grow_the_measured_size() {
+        let label = |in_bounds| KStyle {
+            font_size: StyleProp::Value(20.0),
+            line_height: StyleProp::Value(LineHeight::Pixels(20.0)),
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Hello"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            text_outline: StyleProp::Value((Color::BLACK, 2.0)),
+            text_outline_in_bounds: StyleProp::Value(in_bounds),
+            ..KStyle::initial()
+        };
+        // Text is measured within its closest parent that renders something
+        let root = KStyle {
+            width: StyleProp::Value(Units::Pixels(800.0)),
+            height: StyleProp::Value(Units::Pixels(600.0)),
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", root)
+            .leaf("outside", label(false))
+            .leaf("inside", label(true))
+            .build();
+        tree.calculate_nodes();
+
+        let size = |tree: &TestTree, name| {
+            let node = tree.world.get::<Node>(tree.entity(name)).unwrap();
+            (
+                node.resolved_styles.width.clone(),
+                node.resolved_styles.height.clone(),
+            )
+        };
+        // Five glyphs, each half as wide as the font size
+        assert_eq!(
+            (
+                StyleProp::Value(Units::Pixels(50.0)),
+                StyleProp::Value(Units::Pixels(20.0))
+            ),
+            size(&tree, "outside")
+        );
+        assert_eq!(
+            (
+                StyleProp::Value(Units::Pixels(54.0)),
+                StyleProp::Value(Units::Pixels(24.0))
+            ),
+            size(&tree, "inside")
+        );
+    }
+
+    #[test]
+    fn calc_should_resolve_against_parent_size() {
+        let mut styles = KStyle {
+            width: StyleProp::Value(Units::Calc(Calc::new(100.0, -48.0))),
+            top: StyleProp::Value(Units::Calc(Calc::new(50.0, 10.0))),
+            ..KStyle::initial()
+        };
+        let mut unresolved = styles.clone();
+        assert!(!apply_calc(&mut unresolved, None, None));
+        // Until the parent is laid out, only the percentage is used
+        assert_eq!(StyleProp::Value(Units::Percentage(100.0)), unresolved.width);
+        assert_eq!(StyleProp::Value(Units::Percentage(50.0)), unresolved.top);
+
+        let parent = Rect {
+            width: 400.0,
+            height: 200.0,
+            ..Default::default()
+        };
+        assert!(apply_calc(&mut styles, Some(parent), None));
+        assert_eq!(StyleProp::Value(Units::Pixels(352.0)), styles.width);
+        assert_eq!(StyleProp::Value(Units::Pixels(110.0)), styles.top);
+    }
+
+    #[test]
+    fn calc_grid_tracks_should_resolve_against_own_size() {
+        let mut styles = KStyle {
+            grid_cols: StyleProp::Value(vec![
+                Units::Pixels(48.0),
+                Units::Calc(Calc::new(100.0, -48.0)),
+            ]),
+            grid_rows: StyleProp::Value(vec![Units::Calc(Calc::new(50.0, 10.0))]),
+            ..KStyle::initial()
+        };
+        let mut unresolved = styles.clone();
+        assert!(!apply_calc(&mut unresolved, Some(Rect::default()), None));
+        assert_eq!(
+            StyleProp::Value(vec![Units::Pixels(48.0), Units::Percentage(100.0)]),
+            unresolved.grid_cols
+        );
+
+        let layout = Rect {
+            width: 400.0,
+            height: 200.0,
+            ..Default::default()
+        };
+        assert!(apply_calc(&mut styles, Some(Rect::default()), Some(layout)));
+        assert_eq!(
+            StyleProp::Value(vec![Units::Pixels(48.0), Units::Pixels(352.0)]),
+            styles.grid_cols
+        );
+        assert_eq!(
+            StyleProp::Value(vec![Units::Pixels(110.0)]),
+            styles.grid_rows
+        );
+    }
+}
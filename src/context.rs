@@ -8,26 +8,33 @@ use bevy::{
 use morphorm::Hierarchy;
 
 use crate::{
-    calculate_nodes::{calculate_layout, calculate_nodes},
+    accessibility::{label_from_children, AccessNode, AccessNodeTree},
+    calculate_nodes::{calculate_layout, calculate_nodes, measure_text, resolve_styles},
     children::KChildren,
     clone_component::{clone_state, clone_system, EntityCloneSystems, PreviousWidget},
     context_entities::ContextEntities,
     cursor::PointerEvents,
     event_dispatcher::EventDispatcher,
-    focus_tree::FocusTree,
+    focus_tree::{is_focusable, FocusChange, FocusTree},
     input::query_world,
-    layout::{LayoutCache, Rect},
+    layout::{LayoutCache, LayoutEngine, MorphormLayoutEngine, Rect},
     layout_dispatcher::LayoutEventDispatcher,
     node::{DirtyNode, WrappedIndex},
+    on_unmount::UnmountCallbacks,
     prelude::KayakWidgetContext,
-    render_primitive::RenderPrimitive,
+    render::font::{caret_index, selection_rects, FontProvider},
+    render_primitive::{assign_paint_order, dedup_clips, RenderPrimitive},
+    render_stats::RenderStats,
+    snapshot::{NodeSnapshot, TreeSnapshot},
     styles::{
-        ComputedStyles, Corner, Edge, KCursorIcon, KPositionType, KStyle, LayoutType,
-        RenderCommand, StyleProp, Units,
+        ActiveTransition, AlignItems, BackdropFilter, BoxShadow, ComputedStyles, Corner, Edge,
+        Gradient, KCursorIcon, KPositionType, KStyle, KTransform, LayoutType, LineHeight, Overflow,
+        PseudoState, PseudoStyles, RenderCommand, Repeat, Role, StyleProp, TextDecoration,
+        TextDirection, TextWrap, Transition, Units,
     },
-    tree::{Change, Tree},
+    tree::{Change, Tree, UpwardIterator},
     widget_state::WidgetState,
-    Focusable, KayakUIPlugin, WindowSize,
+    KayakUIPlugin, WindowSize,
 };
 
 /// A tag component representing when a widget has been mounted(added to the tree).
@@ -35,6 +42,55 @@ use crate::{
 #[reflect(Component)]
 pub struct Mounted;
 
+/// A change in which widget the pointer is over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HoverChange {
+    /// The widget the pointer left, if any
+    pub left: Option<Entity>,
+    /// The widget the pointer entered, if any
+    pub entered: Option<Entity>,
+}
+
+/// The widgets that were added to and removed from the tree by the last widget update
+///
+/// This is useful for starting enter and exit animations, such as fading in the rows added to
+/// a list. Removed widgets have already been despawned by the time this is reported, so an exit
+/// animation has to be drawn by something that is still in the tree.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct TreeDelta {
+    /// The widgets that were added, in tree order
+    pub added: Vec<Entity>,
+    /// The widgets that were removed, in the tree order they had before being removed
+    pub removed: Vec<Entity>,
+}
+
+impl TreeDelta {
+    /// Compares the widgets in the tree before and after an update, each given in tree order
+    pub(crate) fn new(before: &[WrappedIndex], after: &[WrappedIndex]) -> Self {
+        let (before_set, after_set) = (
+            before.iter().collect::<HashSet<_>>(),
+            after.iter().collect::<HashSet<_>>(),
+        );
+        Self {
+            added: after
+                .iter()
+                .filter(|index| !before_set.contains(index))
+                .map(|index| index.0)
+                .collect(),
+            removed: before
+                .iter()
+                .filter(|index| !after_set.contains(index))
+                .map(|index| index.0)
+                .collect(),
+        }
+    }
+
+    /// Returns true if no widgets were added or removed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
 const UPDATE_DEPTH: u32 = 0;
 
 type WidgetSystems = HashMap<
@@ -82,6 +138,7 @@ pub struct KayakRootContext {
     pub(crate) focus_tree: Arc<RwLock<FocusTree>>,
     systems: WidgetSystems,
     pub(crate) current_z: f32,
+    pub(crate) z_base: f32,
     pub(crate) context_entities: ContextEntities,
     pub(crate) current_cursor: CursorIcon,
     pub(crate) clone_systems: Arc<RwLock<EntityCloneSystems>>,
@@ -90,6 +147,69 @@ pub struct KayakRootContext {
     pub(crate) order_tree: Arc<RwLock<Tree>>,
     pub(crate) index: Arc<RwLock<HashMap<Entity, usize>>>,
     pub(crate) uninitilized_systems: HashSet<String>,
+    pub(crate) unmount_callbacks: UnmountCallbacks,
+    pub(crate) style_transitions: HashMap<Entity, ActiveTransition>,
+    pub(crate) stats: Arc<RwLock<RenderStats>>,
+    pub(crate) layout_engine: Arc<dyn LayoutEngine>,
+    pub(crate) pending_assets: Arc<RwLock<HashSet<WrappedIndex>>>,
+    /// Nodes whose changes affect layout since the last layout pass
+    pub(crate) dirty_layout_nodes: HashSet<WrappedIndex>,
+    pub(crate) assets_ready_callbacks: Arc<RwLock<Vec<AssetsReadyCallback>>>,
+    pub(crate) focus_changes: Arc<RwLock<Vec<FocusChange>>>,
+    /// The styles the root widget inherits from, and the fallback for widgets without styles
+    pub(crate) default_styles: KStyle,
+    /// Whether every node needs to re-resolve its styles against new default styles
+    pub(crate) default_styles_changed: bool,
+    /// The topmost widget under the pointer
+    pub(crate) hovered: Option<WrappedIndex>,
+    /// Whether the pointer is pressed
+    pub(crate) pointer_pressed: bool,
+    /// The focused widget as of the last time styles were resolved
+    pub(crate) styled_focus: Option<WrappedIndex>,
+    /// The widget to focus once it's mounted, unless something else gets focus first
+    pub(crate) initial_focus: Option<WrappedIndex>,
+    /// The widgets added and removed by the last widget update
+    pub(crate) tree_delta: TreeDelta,
+    /// Whether resolved styles are checked for likely mistakes
+    pub(crate) validate_styles: bool,
+    /// Whether widgets whose parent styles can't be found are logged
+    pub(crate) warn_missing_parent_styles: bool,
+    pub(crate) primitive_filter: Arc<RwLock<Option<PrimitiveFilter>>>,
+    /// How many times each widget's node has been rebuilt
+    pub(crate) generations: HashMap<WrappedIndex, u64>,
+    /// The layouts written back into the styles of each widget, relative to their parents
+    pub(crate) frozen_layouts: HashMap<WrappedIndex, Rect>,
+    /// The roots of subtrees whose widgets aren't updated
+    pub(crate) inactive_subtrees: HashSet<WrappedIndex>,
+    /// The maximum number of dirty nodes to rebuild each frame, if limited
+    pub(crate) node_budget: Option<usize>,
+    /// How many nodes have been rebuilt this frame, counted against the node budget
+    pub(crate) budget_used: usize,
+    /// Whether dirty nodes were left over for the next frame because of the node budget
+    pub(crate) pending_nodes: bool,
+    /// How many batches are open, nodes aren't rebuilt while any are
+    pub(crate) batch_depth: usize,
+    /// The font size that `Units::Rem` sizes are relative to
+    pub(crate) root_font_size: f32,
+    /// The size that `Units::ViewportWidth` and `Units::ViewportHeight` sizes are relative to
+    pub(crate) viewport_size: (f32, f32),
+    /// Whether measured text and layouts are rounded to whole pixels
+    pub(crate) pixel_snapping: bool,
+    pub(crate) layout_subscriptions: Arc<RwLock<HashMap<WrappedIndex, LayoutSubscription>>>,
+}
+
+type AssetsReadyCallback = Box<dyn FnOnce() + Send + Sync>;
+
+type LayoutChangedCallback = Arc<dyn Fn(Rect, Rect) + Send + Sync>;
+
+type PrimitiveFilter = Box<dyn FnMut(&mut RenderPrimitive) + Send + Sync>;
+
+/// The [`on_layout_changed`](KayakRootContext::on_layout_changed) callbacks of a widget
+#[derive(Default)]
+pub(crate) struct LayoutSubscription {
+    /// The layout of the widget as of the last layout pass
+    previous: Option<Rect>,
+    callbacks: Vec<LayoutChangedCallback>,
 }
 
 impl Default for KayakRootContext {
@@ -107,6 +227,7 @@ impl KayakRootContext {
             focus_tree: Default::default(),
             systems: HashMap::default(),
             current_z: 0.0,
+            z_base: 0.0,
             context_entities: ContextEntities::new(),
             current_cursor: CursorIcon::Default,
             clone_systems: Default::default(),
@@ -115,6 +236,297 @@ impl KayakRootContext {
             index: Default::default(),
             order_tree: Default::default(),
             uninitilized_systems: Default::default(),
+            unmount_callbacks: Default::default(),
+            style_transitions: Default::default(),
+            stats: Default::default(),
+            layout_engine: Arc::new(MorphormLayoutEngine),
+            pending_assets: Default::default(),
+            dirty_layout_nodes: Default::default(),
+            assets_ready_callbacks: Default::default(),
+            focus_changes: Default::default(),
+            default_styles: KStyle::new_default(),
+            default_styles_changed: false,
+            hovered: None,
+            pointer_pressed: false,
+            styled_focus: None,
+            initial_focus: None,
+            tree_delta: TreeDelta::default(),
+            validate_styles: false,
+            warn_missing_parent_styles: false,
+            primitive_filter: Default::default(),
+            generations: HashMap::default(),
+            frozen_layouts: HashMap::default(),
+            inactive_subtrees: HashSet::default(),
+            node_budget: None,
+            budget_used: 0,
+            pending_nodes: false,
+            batch_depth: 0,
+            root_font_size: crate::DEFAULT_ROOT_FONT_SIZE,
+            viewport_size: (0.0, 0.0),
+            pixel_snapping: false,
+            layout_subscriptions: Default::default(),
+        }
+    }
+
+    /// Offsets the z-index of every widget in this context by the given base.
+    ///
+    /// Useful for making sure one UI (such as an overlay) always draws above another.
+    pub fn with_z_base(mut self, base: f32) -> Self {
+        self.z_base = base;
+        self
+    }
+
+    /// Uses the given engine to lay out the widgets in this context.
+    ///
+    /// Defaults to [`MorphormLayoutEngine`].
+    pub fn with_layout_engine(mut self, engine: impl LayoutEngine + 'static) -> Self {
+        self.layout_engine = Arc::new(engine);
+        self
+    }
+
+    /// Limits how many dirty nodes are rebuilt each frame, or removes the limit with `None`.
+    ///
+    /// When many nodes are dirtied at once (such as after changing the default styles),
+    /// rebuilding them all in one frame causes a spike. With a budget, nodes are rebuilt in
+    /// tree order, parents first, and the rest are left for the following frames. This trades
+    /// a few frames of stale widgets for smooth frame times.
+    pub fn set_node_budget(&mut self, max_nodes: Option<usize>) {
+        self.node_budget = max_nodes;
+    }
+
+    /// Returns true if dirty nodes were left over for the next frame because of the node budget.
+    ///
+    /// See [`KayakRootContext::set_node_budget`].
+    pub fn has_pending_nodes(&self) -> bool {
+        self.pending_nodes
+    }
+
+    /// Starts a batch of updates, during which no nodes are rebuilt or laid out.
+    ///
+    /// Widgets changed during the batch stay dirty, and are rebuilt once when the batch ends.
+    /// This avoids redundant work when updating many widgets over several systems or frames,
+    /// such as when applying a whole theme. Batches can be nested, and nodes are only rebuilt
+    /// once every batch has ended.
+    pub fn begin_batch(&mut self) {
+        self.batch_depth += 1;
+    }
+
+    /// Ends a batch started with [`KayakRootContext::begin_batch`].
+    pub fn end_batch(&mut self) {
+        if self.batch_depth == 0 {
+            log::warn!("Tried to end a batch when none were started");
+            return;
+        }
+        self.batch_depth -= 1;
+    }
+
+    /// Returns true while a batch is open.
+    ///
+    /// See [`KayakRootContext::begin_batch`].
+    pub fn is_batching(&self) -> bool {
+        self.batch_depth > 0
+    }
+
+    /// Sets the base z-index of every widget in this context.
+    ///
+    /// See [`KayakRootContext::with_z_base`].
+    pub fn set_z_base(&mut self, base: f32) {
+        self.z_base = base;
+    }
+
+    /// Sets the font size that [`Units::Rem`] sizes and `font_size_rem` are relative to.
+    ///
+    /// Every widget is resolved again, so the whole UI scales uniformly. This is useful for
+    /// letting users scale text (and everything sized around it) for accessibility.
+    pub fn set_root_font_size(&mut self, size: f32) {
+        if size != self.root_font_size {
+            self.root_font_size = size;
+            // Re-resolves every node, just like changing the default styles
+            self.default_styles_changed = true;
+        }
+    }
+
+    /// Returns the font size that [`Units::Rem`] sizes are relative to.
+    pub fn root_font_size(&self) -> f32 {
+        self.root_font_size
+    }
+
+    /// Sets the size that [`Units::ViewportWidth`] and [`Units::ViewportHeight`] sizes are
+    /// relative to. This is kept at the size of the window by default, and until it's known,
+    /// those sizes are relative to the parent instead.
+    ///
+    /// Every widget is resolved and the whole tree is laid out again, so widgets sized relative
+    /// to the viewport, such as a full screen overlay, follow it without knowing the root size.
+    pub fn set_viewport_size(&mut self, size: (f32, f32)) {
+        if size != self.viewport_size {
+            self.viewport_size = size;
+            // Re-resolves every node, just like changing the root font size
+            self.default_styles_changed = true;
+            if let Ok(tree) = self.tree.try_read() {
+                self.dirty_layout_nodes.extend(tree.root_node);
+            }
+        }
+    }
+
+    /// Returns the size that [`Units::ViewportWidth`] and [`Units::ViewportHeight`] sizes are
+    /// relative to.
+    pub fn viewport_size(&self) -> (f32, f32) {
+        self.viewport_size
+    }
+
+    /// Sets whether measured text and computed layouts are rounded to whole pixels.
+    ///
+    /// Fractional sizes can make text blurry and make layouts jitter by a sub-pixel amount from
+    /// frame to frame. This is off by default, since it can shift existing layouts slightly.
+    pub fn set_pixel_snapping(&mut self, enabled: bool) {
+        if enabled != self.pixel_snapping {
+            self.pixel_snapping = enabled;
+            // Text has to be measured again
+            self.default_styles_changed = true;
+        }
+    }
+
+    /// Sets whether resolved styles are checked for conflicting or nonsensical properties.
+    ///
+    /// Each problem is logged as a warning along with the name of the widget, whenever the
+    /// widget's styles are resolved. See [`KStyle::validate`] for the problems that are caught.
+    /// This is meant for debugging, so it's off by default.
+    pub fn set_style_validation(&mut self, enabled: bool) {
+        self.validate_styles = enabled;
+    }
+
+    /// Sets whether a warning is logged when a widget's parent has no styles to inherit from.
+    ///
+    /// This happens when the parent has neither a node nor any styles of its own yet, usually
+    /// because it's rendered after its children. The widget then inherits from the
+    /// [default styles](KayakRootContext::set_default_style) instead, which can make theming
+    /// issues hard to track down. This is meant for debugging, so it's off by default.
+    pub fn set_missing_parent_style_warnings(&mut self, enabled: bool) {
+        self.warn_missing_parent_styles = enabled;
+    }
+
+    /// Sets the styles that the root widget inherits from.
+    ///
+    /// Any property marked as [`StyleProp::Inherit`] (such as `color` or `font`) flows down from
+    /// here, which makes it a good place to define the theme of the whole UI. Properties left
+    /// unset fall back to [`StyleProp::Default`]. Every widget re-resolves its styles on the
+    /// next render.
+    pub fn set_default_style(&mut self, style: KStyle) {
+        self.default_styles = style.with_style(KStyle::new_default());
+        self.default_styles_changed = true;
+    }
+
+    /// Returns the styles that the root widget inherits from.
+    ///
+    /// See [`KayakRootContext::set_default_style`].
+    pub fn default_style(&self) -> &KStyle {
+        &self.default_styles
+    }
+
+    /// Runs the given callback once every asset used by the widget tree has loaded.
+    ///
+    /// Text can't be measured until its font has loaded, so this can be used to hold off on
+    /// showing the UI (or to show a loading screen) until everything can be laid out. The
+    /// callback runs after the next render in which no assets are pending.
+    pub fn on_assets_ready(&self, callback: impl FnOnce() + Send + Sync + 'static) {
+        if let Ok(mut callbacks) = self.assets_ready_callbacks.try_write() {
+            callbacks.push(Box::new(callback));
+        }
+    }
+
+    /// Returns true if any widget is waiting on an asset to load before it can be measured.
+    pub fn has_pending_assets(&self) -> bool {
+        self.pending_assets
+            .try_read()
+            .map(|pending| !pending.is_empty())
+            .unwrap_or(true)
+    }
+
+    /// Records whether the given widget is waiting on an asset to load.
+    pub(crate) fn set_asset_pending(&self, id: WrappedIndex, pending: bool) {
+        if let Ok(mut pending_assets) = self.pending_assets.try_write() {
+            if pending {
+                pending_assets.insert(id);
+            } else {
+                pending_assets.remove(&id);
+            }
+        }
+    }
+
+    /// Runs the given callback whenever the layout of the given widget changes.
+    ///
+    /// The callback is given the old and new layout of the widget, and runs after the layout
+    /// pass in which the change happened. The subscription lasts until the widget is removed.
+    pub fn on_layout_changed(
+        &self,
+        widget: Entity,
+        callback: impl Fn(Rect, Rect) + Send + Sync + 'static,
+    ) {
+        let index = WrappedIndex(widget);
+        let current = self
+            .layout_cache
+            .try_read()
+            .ok()
+            .and_then(|layout_cache| layout_cache.rect.get(&index).copied());
+        if let Ok(mut subscriptions) = self.layout_subscriptions.try_write() {
+            let subscription = subscriptions
+                .entry(index)
+                .or_insert_with(|| LayoutSubscription {
+                    previous: current,
+                    ..Default::default()
+                });
+            subscription.callbacks.push(Arc::new(callback));
+        }
+    }
+
+    /// Runs the [`on_layout_changed`](Self::on_layout_changed) callbacks of widgets whose layout
+    /// differs from the last layout pass.
+    pub(crate) fn notify_layout_changed(&self) {
+        let layout_cache = match self.layout_cache.try_read() {
+            Ok(layout_cache) => layout_cache,
+            Err(_) => return,
+        };
+        // The callbacks are collected first, so they can subscribe or read the layout themselves
+        let mut changes = Vec::new();
+        if let Ok(mut subscriptions) = self.layout_subscriptions.try_write() {
+            for (index, subscription) in subscriptions.iter_mut() {
+                let current = match layout_cache.rect.get(index) {
+                    Some(current) => *current,
+                    None => continue,
+                };
+                match subscription.previous.replace(current) {
+                    Some(previous) if previous != current => {
+                        for callback in subscription.callbacks.iter() {
+                            changes.push((callback.clone(), previous, current));
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+        drop(layout_cache);
+
+        for (callback, previous, current) in changes {
+            callback(previous, current);
+        }
+    }
+
+    /// Runs the [`on_assets_ready`](Self::on_assets_ready) callbacks if no assets are pending.
+    pub(crate) fn notify_assets_ready(&self, tree: &Tree) {
+        if let Ok(mut pending_assets) = self.pending_assets.try_write() {
+            // Widgets that were removed will never finish loading
+            pending_assets.retain(|id| tree.contains(*id));
+            if !pending_assets.is_empty() {
+                return;
+            }
+        }
+
+        let callbacks = match self.assets_ready_callbacks.try_write() {
+            Ok(mut callbacks) => std::mem::take(&mut *callbacks),
+            Err(_) => return,
+        };
+        for callback in callbacks {
+            callback();
         }
     }
 
@@ -131,6 +543,88 @@ impl KayakRootContext {
         None
     }
 
+    /// Retrieves the statistics about the work done to render the last frame.
+    pub fn last_stats(&self) -> RenderStats {
+        if let Ok(stats) = self.stats.try_read() {
+            *stats
+        } else {
+            RenderStats::default()
+        }
+    }
+
+    /// Sets the widget that receives focus when it's first mounted.
+    ///
+    /// Focus normally starts out on the root widget. This is only applied while focus is still
+    /// there, so focus that was already given to another widget is kept.
+    pub fn set_initial_focus(&mut self, widget: Entity) {
+        self.initial_focus = Some(WrappedIndex(widget));
+    }
+
+    /// Focuses the [initial focus](Self::set_initial_focus) once it's part of the focus tree.
+    pub(crate) fn apply_initial_focus(&mut self) {
+        let target = match self.initial_focus {
+            Some(target) => target,
+            None => return,
+        };
+        if let Ok(mut focus_tree) = self.focus_tree.try_write() {
+            let current = focus_tree.current();
+            if current.is_some() && current != focus_tree.tree().root_node {
+                // Something else was focused first
+                self.initial_focus = None;
+            } else if focus_tree.contains(target) {
+                focus_tree.focus(target);
+                self.initial_focus = None;
+            }
+        }
+    }
+
+    /// Constrains keyboard focus to the given focusable widget and its focusable descendants.
+    ///
+    /// Useful for keeping focus within a modal dialog. See [`FocusTree::push_trap`].
+    pub fn push_focus_trap(&self, root: Entity) {
+        if let Ok(mut focus_tree) = self.focus_tree.try_write() {
+            focus_tree.push_trap(WrappedIndex(root));
+        }
+    }
+
+    /// Sets whether keyboard focus wraps around when moving past either end of the focus tree.
+    pub fn set_focus_wrap(&self, wrap: bool) {
+        if let Ok(mut focus_tree) = self.focus_tree.try_write() {
+            focus_tree.set_focus_wrap(wrap);
+        }
+    }
+
+    /// Returns every change in focus since this was last called, oldest first.
+    ///
+    /// Focus changes made while widgets are updated, as well as those made by input events,
+    /// are recorded once per update. This can be used to play a sound or scroll the newly
+    /// focused widget into view.
+    pub fn drain_focus_changes(&self) -> Vec<FocusChange> {
+        if let Ok(mut focus_changes) = self.focus_changes.try_write() {
+            return focus_changes.drain(..).collect();
+        }
+        Vec::new()
+    }
+
+    /// Records the change in focus since the last recorded change, if there was one.
+    pub(crate) fn record_focus_change(&self) {
+        let change = match self.focus_tree.try_write() {
+            Ok(mut focus_tree) => focus_tree.take_change(),
+            Err(_) => return,
+        };
+        if let (Some(change), Ok(mut focus_changes)) = (change, self.focus_changes.try_write()) {
+            focus_changes.push(change);
+        }
+    }
+
+    /// Removes the most recently pushed focus trap, returning its root.
+    pub fn pop_focus_trap(&self) -> Option<Entity> {
+        if let Ok(mut focus_tree) = self.focus_tree.try_write() {
+            return focus_tree.pop_trap().map(|index| index.0);
+        }
+        None
+    }
+
     /// Get's the layout for th given widget index.
     pub(crate) fn get_layout(&self, id: &WrappedIndex) -> Option<Rect> {
         if let Ok(cache) = self.layout_cache.try_read() {
@@ -202,6 +696,272 @@ impl KayakRootContext {
         }
     }
 
+    /// Adds a widget as the root of a new layer, or as the root widget if there isn't one yet.
+    ///
+    /// Each layer is an independent widget tree, laid out on its own like the root widget is,
+    /// and drawn above the root widget and every layer added before it. This allows a single
+    /// context to hold separate UIs, such as a HUD, a menu and a tooltip layer. Focus moves
+    /// through the layers in the order they were added, after the root widget.
+    pub fn add_root(&mut self, widget: Entity) {
+        if let Ok(mut tree) = self.tree.write() {
+            tree.add_root(WrappedIndex(widget));
+            if let Ok(mut cache) = self.layout_cache.try_write() {
+                cache.add(WrappedIndex(widget));
+            }
+        }
+    }
+
+    /// Removes a widget and all of its descendants from the tree.
+    ///
+    /// Every removed widget is also dropped from the layout cache and the focus tree, and any
+    /// state or context entities it owned are cleaned up. Unmount callbacks run and the widget
+    /// entities are despawned once the given commands are applied.
+    ///
+    /// The parent should stop rendering the widget as well, otherwise it is added back the next
+    /// time the parent updates.
+    pub fn remove_subtree(&mut self, commands: &mut Commands, entity: Entity) {
+        let index = WrappedIndex(entity);
+        let (removed, parent) = if let Ok(mut tree) = self.tree.try_write() {
+            if !tree.contains(index) {
+                return;
+            }
+            let removed = tree.down_iter_at(index, true).collect::<Vec<_>>();
+            let parent = tree.get_parent(index);
+            tree.remove(index);
+            (removed, parent)
+        } else {
+            return;
+        };
+
+        if let Ok(mut order_tree) = self.order_tree.try_write() {
+            if order_tree.contains(index) {
+                order_tree.remove(index);
+            }
+        }
+
+        if let Ok(mut layout_cache) = self.layout_cache.try_write() {
+            for child in removed.iter() {
+                layout_cache.remove(*child);
+            }
+        }
+
+        if let Ok(mut focus_tree) = self.focus_tree.try_write() {
+            for child in removed.iter() {
+                if focus_tree.contains(*child) && focus_tree.tree().root_node != Some(*child) {
+                    focus_tree.remove(*child);
+                }
+            }
+        }
+
+        if let Ok(mut pending_assets) = self.pending_assets.try_write() {
+            for child in removed.iter() {
+                pending_assets.remove(child);
+            }
+        }
+
+        if let Ok(mut subscriptions) = self.layout_subscriptions.try_write() {
+            for child in removed.iter() {
+                subscriptions.remove(child);
+            }
+        }
+
+        if let Ok(mut index) = self.index.try_write() {
+            for child in removed.iter() {
+                index.remove(&child.0);
+            }
+        }
+
+        let mut despawn_list = removed.iter().map(|child| child.0).collect::<Vec<_>>();
+        if let Ok(mut cloned_widget_entities) = self.cloned_widget_entities.try_write() {
+            for child in removed.iter() {
+                if let Some(cloned_entity) = cloned_widget_entities.remove(&child.0) {
+                    despawn_list.push(cloned_entity);
+                }
+            }
+        }
+
+        for child in removed.iter() {
+            self.style_transitions.remove(&child.0);
+            self.generations.remove(child);
+            self.frozen_layouts.remove(child);
+            self.inactive_subtrees.remove(child);
+            self.dirty_layout_nodes.remove(child);
+            if let Some(state_entity) = self.widget_state.remove(child.0) {
+                despawn_list.push(state_entity);
+            }
+            self.context_entities.remove_context_entities(child.0);
+        }
+
+        // The siblings of the removed widget need to be laid out again.
+        if let Some(parent) = parent {
+            self.dirty_layout_nodes.insert(parent);
+            if let Some(mut entity_commands) = commands.get_entity(parent.0) {
+                entity_commands.insert(DirtyNode);
+            }
+        }
+
+        let unmount_callbacks = self.unmount_callbacks.clone();
+        commands.add(move |world: &mut World| {
+            for child in removed.iter() {
+                unmount_callbacks.run(world, child.0);
+            }
+            for entity in despawn_list {
+                if let Some(entity_mut) = world.get_entity_mut(entity) {
+                    entity_mut.despawn();
+                }
+            }
+        });
+    }
+
+    /// Moves a widget, along with its descendants, to a new parent without recreating it.
+    ///
+    /// The widget is inserted at the given position among the new parent's children, clamped
+    /// to the number of children. Unlike removing the widget and rendering it again, its state
+    /// and entity are kept, which is useful for things like reordering a list by drag and drop.
+    /// Focusable widgets within it move to their new place in the focus order.
+    ///
+    /// Returns false, leaving the tree untouched, if either widget isn't in the tree, the
+    /// widget is a root, or the new parent is the widget itself or one of its descendants.
+    ///
+    /// Both parents should render their children to match, otherwise the next update of either
+    /// parent undoes the move.
+    pub fn reparent(
+        &mut self,
+        commands: &mut Commands,
+        widget: Entity,
+        new_parent: Entity,
+        index: usize,
+    ) -> bool {
+        let (widget, new_parent) = (WrappedIndex(widget), WrappedIndex(new_parent));
+        let old_parent = if let Ok(mut tree) = self.tree.try_write() {
+            let old_parent = tree.get_parent(widget);
+            if !tree.reparent(widget, new_parent, index) {
+                return false;
+            }
+            old_parent
+        } else {
+            return false;
+        };
+
+        // The new parent finds the widget in the ordered tree when it renders its children.
+        if let Ok(mut order_tree) = self.order_tree.try_write() {
+            order_tree.reparent(widget, new_parent, index);
+        }
+
+        if let Ok(tree) = self.tree.try_read() {
+            if let Ok(mut focus_tree) = self.focus_tree.try_write() {
+                focus_tree.reparent(widget, &tree);
+            }
+
+            // The widgets may inherit different styles from their new ancestors.
+            let dirty = tree
+                .down_iter_at(widget, true)
+                .chain(old_parent)
+                .chain(Some(new_parent));
+            for index in dirty {
+                if let Some(mut entity_commands) = commands.get_entity(index.0) {
+                    entity_commands.insert(DirtyNode);
+                }
+            }
+        }
+
+        // The siblings in both parents need to be laid out again.
+        self.dirty_layout_nodes.extend(old_parent);
+        self.dirty_layout_nodes.insert(new_parent);
+        true
+    }
+
+    /// Lists every widget with a cached layout, along with that layout and whether it was
+    /// recomputed by the last layout pass.
+    ///
+    /// This is meant for diagnosing stale layouts, such as a widget that didn't move when its
+    /// styles changed. Widgets that weren't recomputed kept the layout they had before, because
+    /// neither they nor any of the widgets around them were marked as needing layout. Widgets
+    /// are listed in tree order.
+    pub fn layout_cache_report(&self) -> Vec<(Entity, Rect, bool)> {
+        let (tree, layout_cache) = match (self.tree.try_read(), self.layout_cache.try_read()) {
+            (Ok(tree), Ok(layout_cache)) => (tree, layout_cache),
+            _ => return Vec::new(),
+        };
+        tree.roots()
+            .flat_map(|root| tree.down_iter_at(root, true))
+            .filter_map(|index| {
+                let layout = layout_cache.rect.get(&index)?;
+                Some((index.0, *layout, layout_cache.recomputed.contains(&index)))
+            })
+            .collect()
+    }
+
+    /// Finds every widget in the tree that can't be reached from the root.
+    ///
+    /// These are left behind when the tree is manipulated incorrectly, such as by removing a
+    /// widget from its parent's children without removing the widget itself. They are never
+    /// rendered, but still take up memory and show up when inspecting the tree.
+    pub fn find_orphans(&self) -> Vec<Entity> {
+        let tree = match self.tree.try_read() {
+            Ok(tree) => tree,
+            Err(_) => return Vec::new(),
+        };
+        let reachable = tree.down_iter().collect::<HashSet<_>>();
+        let mut orphans = tree
+            .parents
+            .keys()
+            .chain(tree.children.keys())
+            .chain(tree.children.values().flatten())
+            .filter(|index| !reachable.contains(*index))
+            .map(|index| index.0)
+            .collect::<Vec<_>>();
+        orphans.sort();
+        orphans.dedup();
+        orphans
+    }
+
+    /// Removes every widget found by [`KayakRootContext::find_orphans`], returning them.
+    ///
+    /// Orphans are cleaned up like [`KayakRootContext::remove_subtree`] does, so their state is
+    /// released, their unmount callbacks run, and they're despawned once the commands are applied.
+    pub fn prune_orphans(&mut self, commands: &mut Commands) -> Vec<Entity> {
+        let orphans = self.find_orphans();
+        let orphan_roots = match self.tree.try_read() {
+            Ok(tree) => orphans
+                .iter()
+                .filter(|orphan| {
+                    tree.get_parent(WrappedIndex(**orphan))
+                        .map_or(true, |parent| orphans.binary_search(&parent.0).is_err())
+                })
+                .copied()
+                .collect::<Vec<_>>(),
+            Err(_) => return Vec::new(),
+        };
+        for orphan in orphan_roots {
+            self.remove_subtree(commands, orphan);
+        }
+
+        // Orphans without a parent aren't fully removed from the tree by `remove_subtree`
+        if let Ok(mut tree) = self.tree.try_write() {
+            for orphan in orphans.iter() {
+                tree.parents.remove(&WrappedIndex(*orphan));
+                tree.children.remove(&WrappedIndex(*orphan));
+            }
+            for children in tree.children.values_mut() {
+                children.retain(|child| orphans.binary_search(&child.0).is_err());
+            }
+        }
+
+        orphans
+    }
+
+    /// Registers a callback that is ran when the given widget is removed from the tree.
+    /// This is useful for releasing external resources tied to a widget's lifetime.
+    /// Callbacks run before the widget entity is despawned.
+    pub fn on_unmount(
+        &self,
+        widget_entity: Entity,
+        callback: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) {
+        self.unmount_callbacks.add(widget_entity, callback);
+    }
+
     /// Creates a new context using the context entity for the given type_id + parent id.
     /// Context can be considered state that changes across multiple components.
     /// Alternatively you can use bevy's resources.
@@ -283,50 +1043,903 @@ impl KayakRootContext {
         0
     }
 
-    /// Generates a flat list of widget render commands sorted by tree order.
-    /// There is no need to call this unless you are implementing your own custom renderer.
-    pub fn build_render_primitives(
+    /// Finds all widgets in the tree whose [`WidgetName`] exactly matches the given name.
+    /// Widgets are returned in tree order, and an empty list is returned if nothing matches.
+    ///
+    /// This is mostly useful for tests and tooling that need to inspect a specific widget.
+    pub fn find_by_name(&self, widget_names: &Query<&WidgetName>, name: &str) -> Vec<Entity> {
+        if let Ok(tree) = self.tree.try_read() {
+            tree.down_iter()
+                .filter(|index| {
+                    widget_names
+                        .get(index.0)
+                        .map(|widget_name| widget_name.0 == name)
+                        .unwrap_or_default()
+                })
+                .map(|index| index.0)
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    /// Returns the fully resolved styles of the given widget, after inheritance and defaults.
+    ///
+    /// Returns `None` if the widget isn't in this context or its node hasn't been built yet.
+    pub fn get_resolved_style<'a>(
+        &self,
+        widget: Entity,
+        nodes: &'a Query<&crate::node::Node>,
+    ) -> Option<&'a KStyle> {
+        let node_tree = self.tree.try_read().ok()?;
+        if !node_tree.contains(WrappedIndex(widget)) {
+            return None;
+        }
+        nodes.get(widget).ok().map(|node| &node.resolved_styles)
+    }
+
+    /// Computes the highlight rects for the chars in `start..end` of the given text widget.
+    ///
+    /// One rect is returned for each line the selection touches, so selections that span
+    /// wrapped lines are covered correctly. The rects use the same text layout and position the
+    /// widget is rendered with, including the scroll position and transforms of its ancestors,
+    /// and are empty if the widget isn't text or hasn't been laid out yet.
+    pub fn text_selection_rects(
         &self,
+        widget: Entity,
         nodes: &Query<&crate::node::Node>,
-        widget_names: &Query<&WidgetName>,
-    ) -> Vec<RenderPrimitive> {
-        let node_tree = self.tree.try_read();
-        if node_tree.is_err() {
-            return vec![];
+        start: usize,
+        end: usize,
+    ) -> Vec<Rect> {
+        let layout = match self.rendered_layout_of(widget, nodes) {
+            Some(layout) => layout,
+            None => return vec![],
+        };
+        match nodes.get(widget).map(|node| &node.primitive) {
+            Ok(RenderPrimitive::Text {
+                text_layout,
+                direction,
+                ..
+            }) => selection_rects(text_layout, *direction, &layout, start, end),
+            _ => vec![],
         }
+    }
 
-        let node_tree = node_tree.unwrap();
+    /// Returns the layout of the given widget where it's drawn, see [`rendered_layout`].
+    fn rendered_layout_of(
+        &self,
+        widget: Entity,
+        nodes: &Query<&crate::node::Node>,
+    ) -> Option<Rect> {
+        let node_tree = self.tree.try_read().ok()?;
+        let layout_cache = self.layout_cache.try_read().ok()?;
+        rendered_layout(&node_tree, &layout_cache, nodes, WrappedIndex(widget))
+            .map(|(layout, _)| layout)
+    }
 
-        if node_tree.root_node.is_none() {
-            return vec![];
+    /// Finds the char index in the given text widget closest to the given point.
+    ///
+    /// This is where a caret should be placed when clicking within the text. Points outside of
+    /// the text are clamped to the nearest line and its start or end. The text is positioned
+    /// where it's drawn, like with [`KayakRootContext::text_selection_rects`]. Returns `None` if
+    /// the widget isn't text or hasn't been laid out yet.
+    pub fn text_hit(
+        &self,
+        widget: Entity,
+        nodes: &Query<&crate::node::Node>,
+        point: (f32, f32),
+    ) -> Option<usize> {
+        let layout = self.rendered_layout_of(widget, nodes)?;
+        match nodes.get(widget).map(|node| &node.primitive) {
+            Ok(RenderPrimitive::Text {
+                text_layout,
+                direction,
+                ..
+            }) => Some(caret_index(text_layout, *direction, &layout, point)),
+            _ => None,
         }
+    }
 
-        let render_primitives = if let Ok(mut layout_cache) = self.layout_cache.try_write() {
-            recurse_node_tree_to_build_primitives(
-                &node_tree,
-                &mut layout_cache,
-                nodes,
-                widget_names,
-                node_tree.root_node.unwrap(),
-                0.0,
-                RenderPrimitive::Empty,
-            )
-        } else {
-            vec![]
+    /// Finds the topmost widget whose layout contains the given point.
+    ///
+    /// Layouts are positioned like they're rendered, so the scroll position and transforms of
+    /// their ancestors are taken into account.
+    ///
+    /// Children are checked before their parents and siblings are checked in reverse
+    /// z-order, matching the order widgets are drawn in. Points outside of a `Clip`
+    /// region never hit the widgets within it, unless their overflow is visible.
+    pub fn widget_at(
+        &self,
+        nodes: &Query<&crate::node::Node>,
+        point: (f32, f32),
+    ) -> Option<Entity> {
+        let node_tree = self.tree.try_read().ok()?;
+        let layout_cache = self.layout_cache.try_read().ok()?;
+        // Layers added later are drawn above the others
+        let roots = node_tree.visible_roots().collect::<Vec<_>>();
+        roots
+            .into_iter()
+            .rev()
+            .find_map(|root| {
+                recurse_node_tree_to_find_widget(
+                    &node_tree,
+                    &layout_cache,
+                    nodes,
+                    root,
+                    point,
+                    RenderedAncestors::default(),
+                )
+            })
+            .map(|index| index.0)
+    }
+
+    /// Checks if any part of the given widget is actually drawn on screen.
+    ///
+    /// The widget's layout, offset by the scroll position and transforms of its ancestors, has
+    /// to overlap every enclosing `Clip` region, as well as the viewport when one is given.
+    /// Widgets with visible overflow escape the clip regions of their ancestors, just like
+    /// when rendering. Widgets that haven't been laid out yet are never visible.
+    pub fn is_visible(
+        &self,
+        widget: Entity,
+        nodes: &Query<&crate::node::Node>,
+        viewport: Option<Rect>,
+    ) -> bool {
+        let (node_tree, layout_cache) = match (self.tree.try_read(), self.layout_cache.try_read()) {
+            (Ok(node_tree), Ok(layout_cache)) => (node_tree, layout_cache),
+            _ => return false,
+        };
+        if is_within(&node_tree, &node_tree.hidden, WrappedIndex(widget)) {
+            return false;
+        }
+        match rendered_layout(&node_tree, &layout_cache, nodes, WrappedIndex(widget)) {
+            Some((layout, clips)) => {
+                clips.iter().all(|clip| layout.intersects(clip))
+                    && viewport.map_or(true, |viewport| layout.intersects(&viewport))
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the area that the given widget is clipped to when rendering.
+    ///
+    /// This is the intersection of every enclosing `Clip` region, positioned like
+    /// [`KayakRootContext::is_visible`] does, and starts out as the layout of the widget's root.
+    /// Returns `None` if the clip regions don't overlap, so nothing of the widget can be drawn, or
+    /// if the widget hasn't been laid out yet.
+    pub fn effective_clip(
+        &self,
+        widget: Entity,
+        nodes: &Query<&crate::node::Node>,
+    ) -> Option<Rect> {
+        let node_tree = self.tree.try_read().ok()?;
+        let layout_cache = self.layout_cache.try_read().ok()?;
+        let (_, clips) = rendered_layout(&node_tree, &layout_cache, nodes, WrappedIndex(widget))?;
+        let root = UpwardIterator::new(&node_tree, Some(WrappedIndex(widget)), true).last()?;
+        let root_layout = *layout_cache.rect.get(&root)?;
+        clips
+            .iter()
+            .try_fold(root_layout, |clip, region| clip.intersection(region))
+    }
+
+    /// Returns the area covered by all of the descendants of the given widget.
+    ///
+    /// This is the union of their layouts, including the transforms applied below the widget, and
+    /// can be larger than the widget itself. Clipping and scrolling are ignored, so a scroll
+    /// container can use it to find its total content size. Returns `None` if no descendant has
+    /// been laid out.
+    pub fn content_bounds(
+        &self,
+        widget: Entity,
+        nodes: &Query<&crate::node::Node>,
+    ) -> Option<Rect> {
+        let node_tree = self.tree.try_read().ok()?;
+        let layout_cache = self.layout_cache.try_read().ok()?;
+
+        let mut bounds: Option<Rect> = None;
+        let mut stack = node_tree
+            .children
+            .get(&WrappedIndex(widget))
+            .map(|children| {
+                children
+                    .iter()
+                    .map(|child| (*child, KTransform::default()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        while let Some((index, transform)) = stack.pop() {
+            let layout = match layout_cache.rect.get(&index) {
+                Some(layout) => *layout,
+                None => continue,
+            };
+            let transform = match nodes
+                .get(index.0)
+                .map(|node| node.resolved_styles.transform.clone())
+            {
+                Ok(StyleProp::Value(node_transform)) => {
+                    node_transform.about_center(&layout).then(&transform)
+                }
+                _ => transform,
+            };
+            let layout = transform.apply(layout);
+            bounds = Some(bounds.map_or(layout, |bounds| bounds.union(&layout)));
+
+            if let Some(children) = node_tree.children.get(&index) {
+                stack.extend(children.iter().map(|child| (*child, transform)));
+            }
+        }
+        bounds
+    }
+
+    /// Builds a tree describing the visible widgets to assistive technologies, such as screen readers.
+    ///
+    /// Each widget's role comes from its `role` style, or is derived from its render command.
+    /// Widgets that are hidden, presentational or render nothing (`RenderCommand::Empty`) are
+    /// left out, and their children take their place. Widgets without text of their own are
+    /// labelled using the text of their children.
+    pub fn build_accessibility_tree(&self, nodes: &Query<&crate::node::Node>) -> AccessNodeTree {
+        let (node_tree, layout_cache, focus_tree) = match (
+            self.tree.try_read(),
+            self.layout_cache.try_read(),
+            self.focus_tree.try_read(),
+        ) {
+            (Ok(node_tree), Ok(layout_cache), Ok(focus_tree)) => {
+                (node_tree, layout_cache, focus_tree)
+            }
+            _ => return AccessNodeTree::default(),
         };
-        // render_primitives.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
 
-        // render_primitives.iter().enumerate().for_each(|(index, p)| {
-        //     log::info!("Name: {:?}, Z: {:?}", p.to_string(), index);
-        // });
+        AccessNodeTree {
+            roots: node_tree
+                .visible_roots()
+                .flat_map(|root| {
+                    recurse_node_tree_to_build_access_nodes(
+                        &node_tree,
+                        &layout_cache,
+                        &focus_tree,
+                        nodes,
+                        root,
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Generates a flat list of widget render commands sorted by tree order.
+    /// There is no need to call this unless you are implementing your own custom renderer.
+    ///
+    /// The list is in paint order: each widget's shadow, then the widget itself, then its
+    /// children sorted by their `z_index` style (keeping tree order between equal ones). After
+    /// each child, the parent's clip region is emitted again to undo any clip the child set.
+    /// Every primitive's layout z-index is its position in this list, offset by the context's
+    /// z base, so clips and content never share a z-index. The z-indices stored in the layout
+    /// cache are separate, and are only used for picking the widget under the pointer.
+    pub fn build_render_primitives(
+        &self,
+        nodes: &Query<&crate::node::Node>,
+        widget_names: &Query<&WidgetName>,
+    ) -> Vec<RenderPrimitive> {
+        self.build_render_primitives_in(nodes, widget_names, None)
+    }
+
+    /// Sets a function that can modify every primitive built by
+    /// [`build_render_primitives`](Self::build_render_primitives) before it's drawn.
+    ///
+    /// This can be used to post-process the whole UI, such as tinting every color for a night
+    /// mode. The filter is given each primitive in paint order. It can't add, remove or reorder
+    /// primitives, and any change it makes to a primitive's z-index is overwritten.
+    pub fn set_primitive_filter(
+        &self,
+        filter: impl FnMut(&mut RenderPrimitive) + Send + Sync + 'static,
+    ) {
+        if let Ok(mut primitive_filter) = self.primitive_filter.try_write() {
+            *primitive_filter = Some(Box::new(filter));
+        }
+    }
+
+    /// Removes the filter set by [`set_primitive_filter`](Self::set_primitive_filter).
+    pub fn clear_primitive_filter(&self) {
+        if let Ok(mut primitive_filter) = self.primitive_filter.try_write() {
+            *primitive_filter = None;
+        }
+    }
+
+    /// Generates a flat list of widget render commands, skipping any that are outside the viewport.
+    ///
+    /// Nodes are tested against the viewport after scrolling is applied. Clip primitives are
+    /// always kept so that visible descendants are still clipped correctly. This is useful for
+    /// large scrollable content, such as a virtual list, where only a few rows are on screen.
+    pub fn build_render_primitives_culled(
+        &self,
+        nodes: &Query<&crate::node::Node>,
+        widget_names: &Query<&WidgetName>,
+        viewport: Rect,
+    ) -> Vec<RenderPrimitive> {
+        self.build_render_primitives_in(nodes, widget_names, Some(viewport))
+    }
+
+    fn build_render_primitives_in(
+        &self,
+        nodes: &Query<&crate::node::Node>,
+        widget_names: &Query<&WidgetName>,
+        viewport: Option<Rect>,
+    ) -> Vec<RenderPrimitive> {
+        let node_tree = self.tree.try_read();
+        if node_tree.is_err() {
+            return vec![];
+        }
+
+        let node_tree = node_tree.unwrap();
+
+        if node_tree.root_node.is_none() {
+            return vec![];
+        }
+
+        let mut render_primitives = Vec::new();
+        if let Ok(mut layout_cache) = self.layout_cache.try_write() {
+            for root in node_tree.visible_roots() {
+                // Reset any clip left over from the layers drawn below.
+                if node_tree.root_node != Some(root) {
+                    if let Some(root_layout) = layout_cache.rect.get(&root) {
+                        render_primitives.push(RenderPrimitive::Clip {
+                            layout: *root_layout,
+                            scroll: None,
+                            radius: Corner::default(),
+                        });
+                    }
+                }
+                render_primitives.extend(recurse_node_tree_to_build_primitives(
+                    &node_tree,
+                    &mut layout_cache,
+                    nodes,
+                    widget_names,
+                    root,
+                    root,
+                    self.z_base,
+                    RenderPrimitive::Empty,
+                    (0.0, 0.0),
+                    1.0,
+                    KTransform::default(),
+                    viewport,
+                ));
+            }
+        }
+        dedup_clips(&mut render_primitives);
+        if let Ok(mut primitive_filter) = self.primitive_filter.try_write() {
+            if let Some(primitive_filter) = primitive_filter.as_mut() {
+                render_primitives.iter_mut().for_each(primitive_filter);
+            }
+        }
+        assign_paint_order(&mut render_primitives, self.z_base);
 
-        // dbg!(&render_primitives
-        //     .iter()
-        //     .map(|a| (a.1.to_string(), a.0))
-        //     .collect::<Vec<_>>());
+        if let Ok(mut stats) = self.stats.try_write() {
+            stats.primitives_emitted = render_primitives.len();
+        }
 
         render_primitives.into_iter().collect()
     }
+
+    /// Updates the hovered widget to the topmost widget under the given pointer position.
+    ///
+    /// Pass `None` when the pointer leaves the window. Returns the change in hover, if any.
+    /// Widgets that started or stopped being hovered (see [`KayakRootContext::is_hovered`]) are
+    /// marked dirty, so styles that depend on hover are resolved again.
+    ///
+    /// This is called with the cursor position every frame, so it's only needed for custom input.
+    pub fn set_pointer(
+        &mut self,
+        commands: &mut Commands,
+        nodes: &Query<&crate::node::Node>,
+        point: Option<(f32, f32)>,
+    ) -> Option<HoverChange> {
+        let hovered = point
+            .and_then(|point| self.widget_at(nodes, point))
+            .map(WrappedIndex);
+        if hovered == self.hovered {
+            return None;
+        }
+        let previous = std::mem::replace(&mut self.hovered, hovered);
+
+        if let Ok(node_tree) = self.tree.try_read() {
+            let ancestors = |index: Option<WrappedIndex>| {
+                UpwardIterator::new(&node_tree, index, true).collect::<HashSet<_>>()
+            };
+            let previous_chain = ancestors(previous);
+            let hovered_chain = ancestors(hovered);
+            for index in previous_chain.symmetric_difference(&hovered_chain) {
+                if let Some(mut entity_commands) = commands.get_entity(index.0) {
+                    entity_commands.insert(DirtyNode);
+                }
+            }
+        }
+
+        Some(HoverChange {
+            left: previous.map(|index| index.0),
+            entered: hovered.map(|index| index.0),
+        })
+    }
+
+    /// Sets whether the pointer is pressed, which makes the hovered widgets active.
+    ///
+    /// Hovered widgets are marked dirty when this changes, so [`PseudoStyles`] are resolved again.
+    /// This is called with the mouse button state every frame, so it's only needed for custom input.
+    pub fn set_pointer_pressed(&mut self, commands: &mut Commands, pressed: bool) {
+        if pressed == self.pointer_pressed {
+            return;
+        }
+        self.pointer_pressed = pressed;
+
+        if let Ok(node_tree) = self.tree.try_read() {
+            for index in UpwardIterator::new(&node_tree, self.hovered, true) {
+                if let Some(mut entity_commands) = commands.get_entity(index.0) {
+                    entity_commands.insert(DirtyNode);
+                }
+            }
+        }
+    }
+
+    /// Marks the nodes of the given widgets dirty, so they're rebuilt on the next render.
+    ///
+    /// The widgets themselves aren't re-rendered, only their nodes are rebuilt. This lets changes
+    /// that are tracked outside of the widgets, such as a diff of external data, update just the
+    /// widgets they affect. Widgets that aren't in the tree are ignored.
+    ///
+    /// Returns false if the tree is in use elsewhere, in which case nothing is marked.
+    pub fn mark_dirty(
+        &self,
+        commands: &mut Commands,
+        widgets: impl IntoIterator<Item = Entity>,
+    ) -> bool {
+        let node_tree = match self.tree.try_read() {
+            Ok(node_tree) => node_tree,
+            Err(_) => {
+                log::warn!("Couldn't mark widgets dirty, since the tree is in use elsewhere");
+                return false;
+            }
+        };
+        for widget in widgets {
+            if !node_tree.contains(WrappedIndex(widget)) {
+                continue;
+            }
+            if let Some(mut entity_commands) = commands.get_entity(widget) {
+                entity_commands.insert(DirtyNode);
+            }
+        }
+        true
+    }
+
+    /// Returns the interaction state of the given widget, used to resolve its [`PseudoStyles`].
+    pub fn pseudo_state(&self, widget: Entity) -> PseudoState {
+        let hovered = self.is_hovered(widget);
+        PseudoState {
+            hovered,
+            focused: self.get_current_focus() == Some(widget),
+            active: hovered && self.pointer_pressed,
+        }
+    }
+
+    /// Returns the widgets that were added to and removed from the tree by the last update.
+    pub fn tree_delta(&self) -> &TreeDelta {
+        &self.tree_delta
+    }
+
+    /// Returns how many times the node of the given widget has been rebuilt.
+    ///
+    /// A node is rebuilt each time the widget is marked dirty, such as when it re-renders, but
+    /// only rebuilds that change the node are counted. Widgets that rebuild far more often than
+    /// the rest can point to props that change every frame without need.
+    pub fn get_generation(&self, widget: Entity) -> u64 {
+        self.generations
+            .get(&WrappedIndex(widget))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Records that the node of the given widget was rebuilt.
+    pub(crate) fn bump_generation(&mut self, id: WrappedIndex) {
+        *self.generations.entry(id).or_default() += 1;
+    }
+
+    /// Writes the current layout of every widget back into its styles as pixels.
+    ///
+    /// From the next render on, each widget is positioned at its current place relative to its
+    /// parent with its current size, so nothing is computed from relative sizes anymore. This is
+    /// useful for static UIs, or for freezing a responsive layout at a breakpoint. The resolved
+    /// styles of the nodes can then also be serialized as a concrete layout. Widgets added
+    /// afterwards are laid out as usual.
+    pub fn freeze_layout(&mut self) {
+        let (tree, layout_cache) = match (self.tree.try_read(), self.layout_cache.try_read()) {
+            (Ok(tree), Ok(layout_cache)) => (tree, layout_cache),
+            _ => return,
+        };
+        let mut frozen_layouts = HashMap::default();
+        for index in tree.down_iter() {
+            let layout = match layout_cache.rect.get(&index) {
+                Some(layout) => *layout,
+                None => continue,
+            };
+            let parent_layout = tree
+                .get_parent(index)
+                .and_then(|parent| layout_cache.rect.get(&parent))
+                .copied()
+                .unwrap_or_default();
+            frozen_layouts.insert(
+                index,
+                Rect {
+                    posx: layout.posx - parent_layout.posx,
+                    posy: layout.posy - parent_layout.posy,
+                    ..layout
+                },
+            );
+        }
+        drop((tree, layout_cache));
+
+        self.frozen_layouts = frozen_layouts;
+        self.default_styles_changed = true;
+    }
+
+    /// Restores the styles replaced by [`KayakRootContext::freeze_layout`].
+    ///
+    /// Nodes are resolved again from the styles of their widgets, which were never changed.
+    pub fn unfreeze_layout(&mut self) {
+        if !self.frozen_layouts.is_empty() {
+            self.frozen_layouts.clear();
+            self.default_styles_changed = true;
+        }
+    }
+
+    /// Returns true if the layout was frozen with [`KayakRootContext::freeze_layout`].
+    pub fn is_layout_frozen(&self) -> bool {
+        !self.frozen_layouts.is_empty()
+    }
+
+    /// Pauses or resumes updating a widget and all of its descendants.
+    ///
+    /// While a subtree is inactive, none of its widgets re-render, no matter what changes. This
+    /// saves work for widgets that aren't shown, such as the panels of all but the selected tab.
+    /// They keep their nodes and layout, so they should be hidden by other means. Once
+    /// reactivated, every widget in the subtree re-renders, so changes missed while it was
+    /// inactive are picked up.
+    pub fn set_subtree_active(&mut self, commands: &mut Commands, widget: Entity, active: bool) {
+        let index = WrappedIndex(widget);
+        if !active {
+            self.inactive_subtrees.insert(index);
+            return;
+        }
+        if !self.inactive_subtrees.remove(&index) {
+            return;
+        }
+
+        if let Ok(tree) = self.tree.try_read() {
+            for child in tree.down_iter_at(index, true) {
+                if let Some(mut entity_commands) = commands.get_entity(child.0) {
+                    entity_commands.insert(Mounted);
+                }
+            }
+        }
+    }
+
+    /// Returns false if the widget is within a subtree paused by
+    /// [`KayakRootContext::set_subtree_active`].
+    pub fn is_active(&self, widget: Entity) -> bool {
+        if self.inactive_subtrees.is_empty() {
+            return true;
+        }
+        match self.tree.try_read() {
+            Ok(tree) => !is_within(&tree, &self.inactive_subtrees, WrappedIndex(widget)),
+            Err(_) => true,
+        }
+    }
+
+    /// Collects every widget within a subtree paused by [`KayakRootContext::set_subtree_active`].
+    ///
+    /// This walks each paused subtree once, so checking many widgets against it is cheaper than
+    /// calling [`KayakRootContext::is_active`] for each of them.
+    pub(crate) fn inactive_widgets(&self) -> HashSet<WrappedIndex> {
+        let mut inactive = HashSet::default();
+        if let Ok(tree) = self.tree.try_read() {
+            for root in self.inactive_subtrees.iter() {
+                if tree.contains(*root) {
+                    inactive.extend(tree.down_iter_at(*root, true));
+                }
+            }
+        }
+        inactive
+    }
+
+    /// Hides or shows a widget and all of its descendants.
+    ///
+    /// Unlike giving a widget an empty render command, a hidden subtree costs nothing: its
+    /// nodes aren't rebuilt, laid out or turned into render primitives, and it can't be picked
+    /// or focused by the pointer. The widgets themselves are kept, along with their state, so
+    /// showing the subtree again doesn't remount anything.
+    pub fn set_hidden(&mut self, commands: &mut Commands, widget: Entity, hidden: bool) {
+        let index = WrappedIndex(widget);
+        let mut tree = match self.tree.try_write() {
+            Ok(tree) => tree,
+            Err(_) => return,
+        };
+        let changed = if hidden {
+            tree.hidden.insert(index)
+        } else {
+            tree.hidden.remove(&index)
+        };
+        if !changed {
+            return;
+        }
+
+        // The siblings move into or out of the space the subtree takes up
+        let parent = tree.get_parent(index).unwrap_or(index);
+        self.dirty_layout_nodes.insert(parent);
+        if hidden {
+            // The subtree isn't laid out while hidden, so its old layouts would go stale
+            if let Ok(mut layout_cache) = self.layout_cache.try_write() {
+                for child in tree.down_iter_at(index, true) {
+                    layout_cache.remove(child);
+                }
+            }
+        } else {
+            // Nodes aren't rebuilt while hidden, so pick up any changes that were missed
+            for child in tree.down_iter_at(index, true) {
+                if let Some(mut entity_commands) = commands.get_entity(child.0) {
+                    entity_commands.insert(DirtyNode);
+                }
+            }
+        }
+    }
+
+    /// Returns true if the widget is within a subtree hidden by
+    /// [`KayakRootContext::set_hidden`].
+    pub fn is_hidden(&self, widget: Entity) -> bool {
+        match self.tree.try_read() {
+            Ok(tree) => is_within(&tree, &tree.hidden, WrappedIndex(widget)),
+            Err(_) => false,
+        }
+    }
+
+    /// Returns the topmost widget under the pointer, if any.
+    pub fn hovered(&self) -> Option<Entity> {
+        self.hovered.map(|index| index.0)
+    }
+
+    /// Checks if the pointer is over the given widget or any of its descendants.
+    pub fn is_hovered(&self, widget: Entity) -> bool {
+        let hovered = match self.hovered {
+            Some(hovered) => hovered,
+            None => return false,
+        };
+        match self.tree.try_read() {
+            Ok(node_tree) => {
+                UpwardIterator::new(&node_tree, Some(hovered), true).any(|index| index.0 == widget)
+            }
+            Err(_) => hovered.0 == widget,
+        }
+    }
+
+    /// Finds the cursor icon of the topmost widget at the given point.
+    ///
+    /// Widgets with a [`CursorIcon::Default`] cursor fall through to the cursor of their
+    /// closest ancestor that sets one. Returns `None` if there's no widget at the point.
+    pub fn cursor_at(
+        &self,
+        nodes: &Query<&crate::node::Node>,
+        point: (f32, f32),
+    ) -> Option<CursorIcon> {
+        let widget = self.widget_at(nodes, point)?;
+        let node_tree = self.tree.try_read().ok()?;
+        let cursor = UpwardIterator::new(&node_tree, Some(WrappedIndex(widget)), true)
+            .filter_map(|index| nodes.get(index.0).ok())
+            .map(|node| node.resolved_styles.cursor.resolve_or_default().0)
+            .find(|cursor| *cursor != CursorIcon::Default)
+            .unwrap_or(CursorIcon::Default);
+        Some(cursor)
+    }
+
+    /// Focuses the focusable widget at the given point, returning it if it was focused.
+    ///
+    /// The topmost widget at the point is often a non-focusable part of a focusable widget,
+    /// such as the label of a button, so its closest focusable ancestor (or itself) is the one
+    /// that gets focus. Widgets are hit where they're drawn, like with
+    /// [`KayakRootContext::widget_at`]. Returns `None` if there's no widget at the point, or if
+    /// the widget can't be focused because it's outside of the active focus trap.
+    pub fn focus_at(&self, nodes: &Query<&crate::node::Node>, point: (f32, f32)) -> Option<Entity> {
+        let widget = self.widget_at(nodes, point)?;
+        let node_tree = self.tree.try_read().ok()?;
+        let mut focus_tree = self.focus_tree.try_write().ok()?;
+        let focusable = UpwardIterator::new(&node_tree, Some(WrappedIndex(widget)), true)
+            .find(|index| focus_tree.contains(*index))?;
+        focus_tree.focus(focusable);
+        (focus_tree.current() == Some(focusable)).then_some(focusable.0)
+    }
+
+    /// Measures the size of a widget against the current layout of its parent.
+    ///
+    /// This runs the same text measurement used when building render primitives, without
+    /// waiting for a full render and layout pass. It's useful for making content-aware
+    /// decisions, such as only showing a "show more" button when text would overflow.
+    /// The widget's styles are resolved the same way as when building its node, so sizes in
+    /// any unit that converts to pixels (or a percentage) are used directly, anything else
+    /// falls back to the widget's measured text or its last computed layout.
+    ///
+    /// Text is measured with the given fonts, usually the [`LoadedFonts`](crate::prelude::LoadedFonts),
+    /// or the [`HeadlessAssets`](crate::prelude::HeadlessAssets) when running without a renderer.
+    pub fn measure(
+        &self,
+        widget: Entity,
+        nodes: &Query<&crate::node::Node>,
+        fonts: &dyn FontProvider,
+        all_styles_query: &Query<&ComputedStyles>,
+        pseudo_styles_query: &Query<&PseudoStyles>,
+    ) -> Option<(f32, f32)> {
+        let id = WrappedIndex(widget);
+        let parent = self.tree.try_read().ok()?.get_parent(id);
+
+        let parent_styles = parent
+            .and_then(|parent| nodes.get(parent.0).ok())
+            .map(|parent_node| &parent_node.resolved_styles)
+            .unwrap_or(&self.default_styles);
+        let (styles, _) = resolve_styles(
+            self,
+            id,
+            &all_styles_query.get(widget).ok()?.0,
+            pseudo_styles_query.get(widget).ok(),
+            parent,
+            parent_styles,
+        );
+
+        let mut render_primitive = RenderPrimitive::from(&styles);
+        let measurement = measure_text(
+            self,
+            fonts,
+            all_styles_query,
+            id,
+            &styles,
+            &mut render_primitive,
+        )
+        .map(|(_, measurement)| measurement)
+        .or_else(|| {
+            self.get_layout(&id)
+                .map(|layout| (layout.width, layout.height))
+        });
+
+        let parent_layout = parent.and_then(|parent| self.get_layout(&parent));
+        let resolve = |units: &StyleProp<Units>,
+                       parent_size: Option<f32>,
+                       measured: Option<f32>|
+         -> Option<f32> {
+            match units {
+                StyleProp::Value(Units::Pixels(size)) => Some(*size),
+                StyleProp::Value(Units::Percentage(percentage)) => {
+                    parent_size.map(|parent_size| parent_size * percentage / 100.0)
+                }
+                _ => measured,
+            }
+        };
+
+        Some((
+            resolve(
+                &styles.width,
+                parent_layout.map(|layout| layout.width),
+                measurement.map(|size| size.0),
+            )?,
+            resolve(
+                &styles.height,
+                parent_layout.map(|layout| layout.height),
+                measurement.map(|size| size.1),
+            )?,
+        ))
+    }
+
+    /// Creates a serializable snapshot of the tree, along with each node's resolved primitive and layout.
+    pub fn dump_tree(
+        &self,
+        nodes: &Query<&crate::node::Node>,
+        widget_names: &Query<&WidgetName>,
+    ) -> TreeSnapshot {
+        let mut snapshot = TreeSnapshot::default();
+        if let (Ok(tree), Ok(layout_cache)) = (self.tree.try_read(), self.layout_cache.try_read()) {
+            for index in tree.down_iter() {
+                let layout = layout_cache.rect.get(&index).copied().unwrap_or_default();
+                snapshot.nodes.push(NodeSnapshot {
+                    index: index.0.index(),
+                    name: widget_names
+                        .get(index.0)
+                        .map(|name| name.0.clone())
+                        .unwrap_or_default(),
+                    depth: UpwardIterator::new(&tree, Some(index), false).count(),
+                    render_command: nodes
+                        .get(index.0)
+                        .map(|node| node.primitive.to_string())
+                        .unwrap_or_else(|_| String::from("None")),
+                    posx: layout.posx,
+                    posy: layout.posy,
+                    width: layout.width,
+                    height: layout.height,
+                });
+            }
+        }
+        snapshot
+    }
+
+    /// Visits every node in the order it is rendered in, along with its rendered layout.
+    ///
+    /// This follows the same z-ordering and scrolling as
+    /// [`KayakRootContext::build_render_primitives`], allowing render primitives to be
+    /// correlated back to the widgets that produced them.
+    pub fn for_each_in_render_order(
+        &self,
+        nodes: &Query<&crate::node::Node>,
+        mut f: impl FnMut(Entity, &crate::node::Node, &Rect),
+    ) {
+        let node_tree = match self.tree.try_read() {
+            Ok(node_tree) => node_tree,
+            Err(_) => return,
+        };
+        if let Ok(layout_cache) = self.layout_cache.try_read() {
+            for root in node_tree.visible_roots() {
+                recurse_node_tree_in_render_order(
+                    &node_tree,
+                    &layout_cache,
+                    nodes,
+                    root,
+                    self.z_base,
+                    (0.0, 0.0),
+                    &mut f,
+                );
+            }
+        }
+    }
+
+    /// Generates a flat list of outlines for every widget's layout, colored by tree depth.
+    ///
+    /// The outlines are meant to be drawn after the primitives from
+    /// [`KayakRootContext::build_render_primitives`] so they sit above all content. Useful
+    /// for inspecting the layout of a UI.
+    pub fn build_debug_primitives(
+        &self,
+        nodes: &Query<&crate::node::Node>,
+    ) -> Vec<RenderPrimitive> {
+        let node_tree = match self.tree.try_read() {
+            Ok(node_tree) => node_tree,
+            Err(_) => return vec![],
+        };
+        let layout_cache = match self.layout_cache.try_read() {
+            Ok(layout_cache) => layout_cache,
+            Err(_) => return vec![],
+        };
+
+        let mut render_primitives = Vec::new();
+        for root in node_tree.visible_roots() {
+            // Reset any clip left over from the regular render primitives.
+            if let Some(root_layout) = layout_cache.rect.get(&root) {
+                render_primitives.push(RenderPrimitive::Clip {
+                    layout: *root_layout,
+                    scroll: None,
+                    radius: Corner::default(),
+                });
+            }
+            recurse_node_tree_to_build_debug_primitives(
+                &node_tree,
+                &layout_cache,
+                nodes,
+                root,
+                0,
+                (0.0, 0.0),
+                &mut render_primitives,
+            );
+        }
+        render_primitives
+    }
+}
+
+/// Checks if the node is one of the given subtree roots or one of their descendants
+pub(crate) fn is_within(
+    tree: &Tree,
+    subtrees: &HashSet<WrappedIndex>,
+    index: WrappedIndex,
+) -> bool {
+    !subtrees.is_empty()
+        && UpwardIterator::new(tree, Some(index), true).any(|ancestor| subtrees.contains(&ancestor))
 }
 
 fn recurse_node_tree_to_build_primitives(
@@ -335,13 +1948,22 @@ fn recurse_node_tree_to_build_primitives(
     nodes: &Query<&crate::node::Node>,
     widget_names: &Query<&WidgetName>,
     current_node: WrappedIndex,
+    layer_root: WrappedIndex,
     main_z_index: f32,
     mut prev_clip: RenderPrimitive,
+    scroll_offset: (f32, f32),
+    opacity: f32,
+    transform: KTransform,
+    viewport: Option<Rect>,
 ) -> Vec<RenderPrimitive> {
     let mut render_primitives = Vec::new();
     if let Ok(node) = nodes.get(current_node.0) {
         let mut render_primitive = node.primitive.clone();
         let mut new_z_index = main_z_index;
+        // Opacity cascades down to every descendant.
+        let opacity = opacity * node.resolved_styles.opacity.resolve_or(1.0).clamp(0.0, 1.0);
+        render_primitive.apply_opacity(opacity);
+        let mut transform = transform;
 
         let layout = if let Some(layout) = layout_cache.rect.get_mut(&current_node) {
             log::trace!(
@@ -355,8 +1977,18 @@ fn recurse_node_tree_to_build_primitives(
             new_z_index += if node.z <= 0.0 { 0.0 } else { node.z };
 
             layout.z_index = new_z_index;
-            render_primitive.set_layout(*layout);
-            *layout
+            // Scrolling only affects what is rendered, the cached layout stays untouched.
+            let mut layout = *layout;
+            layout.posx += scroll_offset.0;
+            layout.posy += scroll_offset.1;
+            // Transforms cascade down to every descendant, without touching the cached layout.
+            if let StyleProp::Value(node_transform) = node.resolved_styles.transform {
+                transform = node_transform.about_center(&layout).then(&transform);
+            }
+            let layout = transform.apply(layout);
+            render_primitive.set_layout(layout);
+            render_primitive.set_scale(transform.scale);
+            layout
         } else {
             log::warn!(
                 "No layout for node: {}-{}",
@@ -378,7 +2010,7 @@ fn recurse_node_tree_to_build_primitives(
                     layout,
                 );
             }
-            RenderPrimitive::Clip { layout } => {
+            RenderPrimitive::Clip { layout, .. } | RenderPrimitive::ClipMask { layout, .. } => {
                 log::trace!(
                     "Clip node: {}-{} is equal to: {:?}",
                     widget_names.get(current_node.0).unwrap().0,
@@ -397,10 +2029,80 @@ fn recurse_node_tree_to_build_primitives(
             _ => {}
         }
 
-        render_primitives.push(render_primitive.clone());
+        // Widgets with visible overflow escape the clip regions of their ancestors, up to the
+        // root of the layer they're in.
+        if matches!(
+            node.resolved_styles.overflow,
+            StyleProp::Value(Overflow::Visible)
+        ) && prev_clip.is_clip()
+        {
+            if let Some(root_layout) = layout_cache.rect.get(&layer_root) {
+                render_primitives.push(RenderPrimitive::Clip {
+                    layout: *root_layout,
+                    scroll: None,
+                    radius: Corner::default(),
+                });
+            }
+            prev_clip = RenderPrimitive::Empty;
+        }
 
-        let new_prev_clip = if matches!(render_primitive, RenderPrimitive::Clip { .. }) {
-            render_primitive.clone()
+        // Cull anything that is entirely outside of the current clip region.
+        let is_culled = match &prev_clip {
+            RenderPrimitive::Clip {
+                layout: clip_layout,
+                ..
+            }
+            | RenderPrimitive::ClipMask {
+                layout: clip_layout,
+                ..
+            } => {
+                !render_primitive.is_clip()
+                    && !matches!(render_primitive, RenderPrimitive::Empty)
+                    && !layout.intersects(clip_layout)
+            }
+            _ => false,
+        };
+        // As well as anything outside of the viewport, when one is given.
+        let is_culled = is_culled
+            || viewport.map_or(false, |viewport| {
+                !render_primitive.is_clip()
+                    && !matches!(render_primitive, RenderPrimitive::Empty)
+                    && !layout.intersects(&viewport)
+            });
+
+        if !is_culled {
+            render_primitive.round_to_clip(&prev_clip);
+            // Shadows are drawn just below their owning node.
+            if let Some(mut box_shadow) = RenderPrimitive::box_shadow(&node.resolved_styles, layout)
+            {
+                box_shadow.apply_opacity(opacity);
+                render_primitives.push(box_shadow);
+            }
+            // Backdrops blur everything drawn before them, so they go between the shadow and the
+            // node's own content.
+            if let Some(mut backdrop) = RenderPrimitive::backdrop(&node.resolved_styles, layout) {
+                backdrop.apply_opacity(opacity);
+                render_primitives.push(backdrop);
+            }
+            render_primitives.push(render_primitive.clone());
+        } else {
+            log::trace!(
+                "Culled node: {}-{}",
+                widget_names.get(current_node.0).unwrap().0,
+                current_node.0.index()
+            );
+        }
+
+        let children_scroll_offset = match &render_primitive {
+            RenderPrimitive::Clip {
+                scroll: Some(scroll),
+                ..
+            } => (scroll_offset.0 + scroll.0, scroll_offset.1 + scroll.1),
+            _ => scroll_offset,
+        };
+
+        let new_prev_clip = if render_primitive.is_clip() {
+            render_primitive.clone()
         } else {
             prev_clip
         };
@@ -409,20 +2111,24 @@ fn recurse_node_tree_to_build_primitives(
         if node_tree.children.contains_key(&current_node) {
             let z = 1.0f32;
             let mut children_primitives = Vec::new();
-            for child in node_tree.children.get(&current_node).unwrap() {
-                // main_z_index += 1.0;
+            for child in node_tree.visible_children(current_node) {
                 let mut children_p = recurse_node_tree_to_build_primitives(
                     node_tree,
                     layout_cache,
                     nodes,
                     widget_names,
-                    *child,
+                    child,
+                    layer_root,
                     main_z_index + if node.z < 0.0 { 0.0 } else { node.z } + z,
                     new_prev_clip.clone(),
+                    children_scroll_offset,
+                    opacity,
+                    transform,
+                    viewport,
                 );
 
                 // Between each child node we need to reset the clip.
-                if matches!(prev_clip, RenderPrimitive::Clip { .. }) {
+                if prev_clip.is_clip() {
                     children_p.push(prev_clip.clone());
                 }
 
@@ -471,6 +2177,311 @@ fn recurse_node_tree_to_build_primitives(
     render_primitives
 }
 
+fn recurse_node_tree_in_render_order(
+    node_tree: &Tree,
+    layout_cache: &LayoutCache,
+    nodes: &Query<&crate::node::Node>,
+    current_node: WrappedIndex,
+    main_z_index: f32,
+    scroll_offset: (f32, f32),
+    f: &mut impl FnMut(Entity, &crate::node::Node, &Rect),
+) {
+    let node = match nodes.get(current_node.0) {
+        Ok(node) => node,
+        Err(_) => return,
+    };
+    let node_z = if node.z < 0.0 { 0.0 } else { node.z };
+
+    if let Some(layout) = layout_cache.rect.get(&current_node) {
+        let layout = Rect {
+            posx: layout.posx + scroll_offset.0,
+            posy: layout.posy + scroll_offset.1,
+            z_index: main_z_index + node_z,
+            ..*layout
+        };
+        f(current_node.0, node, &layout);
+    }
+
+    let children_scroll_offset = match &node.primitive {
+        RenderPrimitive::Clip {
+            scroll: Some(scroll),
+            ..
+        } => (scroll_offset.0 + scroll.0, scroll_offset.1 + scroll.1),
+        _ => scroll_offset,
+    };
+
+    let mut children = node_tree.visible_children(current_node).collect::<Vec<_>>();
+    children.sort_by(|a, b| {
+        let a = nodes.get(a.0).map(|node| node.z.max(0.0)).unwrap_or(0.0);
+        let b = nodes.get(b.0).map(|node| node.z.max(0.0)).unwrap_or(0.0);
+        a.partial_cmp(&b).unwrap()
+    });
+    for child in children {
+        recurse_node_tree_in_render_order(
+            node_tree,
+            layout_cache,
+            nodes,
+            child,
+            main_z_index + node_z + 1.0,
+            children_scroll_offset,
+            f,
+        );
+    }
+}
+
+fn recurse_node_tree_to_build_debug_primitives(
+    node_tree: &Tree,
+    layout_cache: &LayoutCache,
+    nodes: &Query<&crate::node::Node>,
+    current_node: WrappedIndex,
+    depth: usize,
+    scroll_offset: (f32, f32),
+    render_primitives: &mut Vec<RenderPrimitive>,
+) {
+    let mut children_scroll_offset = scroll_offset;
+    if let Some(layout) = layout_cache.rect.get(&current_node) {
+        let mut layout = *layout;
+        layout.posx += scroll_offset.0;
+        layout.posy += scroll_offset.1;
+        render_primitives.push(RenderPrimitive::Quad {
+            layout,
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.0),
+            border_color: Color::hsl((depth as f32 * 47.0) % 360.0, 0.8, 0.6),
+            border: Edge::all(1.0),
+            border_radius: Corner::all(0.0),
+        });
+    }
+
+    if let Ok(node) = nodes.get(current_node.0) {
+        if let RenderPrimitive::Clip {
+            scroll: Some(scroll),
+            ..
+        } = &node.primitive
+        {
+            children_scroll_offset = (scroll_offset.0 + scroll.0, scroll_offset.1 + scroll.1);
+        }
+    }
+
+    // Match the sibling order used by the regular render primitives.
+    let mut children = node_tree.visible_children(current_node).collect::<Vec<_>>();
+    children.sort_by(|a, b| {
+        let a = nodes.get(a.0).map(|node| node.z.max(0.0)).unwrap_or(0.0);
+        let b = nodes.get(b.0).map(|node| node.z.max(0.0)).unwrap_or(0.0);
+        a.partial_cmp(&b).unwrap()
+    });
+    for child in children.iter() {
+        recurse_node_tree_to_build_debug_primitives(
+            node_tree,
+            layout_cache,
+            nodes,
+            *child,
+            depth + 1,
+            children_scroll_offset,
+            render_primitives,
+        );
+    }
+}
+
+/// The scroll offsets, transforms and clip regions that the ancestors of a node apply to it when
+/// rendering.
+#[derive(Debug, Clone, Default)]
+struct RenderedAncestors {
+    scroll_offset: (f32, f32),
+    transform: KTransform,
+    /// The clip regions along with the radius of their corners
+    clips: Vec<(Rect, Corner<f32>)>,
+}
+
+impl RenderedAncestors {
+    /// Positions the layout of a node the same way it's drawn, picking up its own transform and
+    /// escaping the clip regions above it when its overflow is visible.
+    fn place(&mut self, node: &crate::node::Node, layout: Rect) -> Rect {
+        let layout = Rect {
+            posx: layout.posx + self.scroll_offset.0,
+            posy: layout.posy + self.scroll_offset.1,
+            ..layout
+        };
+        if let StyleProp::Value(node_transform) = node.resolved_styles.transform {
+            self.transform = node_transform.about_center(&layout).then(&self.transform);
+        }
+        if matches!(
+            node.resolved_styles.overflow,
+            StyleProp::Value(Overflow::Visible)
+        ) {
+            self.clips.clear();
+        }
+        self.transform.apply(layout)
+    }
+
+    /// Adds the clip region and scroll offset that a placed node applies to its descendants.
+    fn enter(&mut self, node: &crate::node::Node, layout: Rect) {
+        match &node.primitive {
+            RenderPrimitive::Clip { scroll, radius, .. } => {
+                self.clips.push((layout, *radius));
+                if let Some(scroll) = scroll {
+                    self.scroll_offset.0 += scroll.0;
+                    self.scroll_offset.1 += scroll.1;
+                }
+            }
+            RenderPrimitive::ClipMask { .. } => self.clips.push((layout, Corner::default())),
+            _ => {}
+        }
+    }
+
+    /// Checks if the point is inside of every clip region
+    fn clips_contain(&self, point: &(f32, f32)) -> bool {
+        self.clips
+            .iter()
+            .all(|(clip, radius)| clip.contains_rounded(point, radius))
+    }
+}
+
+/// Returns the rendered layout of the given node, along with the clip regions it's drawn within.
+///
+/// This applies the scroll position and transforms of the node's ancestors, just like when
+/// rendering. Returns `None` if the node or one of its ancestors hasn't been laid out yet.
+fn rendered_layout(
+    node_tree: &Tree,
+    layout_cache: &LayoutCache,
+    nodes: &Query<&crate::node::Node>,
+    index: WrappedIndex,
+) -> Option<(Rect, Vec<Rect>)> {
+    if !node_tree.contains(index) {
+        return None;
+    }
+
+    let mut path = vec![index];
+    let mut current = index;
+    while let Some(parent) = node_tree.get_parent(current) {
+        path.push(parent);
+        current = parent;
+    }
+
+    let mut ancestors = RenderedAncestors::default();
+    for id in path.into_iter().rev() {
+        let (node, layout) = match (nodes.get(id.0), layout_cache.rect.get(&id)) {
+            (Ok(node), Some(layout)) => (node, *layout),
+            _ => return None,
+        };
+
+        let layout = ancestors.place(node, layout);
+        if id == index {
+            let clips = ancestors.clips.into_iter().map(|(clip, _)| clip).collect();
+            return Some((layout, clips));
+        }
+        ancestors.enter(node, layout);
+    }
+
+    None
+}
+
+/// Builds the access nodes for the given node, which is either a single node or, when the node
+/// itself is left out, the access nodes of its children.
+fn recurse_node_tree_to_build_access_nodes(
+    node_tree: &Tree,
+    layout_cache: &LayoutCache,
+    focus_tree: &FocusTree,
+    nodes: &Query<&crate::node::Node>,
+    current_node: WrappedIndex,
+) -> Vec<AccessNode> {
+    let mut children = Vec::new();
+    for child in node_tree.visible_children(current_node) {
+        children.extend(recurse_node_tree_to_build_access_nodes(
+            node_tree,
+            layout_cache,
+            focus_tree,
+            nodes,
+            child,
+        ));
+    }
+
+    let node = match nodes.get(current_node.0) {
+        Ok(node) => node,
+        Err(_) => return children,
+    };
+    let render_command = node.resolved_styles.render_command.resolve();
+    let role = match node.resolved_styles.role {
+        StyleProp::Value(role) => role,
+        _ => Role::from_render_command(&render_command),
+    };
+    if role == Role::Presentation {
+        return children;
+    }
+
+    let bounds = match rendered_layout(node_tree, layout_cache, nodes, current_node) {
+        Some((layout, clips)) if clips.iter().all(|clip| layout.intersects(clip)) => layout,
+        _ => return children,
+    };
+
+    let label = match render_command {
+        RenderCommand::Text { content, .. } if !content.trim().is_empty() => Some(content),
+        _ => label_from_children(&children),
+    };
+
+    vec![AccessNode {
+        id: current_node.0,
+        role,
+        label,
+        bounds,
+        focusable: focus_tree.contains(current_node),
+        focused: focus_tree.current() == Some(current_node),
+        children,
+    }]
+}
+
+fn recurse_node_tree_to_find_widget(
+    node_tree: &Tree,
+    layout_cache: &LayoutCache,
+    nodes: &Query<&crate::node::Node>,
+    current_node: WrappedIndex,
+    point: (f32, f32),
+    mut ancestors: RenderedAncestors,
+) -> Option<WrappedIndex> {
+    let node = nodes.get(current_node.0).ok()?;
+    let layout = ancestors.place(node, layout_cache.rect.get(&current_node).copied()?);
+
+    // Only the parts of the widget that are drawn can be hit
+    let contains_point = ancestors.clips_contain(&point)
+        && match &node.primitive {
+            RenderPrimitive::Clip { radius, .. } => layout.contains_rounded(&point, radius),
+            _ => layout.contains(&point),
+        };
+    // Descendants outside of this widget can still be hit when their overflow is visible, so
+    // they're always checked against the clip regions the renderer applies to them.
+    ancestors.enter(node, layout);
+
+    let mut children = node_tree
+        .visible_children(current_node)
+        .map(|child| {
+            let z = nodes
+                .get(child.0)
+                .map(|node| node.z.max(0.0))
+                .unwrap_or(0.0);
+            (z, child)
+        })
+        .collect::<Vec<_>>();
+    // Stable sort so that later siblings (drawn last) are checked first on ties.
+    children.sort_by(|a, b| a.0.total_cmp(&b.0));
+    for (_, child) in children.into_iter().rev() {
+        if let Some(hit) = recurse_node_tree_to_find_widget(
+            node_tree,
+            layout_cache,
+            nodes,
+            child,
+            point,
+            ancestors.clone(),
+        ) {
+            return Some(hit);
+        }
+    }
+
+    if contains_point {
+        Some(current_node)
+    } else {
+        None
+    }
+}
+
 fn update_widgets_sys(world: &mut World) {
     let mut context_data = Vec::new();
 
@@ -497,14 +2508,16 @@ fn update_widgets_sys(world: &mut World) {
             panic!("Failed to acquire read lock.");
         };
 
-        // let change_tick = world.increment_change_tick();
-
+        // The focus tree is patched as widgets are updated instead of being rebuilt,
+        // it only needs to be reset when the root node changes.
         let old_focus = if let Ok(mut focus_tree) = context.focus_tree.try_write() {
             let current = focus_tree.current();
-            focus_tree.clear();
             if let Ok(tree) = context.tree.read() {
                 if let Some(root_node) = tree.root_node {
-                    focus_tree.add(root_node, &tree);
+                    if focus_tree.tree().root_node != Some(root_node) {
+                        focus_tree.clear();
+                        focus_tree.add(root_node, &tree);
+                    }
                 }
             }
             current
@@ -513,8 +2526,9 @@ fn update_widgets_sys(world: &mut World) {
         };
 
         let mut new_ticks = HashMap::new();
+        let widgets_before = tree_iterator.clone();
+        let inactive = context.inactive_widgets();
 
-        // dbg!("Updating widgets!");
         update_widgets(
             camera_entity,
             world,
@@ -530,6 +2544,8 @@ fn update_widgets_sys(world: &mut World) {
             &mut new_ticks,
             &context.order_tree,
             &context.index,
+            &context.unmount_callbacks,
+            &inactive,
         );
 
         if let Some(old_focus) = old_focus {
@@ -539,8 +2555,25 @@ fn update_widgets_sys(world: &mut World) {
                 }
             }
         }
+        context.apply_initial_focus();
+        context.record_focus_change();
+
+        if let Ok(tree) = context.tree.read() {
+            context.tree_delta =
+                TreeDelta::new(&widgets_before, &tree.down_iter().collect::<Vec<_>>());
+        }
+        for removed in context.tree_delta.removed.iter() {
+            context.generations.remove(&WrappedIndex(*removed));
+            context.frozen_layouts.remove(&WrappedIndex(*removed));
+            context.inactive_subtrees.remove(&WrappedIndex(*removed));
+            context.style_transitions.remove(removed);
+        }
+        if let Ok(mut tree) = context.tree.try_write() {
+            for removed in context.tree_delta.removed.iter() {
+                tree.hidden.remove(&WrappedIndex(*removed));
+            }
+        }
 
-        // dbg!("Finished updating widgets!");
         let tick = world.read_change_tick();
 
         for (key, system) in context.systems.iter_mut() {
@@ -551,16 +2584,10 @@ fn update_widgets_sys(world: &mut World) {
                 system.0.set_last_change_tick(tick);
                 system.1.set_last_change_tick(tick);
             }
-            // system.apply_buffers(world);
         }
 
         // Clear out indices
         if let Ok(mut indices) = context.index.try_write() {
-            // for (entity, value) in indices.iter_mut() {
-            //     if tree.root_node.unwrap().0.id() != entity.id() {
-            //         *value = 0;
-            //     }
-            // }
             indices.clear();
         }
 
@@ -583,8 +2610,15 @@ fn update_widgets(
     new_ticks: &mut HashMap<String, u32>,
     order_tree: &Arc<RwLock<Tree>>,
     index: &Arc<RwLock<HashMap<Entity, usize>>>,
+    unmount_callbacks: &UnmountCallbacks,
+    inactive: &HashSet<WrappedIndex>,
 ) {
     for entity in widgets.iter() {
+        // Paused subtrees are skipped, even when their parent re-renders
+        if inactive.contains(entity) {
+            continue;
+        }
+
         // A small hack to add parents to widgets
         let mut command_queue = CommandQueue::default();
         {
@@ -604,6 +2638,7 @@ fn update_widgets(
                     widget_state.clone(),
                     order_tree.clone(),
                     index.clone(),
+                    unmount_callbacks.clone(),
                     Some(camera_entity),
                 );
                 widget_context.copy_from_point(tree, *entity);
@@ -625,7 +2660,6 @@ fn update_widgets(
 
                 if should_update_children {
                     if let Ok(mut tree) = tree.write() {
-                        // let mut had_removal = false;
                         let diff = tree.diff_children(&widget_context, *entity, UPDATE_DEPTH);
                         for (_index, child, _parent, changes) in diff.changes.iter() {
                             if changes
@@ -658,7 +2692,6 @@ fn update_widgets(
                                         despawn_list.push(child.0);
                                     }
                                     if let Ok(mut order_tree) = order_tree.try_write() {
-                                        // had_removal = true;
                                         log::trace!(
                                             "Removing entity! {:?} inside of: {}-{:?}",
                                             child.0.index(),
@@ -669,6 +2702,14 @@ fn update_widgets(
                                     }
                                 }
                                 for entity in despawn_list.drain(..) {
+                                    unmount_widget(
+                                        world,
+                                        entity,
+                                        unmount_callbacks,
+                                        widget_state,
+                                        context_entities,
+                                        focus_tree,
+                                    );
                                     if let Some(entity_mut) = world.get_entity_mut(entity) {
                                         entity_mut.despawn();
                                     }
@@ -676,17 +2717,8 @@ fn update_widgets(
                             }
                         }
 
-                        // if had_removal {
-                        //     tree.dump();
-                        //     dbg!(&diff);
-                        // }
-
                         tree.merge(&widget_context, *entity, diff, UPDATE_DEPTH);
 
-                        // if had_removal {
-                        // tree.dump();
-                        // }
-
                         for child in widget_context.child_iter(*entity) {
                             if let Some(mut entity_commands) = world.get_entity_mut(child.0) {
                                 entity_commands.insert(DirtyNode);
@@ -695,7 +2727,6 @@ fn update_widgets(
                     }
                 }
 
-                // if should_update_children {
                 let children = if let Ok(tree) = tree.read() {
                     tree.child_iter(*entity).collect::<Vec<_>>()
                 } else {
@@ -716,8 +2747,9 @@ fn update_widgets(
                     new_ticks,
                     order_tree,
                     index,
+                    unmount_callbacks,
+                    inactive,
                 );
-                // }
             }
         } else {
             // In this case the entity we are trying to process no longer exists.
@@ -727,7 +2759,6 @@ fn update_widgets(
                 for child in tree.down_iter_at(*entity, true) {
                     despawn_list.push(child.0);
                     if let Ok(mut order_tree) = order_tree.try_write() {
-                        // had_removal = true;
                         log::trace!(
                             "Removing entity! {:?} inside of: {:?}",
                             child.0.index(),
@@ -739,6 +2770,14 @@ fn update_widgets(
 
                 for entity in despawn_list.drain(..) {
                     tree.remove(WrappedIndex(entity));
+                    unmount_widget(
+                        world,
+                        entity,
+                        unmount_callbacks,
+                        widget_state,
+                        context_entities,
+                        focus_tree,
+                    );
                     if let Some(entity_mut) = world.get_entity_mut(entity) {
                         entity_mut.despawn();
                     }
@@ -746,11 +2785,24 @@ fn update_widgets(
             }
         }
 
-        if let Some(entity_ref) = world.get_entity(entity.0) {
-            if entity_ref.contains::<Focusable>() {
-                if let Ok(tree) = tree.try_read() {
-                    if let Ok(mut focus_tree) = focus_tree.try_write() {
+        if world.get_entity(entity.0).is_some() {
+            // Disabled widgets are removed until they're re-enabled
+            let is_focusable = is_focusable(world, entity.0);
+            let focus_index = world
+                .get::<ComputedStyles>(entity.0)
+                .and_then(|styles| match styles.0.focus_index {
+                    StyleProp::Value(focus_index) => Some(focus_index),
+                    _ => None,
+                });
+            if let Ok(tree) = tree.try_read() {
+                if let Ok(mut focus_tree) = focus_tree.try_write() {
+                    let focus_index = focus_index.filter(|_| is_focusable);
+                    focus_tree.set_focus_index(*entity, focus_index, &tree);
+                    let in_focus_tree = focus_tree.contains(*entity);
+                    if is_focusable && !in_focus_tree {
                         focus_tree.add(*entity, &tree);
+                    } else if !is_focusable && in_focus_tree && tree.root_node != Some(*entity) {
+                        focus_tree.remove(*entity);
                     }
                 }
             }
@@ -758,6 +2810,33 @@ fn update_widgets(
     }
 }
 
+/// Runs the unmount callbacks for a widget that is about to be despawned and
+/// cleans up any state and context entities it owned.
+fn unmount_widget(
+    world: &mut World,
+    entity: Entity,
+    unmount_callbacks: &UnmountCallbacks,
+    widget_state: &WidgetState,
+    context_entities: &ContextEntities,
+    focus_tree: &Arc<RwLock<FocusTree>>,
+) {
+    unmount_callbacks.run(world, entity);
+
+    if let Ok(mut focus_tree) = focus_tree.try_write() {
+        let index = WrappedIndex(entity);
+        if focus_tree.contains(index) && focus_tree.tree().root_node != Some(index) {
+            focus_tree.remove(index);
+        }
+    }
+
+    if let Some(state_entity) = widget_state.remove(entity) {
+        if let Some(state_entity_mut) = world.get_entity_mut(state_entity) {
+            state_entity_mut.despawn();
+        }
+    }
+    context_entities.remove_context_entities(entity);
+}
+
 fn update_widget(
     systems: &mut WidgetSystems,
     tree: &Arc<RwLock<Tree>>,
@@ -848,11 +2927,6 @@ fn update_widget(
         return (widget_context.take(), false);
     }
 
-    // if let Ok(tree) = tree.try_read() {
-    //     if tree.root_node.unwrap() != entity {
-
-    // }
-
     let should_update_children;
     if let Ok(tree) = tree.try_read() {
         log::trace!(
@@ -913,11 +2987,6 @@ fn update_widget(
     commands.entity(entity.0).insert(DirtyNode);
 
     for (_index, changed_entity, _parent, changes) in diff.changes.iter() {
-        if changes.iter().any(|change| *change == Change::Deleted) {
-            // commands.entity(changed_entity.0).despawn();
-            // commands.entity(changed_entity.0).remove::<DirtyNode>();
-            // commands.entity(changed_entity.0).despawn_recursive();
-        }
         if changes.iter().any(|change| *change == Change::Inserted) {
             if let Some(mut entity_commands) = commands.get_entity(changed_entity.0) {
                 entity_commands.insert(Mounted);
@@ -998,28 +3067,46 @@ impl Plugin for KayakContextPlugin {
         // Register reflection types.
         // A bit annoying..
         app.register_type::<ComputedStyles>()
+            .register_type::<PseudoStyles>()
             .register_type::<KStyle>()
             .register_type::<KChildren>()
             .register_type::<WidgetName>()
             .register_type::<StyleProp<Color>>()
             .register_type::<StyleProp<Corner<f32>>>()
+            .register_type::<StyleProp<Gradient>>()
+            .register_type::<StyleProp<BackdropFilter>>()
+            .register_type::<StyleProp<BoxShadow>>()
+            .register_type::<StyleProp<KTransform>>()
+            .register_type::<StyleProp<Transition>>()
             .register_type::<StyleProp<Edge<f32>>>()
             .register_type::<StyleProp<Units>>()
             .register_type::<StyleProp<KCursorIcon>>()
             .register_type::<StyleProp<String>>()
+            .register_type::<StyleProp<Vec<String>>>()
+            .register_type::<StyleProp<kayak_font::Alignment>>()
+            .register_type::<StyleProp<TextDecoration>>()
+            .register_type::<StyleProp<Repeat>>()
+            .register_type::<StyleProp<Role>>()
+            .register_type::<StyleProp<TextDirection>>()
+            .register_type::<StyleProp<TextWrap>>()
+            .register_type::<StyleProp<LineHeight>>()
+            .register_type::<StyleProp<AlignItems>>()
             .register_type::<StyleProp<f32>>()
+            .register_type::<StyleProp<bool>>()
             .register_type::<StyleProp<LayoutType>>()
             .register_type::<StyleProp<Edge<Units>>>()
             .register_type::<StyleProp<PointerEvents>>()
             .register_type::<StyleProp<KPositionType>>()
+            .register_type::<StyleProp<Overflow>>()
             .register_type::<StyleProp<RenderCommand>>()
-            .register_type::<StyleProp<i32>>();
+            .register_type::<StyleProp<i32>>()
+            .register_type::<StyleProp<usize>>()
+            .register_type::<StyleProp<(Color, f32)>>()
+            .register_type::<StyleProp<(f32, f32)>>();
     }
 }
 
 fn calculate_ui(world: &mut World) {
-    // dbg!("Calculating nodes!");
-
     let mut context_data = Vec::new();
 
     query_world::<Query<(Entity, &mut EventDispatcher, &mut KayakRootContext)>, _, _>(
@@ -1036,11 +3123,29 @@ fn calculate_ui(world: &mut World) {
     );
 
     for (entity, event_dispatcher, mut context) in context_data.drain(..) {
+        // Dirty nodes are kept until the batch ends
+        if context.is_batching() {
+            world.entity_mut(entity).insert((event_dispatcher, context));
+            continue;
+        }
+
         let mut node_system = IntoSystem::into_system(calculate_nodes);
         node_system.initialize(world);
         let mut layout_system = IntoSystem::into_system(calculate_layout);
         layout_system.initialize(world);
 
+        // Nodes can stay dirty through every pass of the frame, so they're only counted once
+        let dirty_nodes = world
+            .query_filtered::<(), With<DirtyNode>>()
+            .iter(world)
+            .count();
+        if let Ok(mut stats) = context.stats.try_write() {
+            stats.dirty_render_count = dirty_nodes;
+            stats.nodes_rebuilt = 0;
+        }
+        // The budget covers every pass of the frame, not each pass on its own
+        context.budget_used = 0;
+
         for _ in 0..3 {
             context = node_system.run(context, world);
             node_system.apply_buffers(world);
@@ -1070,10 +3175,6 @@ fn calculate_ui(world: &mut World) {
 
         world.entity_mut(entity).insert((event_dispatcher, context));
     }
-    // dbg!("Finished calculating nodes!");
-
-    // dbg!("Dispatching layout events!");
-    // dbg!("Finished dispatching layout events!");
 }
 
 /// A simple component that stores the type name of a widget
@@ -1100,3 +3201,1965 @@ impl Into<String> for WidgetName {
         self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
+
+    use bevy::{
+        ecs::system::{CommandQueue, System, SystemState},
+        prelude::{Color, Commands, Entity, In, IntoSystem, Query, ResMut, Resource, Vec2, World},
+        time::Time,
+        window::CursorIcon,
+    };
+
+    use kayak_font::Alignment;
+
+    use super::{
+        calculate_ui, update_widgets_sys, BackdropFilter, BoxShadow, HoverChange, KayakRootContext,
+        Mounted, TreeDelta, WidgetName,
+    };
+    use crate::{
+        accessibility::AccessNodeTree,
+        event_dispatcher::EventDispatcher,
+        layout::Rect,
+        node::{DirtyNode, Node, NodeBuilder, WrappedIndex},
+        render::font::HeadlessAssets,
+        render_primitive::RenderPrimitive,
+        styles::{
+            ComputedStyles, Corner, KCursorIcon, KStyle, KTransform, Overflow, PseudoStyles,
+            RenderCommand, Role, StyleProp, Units,
+        },
+        test_utils::{TestTree, TreeBuilder},
+        widget_context::KayakWidgetContext,
+    };
+
+    fn rect(posx: f32, posy: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            posx,
+            posy,
+            width,
+            height,
+            z_index: 0.0,
+            paint_order: 0,
+        }
+    }
+
+    fn is_visible(
+        In(widget): In<Entity>,
+        contexts: Query<&KayakRootContext>,
+        nodes: Query<&Node>,
+    ) -> bool {
+        contexts.single().is_visible(widget, &nodes, None)
+    }
+
+    #[test]
+    fn remove_subtree_should_prune_every_map() {
+        let mut world = World::new();
+        let mut context = KayakRootContext::new();
+
+        let root = world.spawn_empty().id();
+        let parent = world.spawn_empty().id();
+        let child = world.spawn_empty().id();
+        let sibling = world.spawn_empty().id();
+        context.add_widget(None, root);
+        context.add_widget(Some(root), parent);
+        context.add_widget(Some(parent), child);
+        context.add_widget(Some(root), sibling);
+        if let Ok(mut order_tree) = context.order_tree.try_write() {
+            order_tree.add(WrappedIndex(root), None);
+            order_tree.add(WrappedIndex(parent), Some(WrappedIndex(root)));
+            order_tree.add(WrappedIndex(child), Some(WrappedIndex(parent)));
+        }
+        if let (Ok(tree), Ok(mut focus_tree)) =
+            (context.tree.try_read(), context.focus_tree.try_write())
+        {
+            focus_tree.add(WrappedIndex(root), &tree);
+            focus_tree.add(WrappedIndex(child), &tree);
+            focus_tree.focus(WrappedIndex(child));
+        }
+
+        let unmounted = Arc::new(AtomicUsize::new(0));
+        for entity in [parent, child] {
+            let unmounted = unmounted.clone();
+            context.on_unmount(entity, move |_, _| {
+                unmounted.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+
+        let mut command_queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut command_queue, &world);
+        context.remove_subtree(&mut commands, parent);
+        command_queue.apply(&mut world);
+
+        let tree = context.tree.read().unwrap();
+        assert!(!tree.contains(WrappedIndex(parent)));
+        assert!(!tree.contains(WrappedIndex(child)));
+        assert!(tree.contains(WrappedIndex(sibling)));
+        assert!(!context
+            .order_tree
+            .read()
+            .unwrap()
+            .contains(WrappedIndex(child)));
+
+        let layout_cache = context.layout_cache.read().unwrap();
+        assert!(!layout_cache.contains(WrappedIndex(parent)));
+        assert!(!layout_cache.contains(WrappedIndex(child)));
+        assert!(layout_cache.contains(WrappedIndex(sibling)));
+
+        let focus_tree = context.focus_tree.read().unwrap();
+        assert!(!focus_tree.contains(WrappedIndex(child)));
+        assert_eq!(None, focus_tree.current());
+
+        assert_eq!(2, unmounted.load(Ordering::SeqCst));
+        assert!(world.get_entity(parent).is_none());
+        assert!(world.get_entity(child).is_none());
+        assert!(world.get_entity(sibling).is_some());
+    }
+
+    #[test]
+    fn default_style_should_fill_unset_properties() {
+        let mut context = KayakRootContext::new();
+        context.set_default_style(KStyle {
+            color: StyleProp::Value(Color::RED),
+            ..Default::default()
+        });
+
+        assert!(context.default_styles_changed);
+        assert_eq!(StyleProp::Value(Color::RED), context.default_style().color);
+        assert_eq!(StyleProp::Default, context.default_style().font_size);
+    }
+
+    #[test]
+    fn scrolled_out_widget_should_not_be_visible() {
+        let mut world = World::new();
+        let mut context = KayakRootContext::new();
+
+        let root = world.spawn_empty().id();
+        let scroll_box = world.spawn_empty().id();
+        let shown = world.spawn_empty().id();
+        let hidden = world.spawn_empty().id();
+        context.add_widget(None, root);
+        context.add_widget(Some(root), scroll_box);
+        context.add_widget(Some(scroll_box), shown);
+        context.add_widget(Some(scroll_box), hidden);
+
+        let layouts = [
+            (root, rect(0.0, 0.0, 800.0, 600.0), RenderPrimitive::Empty),
+            (
+                scroll_box,
+                rect(0.0, 0.0, 100.0, 100.0),
+                RenderPrimitive::Clip {
+                    layout: Rect::default(),
+                    scroll: Some((0.0, -150.0)),
+                    radius: Corner::default(),
+                },
+            ),
+            (shown, rect(0.0, 200.0, 100.0, 50.0), RenderPrimitive::Empty),
+            (hidden, rect(0.0, 0.0, 100.0, 50.0), RenderPrimitive::Empty),
+        ];
+        if let Ok(mut layout_cache) = context.layout_cache.try_write() {
+            for (entity, layout, _) in layouts.iter() {
+                layout_cache.rect.insert(WrappedIndex(*entity), *layout);
+            }
+        }
+        for (entity, _, primitive) in layouts {
+            world.entity_mut(entity).insert(
+                NodeBuilder::empty()
+                    .with_id(WrappedIndex(entity))
+                    .with_styles(KStyle::initial(), None)
+                    .with_primitive(primitive)
+                    .build(),
+            );
+        }
+        world.spawn(context);
+
+        let mut system = IntoSystem::into_system(is_visible);
+        system.initialize(&mut world);
+        assert!(system.run(shown, &mut world));
+        assert!(!system.run(hidden, &mut world));
+    }
+
+    #[test]
+    fn set_pointer_should_track_hover() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("button", KStyle::initial())
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("button", rect(10.0, 10.0, 100.0, 30.0));
+        let (root, button) = (tree.entity("root"), tree.entity("button"));
+
+        tree.with_nodes(|context, commands, nodes| {
+            assert_eq!(
+                Some(HoverChange {
+                    left: None,
+                    entered: Some(button),
+                }),
+                context.set_pointer(commands, nodes, Some((50.0, 20.0)))
+            );
+            assert_eq!(
+                None,
+                context.set_pointer(commands, nodes, Some((60.0, 20.0)))
+            );
+        });
+
+        assert_eq!(Some(button), tree.context.hovered());
+        assert!(tree.context.is_hovered(button));
+        assert!(tree.context.is_hovered(root));
+        assert!(tree.world.get::<DirtyNode>(button).is_some());
+        assert!(tree.world.get::<DirtyNode>(root).is_some());
+        tree.world.entity_mut(button).remove::<DirtyNode>();
+        tree.world.entity_mut(root).remove::<DirtyNode>();
+
+        tree.with_nodes(|context, commands, nodes| {
+            assert_eq!(
+                Some(HoverChange {
+                    left: Some(button),
+                    entered: Some(root),
+                }),
+                context.set_pointer(commands, nodes, Some((500.0, 500.0)))
+            );
+        });
+
+        assert!(!tree.context.is_hovered(button));
+        assert!(tree.context.is_hovered(root));
+        // The root was hovered before and after, so only the button needs new styles
+        assert!(tree.world.get::<DirtyNode>(button).is_some());
+        assert!(tree.world.get::<DirtyNode>(root).is_none());
+    }
+
+    #[test]
+    fn pseudo_styles_should_follow_pointer() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("button", KStyle::initial())
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("button", rect(10.0, 10.0, 100.0, 30.0));
+        let (root, button) = (tree.entity("root"), tree.entity("button"));
+
+        let styles = KStyle {
+            background_color: StyleProp::Value(Color::WHITE),
+            ..Default::default()
+        };
+        let pseudo_styles = PseudoStyles {
+            hover: Some(KStyle {
+                background_color: StyleProp::Value(Color::GRAY),
+                ..Default::default()
+            }),
+            active: Some(KStyle {
+                background_color: StyleProp::Value(Color::BLACK),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let background = |context: &KayakRootContext| {
+            pseudo_styles
+                .layer(&styles, context.pseudo_state(button))
+                .background_color
+                .resolve()
+        };
+        tree.with_nodes(|context, commands, nodes| {
+            assert_eq!(Color::WHITE, background(context));
+
+            context.set_pointer(commands, nodes, Some((50.0, 20.0)));
+            assert_eq!(Color::GRAY, background(context));
+
+            context.set_pointer_pressed(commands, true);
+            assert_eq!(Color::BLACK, background(context));
+
+            context.set_pointer(commands, nodes, Some((500.0, 500.0)));
+            assert_eq!(Color::WHITE, background(context));
+        });
+    }
+
+    fn build_accessibility_tree(
+        In(_): In<()>,
+        contexts: Query<&KayakRootContext>,
+        nodes: Query<&Node>,
+    ) -> AccessNodeTree {
+        contexts.single().build_accessibility_tree(&nodes)
+    }
+
+    #[test]
+    fn accessibility_tree_should_collapse_empty_nodes() {
+        let mut world = World::new();
+        let mut context = KayakRootContext::new();
+
+        let root = world.spawn_empty().id();
+        let button = world.spawn_empty().id();
+        let wrapper = world.spawn_empty().id();
+        let text = world.spawn_empty().id();
+        let hidden = world.spawn_empty().id();
+        context.add_widget(None, root);
+        context.add_widget(Some(root), button);
+        context.add_widget(Some(button), wrapper);
+        context.add_widget(Some(wrapper), text);
+        context.add_widget(Some(root), hidden);
+
+        let nodes = [
+            (
+                root,
+                rect(0.0, 0.0, 800.0, 600.0),
+                KStyle::initial(),
+                RenderPrimitive::Clip {
+                    layout: Rect::default(),
+                    scroll: None,
+                    radius: Corner::default(),
+                },
+            ),
+            (
+                button,
+                rect(10.0, 10.0, 100.0, 30.0),
+                KStyle {
+                    render_command: StyleProp::Value(RenderCommand::Quad),
+                    role: StyleProp::Value(Role::Button),
+                    ..KStyle::initial()
+                },
+                RenderPrimitive::Empty,
+            ),
+            (
+                wrapper,
+                rect(10.0, 10.0, 100.0, 30.0),
+                KStyle {
+                    render_command: StyleProp::Value(RenderCommand::Empty),
+                    ..KStyle::initial()
+                },
+                RenderPrimitive::Empty,
+            ),
+            (
+                text,
+                rect(20.0, 15.0, 80.0, 20.0),
+                KStyle {
+                    render_command: StyleProp::Value(RenderCommand::Text {
+                        content: String::from("Click me"),
+                        alignment: Alignment::Start,
+                        word_wrap: true,
+                        subpixel: false,
+                    }),
+                    ..KStyle::initial()
+                },
+                RenderPrimitive::Empty,
+            ),
+            (
+                hidden,
+                rect(1000.0, 10.0, 100.0, 30.0),
+                KStyle {
+                    render_command: StyleProp::Value(RenderCommand::Quad),
+                    ..KStyle::initial()
+                },
+                RenderPrimitive::Empty,
+            ),
+        ];
+        if let Ok(mut layout_cache) = context.layout_cache.try_write() {
+            for (entity, layout, _, _) in nodes.iter() {
+                layout_cache.rect.insert(WrappedIndex(*entity), *layout);
+            }
+        }
+        for (entity, _, styles, primitive) in nodes {
+            world.entity_mut(entity).insert(
+                NodeBuilder::empty()
+                    .with_id(WrappedIndex(entity))
+                    .with_styles(styles, None)
+                    .with_primitive(primitive)
+                    .build(),
+            );
+        }
+        if let (Ok(tree), Ok(mut focus_tree)) =
+            (context.tree.try_read(), context.focus_tree.try_write())
+        {
+            focus_tree.add(WrappedIndex(root), &tree);
+            focus_tree.add(WrappedIndex(button), &tree);
+            focus_tree.focus(WrappedIndex(button));
+        }
+        world.spawn(context);
+
+        let mut system = IntoSystem::into_system(build_accessibility_tree);
+        system.initialize(&mut world);
+        let access_tree = system.run((), &mut world);
+
+        assert_eq!(1, access_tree.roots.len());
+        let root_node = &access_tree.roots[0];
+        assert_eq!(root, root_node.id);
+        assert_eq!(Role::Group, root_node.role);
+        // The hidden widget is outside of the root's clip region
+        assert_eq!(1, root_node.children.len());
+
+        let button_node = &root_node.children[0];
+        assert_eq!(button, button_node.id);
+        assert_eq!(Role::Button, button_node.role);
+        assert_eq!(Some(String::from("Click me")), button_node.label);
+        assert!(button_node.focusable && button_node.focused);
+        assert_eq!(rect(10.0, 10.0, 100.0, 30.0), button_node.bounds);
+
+        // The empty wrapper is collapsed, so the text is a direct child of the button
+        assert_eq!(1, button_node.children.len());
+        assert_eq!(text, button_node.children[0].id);
+        assert_eq!(Role::Text, button_node.children[0].role);
+        assert!(access_tree.get(wrapper).is_none());
+        assert!(access_tree.get(hidden).is_none());
+    }
+
+    fn build_render_primitives(
+        In(_): In<()>,
+        contexts: Query<&KayakRootContext>,
+        nodes: Query<&Node>,
+        widget_names: Query<&WidgetName>,
+    ) -> Vec<RenderPrimitive> {
+        contexts
+            .single()
+            .build_render_primitives(&nodes, &widget_names)
+    }
+
+    #[test]
+    fn deeply_nested_clips_should_have_unique_z_indices() {
+        const DEPTH: usize = 50;
+        let mut world = World::new();
+        let mut context = KayakRootContext::new().with_z_base(100.0);
+
+        // Each clip holds the next clip followed by a quad, so the quads are drawn after
+        // their sibling clip region has been reset.
+        let mut parent = None;
+        let mut widgets = Vec::new();
+        for depth in 0..DEPTH {
+            let offset = depth as f32;
+            let clip = world.spawn_empty().id();
+            context.add_widget(parent, clip);
+            widgets.push((
+                clip,
+                RenderPrimitive::Clip {
+                    layout: Rect::default(),
+                    scroll: None,
+                    radius: Corner::default(),
+                },
+                rect(offset, offset, 800.0 - offset * 2.0, 600.0 - offset * 2.0),
+            ));
+            if let Some(parent) = parent {
+                let quad = world.spawn_empty().id();
+                context.add_widget(Some(parent), quad);
+                widgets.push((
+                    quad,
+                    RenderPrimitive::Quad {
+                        layout: Rect::default(),
+                        background_color: Color::WHITE,
+                        border_color: Color::NONE,
+                        border: Default::default(),
+                        border_radius: Default::default(),
+                    },
+                    rect(offset, offset, 10.0, 10.0),
+                ));
+            }
+            parent = Some(clip);
+        }
+
+        if let Ok(mut layout_cache) = context.layout_cache.try_write() {
+            for (entity, _, layout) in widgets.iter() {
+                layout_cache.rect.insert(WrappedIndex(*entity), *layout);
+            }
+        }
+        for (entity, primitive, _) in widgets {
+            world.entity_mut(entity).insert((
+                NodeBuilder::empty()
+                    .with_id(WrappedIndex(entity))
+                    .with_styles(KStyle::initial(), None)
+                    .with_primitive(primitive)
+                    .build(),
+                WidgetName(String::from("Widget")),
+            ));
+        }
+        world.spawn(context);
+
+        let mut system = IntoSystem::into_system(build_render_primitives);
+        system.initialize(&mut world);
+        let primitives = system.run((), &mut world);
+
+        let sort_keys = primitives
+            .iter()
+            .filter_map(|primitive| match primitive {
+                RenderPrimitive::Clip { layout, .. } | RenderPrimitive::Quad { layout, .. } => {
+                    Some((layout.z_index, layout.paint_order))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            DEPTH - 1,
+            primitives
+                .iter()
+                .filter(|primitive| matches!(primitive, RenderPrimitive::Quad { .. }))
+                .count()
+        );
+        assert_eq!(Some(&(100.0, 0)), sort_keys.first());
+        for (z_index, _) in sort_keys.iter() {
+            assert_eq!(100.0, *z_index);
+        }
+        // Sorting by paint order has to reproduce the order the primitives are painted in.
+        for pair in sort_keys.windows(2) {
+            assert!(
+                pair[0].1 < pair[1].1,
+                "{:?} is not below {:?}",
+                pair[0],
+                pair[1]
+            );
+        }
+    }
+
+    #[test]
+    fn resolved_style_should_require_a_built_node() {
+        let mut tree = TreeBuilder::new()
+            .node(
+                "root",
+                KStyle {
+                    color: StyleProp::Value(Color::RED),
+                    ..KStyle::initial()
+                },
+            )
+            .build();
+        let root = tree.entity("root");
+        let unbuilt = tree.world.spawn_empty().id();
+        tree.context.add_widget(Some(root), unbuilt);
+        let outside = tree.world.spawn_empty().id();
+
+        tree.with_nodes(|context, _, nodes| {
+            assert_eq!(
+                Some(Color::RED),
+                context
+                    .get_resolved_style(root, nodes)
+                    .map(|styles| styles.color.resolve())
+            );
+            assert!(context.get_resolved_style(unbuilt, nodes).is_none());
+            assert!(context.get_resolved_style(outside, nodes).is_none());
+        });
+    }
+
+    #[test]
+    fn layout_changes_should_notify_subscribers() {
+        let sized = |width: f32, height: f32| KStyle {
+            width: StyleProp::Value(Units::Pixels(width)),
+            height: StyleProp::Value(Units::Pixels(height)),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(800.0, 600.0))
+            .leaf("panel", sized(200.0, 100.0))
+            .build();
+        tree.layout();
+        let panel = tree.entity("panel");
+
+        let changes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = changes.clone();
+        tree.context.on_layout_changed(panel, move |old, new| {
+            recorded.lock().unwrap().push((old, new));
+        });
+
+        // Laying out again without changes shouldn't notify
+        tree.layout();
+        assert!(changes.lock().unwrap().is_empty());
+
+        if let Some(mut node) = tree.world.get_mut::<Node>(panel) {
+            node.resolved_styles.width = StyleProp::Value(Units::Pixels(300.0));
+        }
+        tree.layout();
+        {
+            let changes = changes.lock().unwrap();
+            assert_eq!(1, changes.len());
+            assert_eq!(200.0, changes[0].0.width);
+            assert_eq!(300.0, changes[0].1.width);
+        }
+
+        // Callbacks run without the subscriptions locked, so they can subscribe in turn
+        let subscriptions = tree.context.layout_subscriptions.clone();
+        let locked = Arc::new(Mutex::new(None));
+        let observed = locked.clone();
+        tree.context.on_layout_changed(panel, move |_, _| {
+            *observed.lock().unwrap() = Some(subscriptions.try_write().is_err());
+        });
+        if let Some(mut node) = tree.world.get_mut::<Node>(panel) {
+            node.resolved_styles.width = StyleProp::Value(Units::Pixels(400.0));
+        }
+        tree.layout();
+        assert_eq!(Some(false), *locked.lock().unwrap());
+
+        // Removed widgets stop being tracked
+        tree.with_nodes(|context, commands, _| context.remove_subtree(commands, panel));
+        if let Ok(subscriptions) = tree.context.layout_subscriptions.try_read() {
+            assert!(subscriptions.is_empty());
+        }
+    }
+
+    #[test]
+    fn initial_focus_should_wait_for_its_widget() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("form", KStyle::initial(), |form| {
+                form.leaf("username", KStyle::initial())
+                    .leaf("password", KStyle::initial())
+            })
+            .build();
+        let (root, username, password) = (
+            tree.entity("root"),
+            tree.entity("username"),
+            tree.entity("password"),
+        );
+        let mount = |context: &KayakRootContext, widget: Entity| {
+            if let (Ok(tree), Ok(mut focus_tree)) =
+                (context.tree.try_read(), context.focus_tree.try_write())
+            {
+                focus_tree.add(WrappedIndex(widget), &tree);
+            }
+        };
+
+        let context = &mut tree.context;
+        context.set_initial_focus(username);
+        mount(context, root);
+        context.apply_initial_focus();
+        // The username field isn't focusable yet
+        assert_eq!(Some(root), context.get_current_focus());
+
+        mount(context, username);
+        mount(context, password);
+        context.apply_initial_focus();
+        assert_eq!(Some(username), context.get_current_focus());
+
+        // Focus given to another widget first takes precedence
+        if let Ok(mut focus_tree) = context.focus_tree.try_write() {
+            focus_tree.focus(WrappedIndex(password));
+        }
+        context.set_initial_focus(username);
+        context.apply_initial_focus();
+        assert_eq!(Some(password), context.get_current_focus());
+        assert_eq!(None, context.initial_focus);
+    }
+
+    #[test]
+    fn sorting_should_restore_paint_order() {
+        let quad = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            ..KStyle::initial()
+        };
+        // With depth based z-indices, the nested quad and the second panel (and the third
+        // panel and the deeper quad) end up at the same z-index.
+        let mut tree = TreeBuilder::new()
+            .node("root", quad.clone())
+            .child("first", quad.clone(), |first| {
+                first.child("nested", quad.clone(), |nested| {
+                    nested.leaf("deeper", quad.clone())
+                })
+            })
+            .leaf("second", quad.clone())
+            .leaf("third", quad.clone())
+            .build();
+        for name in ["root", "first", "nested", "deeper", "second", "third"] {
+            tree.set_layout(name, rect(0.0, 0.0, 100.0, 100.0));
+        }
+
+        let primitives = tree.render_primitives();
+        let sort_keys = primitives
+            .iter()
+            .filter_map(|primitive| primitive.sort_key())
+            .collect::<Vec<_>>();
+        for pair in sort_keys.windows(2) {
+            assert!(pair[0] < pair[1]);
+        }
+        let mut sorted = primitives.clone();
+        sorted.reverse();
+        RenderPrimitive::sort(&mut sorted);
+        assert_eq!(primitives, sorted);
+    }
+
+    #[test]
+    fn sorting_should_not_depend_on_the_z_base() {
+        let quad = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            ..KStyle::initial()
+        };
+        let build = |z_base: f32| {
+            let mut tree = TreeBuilder::new()
+                .node("root", quad.clone())
+                .child("first", quad.clone(), |first| {
+                    first.leaf("nested", quad.clone())
+                })
+                .leaf("second", quad.clone())
+                .build();
+            for name in ["root", "first", "nested", "second"] {
+                tree.set_layout(name, rect(0.0, 0.0, 100.0, 100.0));
+            }
+            tree.context.set_z_base(z_base);
+            tree.render_primitives()
+        };
+
+        // Above 2^24, consecutive whole numbers round to the same `f32`, so z-indices added to
+        // this z-base would alias.
+        let z_base = 16_777_216.0;
+        assert_eq!(z_base, z_base + 1.0);
+        let (low, high) = (build(0.0), build(z_base));
+        for primitives in [&low, &high] {
+            let mut sorted = primitives.clone();
+            sorted.reverse();
+            RenderPrimitive::sort(&mut sorted);
+            assert_eq!(primitives, &sorted);
+        }
+        let paint_order = |primitives: &Vec<RenderPrimitive>| {
+            primitives
+                .iter()
+                .filter_map(|primitive| primitive.sort_key())
+                .map(|(_, order)| order)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(paint_order(&low), paint_order(&high));
+    }
+
+    #[test]
+    fn tree_delta_should_report_added_and_removed_widgets() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("list", KStyle::initial(), |list| {
+                list.leaf("first", KStyle::initial())
+                    .leaf("second", KStyle::initial())
+            })
+            .build();
+        let (root, list, first, second) = (
+            tree.entity("root"),
+            tree.entity("list"),
+            tree.entity("first"),
+            tree.entity("second"),
+        );
+        let widgets = |context: &KayakRootContext| {
+            context
+                .tree
+                .try_read()
+                .map(|tree| tree.down_iter().collect::<Vec<_>>())
+                .unwrap_or_default()
+        };
+
+        let before = widgets(&tree.context);
+        assert!(TreeDelta::new(&before, &before).is_empty());
+
+        let third = tree.world.spawn_empty().id();
+        tree.context.add_widget(Some(list), third);
+        tree.with_nodes(|context, commands, _| context.remove_subtree(commands, first));
+        let delta = TreeDelta::new(&before, &widgets(&tree.context));
+        assert_eq!(vec![third], delta.added);
+        assert_eq!(vec![first], delta.removed);
+
+        // Removing a subtree reports every widget in it
+        let before = widgets(&tree.context);
+        tree.with_nodes(|context, commands, _| context.remove_subtree(commands, list));
+        let delta = TreeDelta::new(&before, &widgets(&tree.context));
+        assert!(delta.added.is_empty());
+        assert_eq!(vec![list, second, third], delta.removed);
+        assert_eq!(vec![WrappedIndex(root)], widgets(&tree.context));
+    }
+
+    #[test]
+    fn rounded_clip_should_round_content_and_picking() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child(
+                "card",
+                KStyle {
+                    render_command: StyleProp::Value(RenderCommand::Clip),
+                    border_radius: StyleProp::Value(Corner::all(10.0)),
+                    ..KStyle::initial()
+                },
+                |card| {
+                    card.leaf(
+                        "image",
+                        KStyle {
+                            render_command: StyleProp::Value(RenderCommand::Image {
+                                handle: Default::default(),
+                            }),
+                            ..KStyle::initial()
+                        },
+                    )
+                },
+            )
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("card", rect(100.0, 100.0, 200.0, 100.0));
+        // The image overflows the bottom of the card
+        tree.set_layout("image", rect(100.0, 100.0, 200.0, 300.0));
+        let (root, image) = (tree.entity("root"), tree.entity("image"));
+
+        let primitives = tree.render_primitives();
+        let border_radius = primitives.iter().find_map(|primitive| match primitive {
+            RenderPrimitive::Image { border_radius, .. } => Some(*border_radius),
+            _ => None,
+        });
+        assert_eq!(
+            Some(Corner {
+                top_left: 10.0,
+                top_right: 10.0,
+                bottom_left: 0.0,
+                bottom_right: 0.0,
+            }),
+            border_radius
+        );
+
+        tree.with_nodes(|context, _, nodes| {
+            assert_eq!(Some(image), context.widget_at(nodes, (150.0, 150.0)));
+            // Just inside the card's bounds, but outside of its rounded corner
+            assert_eq!(Some(root), context.widget_at(nodes, (101.0, 101.0)));
+            assert_eq!(Some(image), context.widget_at(nodes, (105.0, 105.0)));
+        });
+    }
+
+    #[test]
+    fn primitive_filter_should_modify_primitives_in_place() {
+        let quad = |color: Color| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            background_color: StyleProp::Value(color),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", quad(Color::WHITE))
+            .leaf("first", quad(Color::BLUE))
+            .leaf("second", quad(Color::GREEN))
+            .build();
+        for name in ["root", "first", "second"] {
+            tree.set_layout(name, rect(0.0, 0.0, 100.0, 100.0));
+        }
+        let unfiltered = tree.render_primitives();
+
+        let visited = Arc::new(AtomicUsize::new(0));
+        let counter = visited.clone();
+        tree.context.set_primitive_filter(move |primitive| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            if let RenderPrimitive::Quad {
+                background_color, ..
+            } = primitive
+            {
+                *background_color = Color::RED;
+            }
+        });
+        let filtered = tree.render_primitives();
+
+        assert_eq!(unfiltered.len(), visited.load(Ordering::SeqCst));
+        assert_eq!(unfiltered.len(), filtered.len());
+        for (unfiltered, filtered) in unfiltered.iter().zip(filtered.iter()) {
+            assert_eq!(unfiltered.z_index(), filtered.z_index());
+            if let RenderPrimitive::Quad {
+                background_color, ..
+            } = filtered
+            {
+                assert_eq!(Color::RED, *background_color);
+            }
+        }
+
+        tree.context.clear_primitive_filter();
+        assert_eq!(unfiltered, tree.render_primitives());
+    }
+
+    #[test]
+    fn generations_should_count_rebuilds() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("label", KStyle::initial())
+            .build();
+        let label = tree.entity("label");
+        assert_eq!(0, tree.context.get_generation(label));
+
+        tree.calculate_nodes();
+        let generation = tree.context.get_generation(label);
+
+        // Rebuilding the same node again isn't counted
+        tree.calculate_nodes();
+        assert_eq!(generation, tree.context.get_generation(label));
+
+        if let Some(mut styles) = tree.world.get_mut::<ComputedStyles>(label) {
+            styles.0.color = StyleProp::Value(Color::RED);
+        }
+        tree.calculate_nodes();
+        assert_eq!(generation + 1, tree.context.get_generation(label));
+
+        tree.with_nodes(|context, commands, _| context.remove_subtree(commands, label));
+        assert_eq!(0, tree.context.get_generation(label));
+    }
+
+    #[test]
+    fn nested_batches_should_be_reference_counted() {
+        let mut context = KayakRootContext::new();
+        assert!(!context.is_batching());
+
+        context.begin_batch();
+        context.begin_batch();
+        context.end_batch();
+        assert!(context.is_batching());
+        context.end_batch();
+        assert!(!context.is_batching());
+
+        // Unbalanced ends are ignored
+        context.end_batch();
+        context.begin_batch();
+        assert!(context.is_batching());
+    }
+
+    #[test]
+    fn batched_nodes_should_be_rebuilt_once_the_outermost_batch_ends() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("label", KStyle::initial())
+            .build();
+        let label = tree.entity("label");
+        let mut world = std::mem::take(&mut tree.world);
+        let mut context = std::mem::take(&mut tree.context);
+        world.insert_resource(HeadlessAssets::default());
+        world.init_resource::<Time>();
+
+        context.begin_batch();
+        context.begin_batch();
+        world.entity_mut(label).insert(DirtyNode);
+        let root = world.spawn((EventDispatcher::default(), context)).id();
+        let end_batch = |world: &mut World| {
+            if let Some(mut context) = world.get_mut::<KayakRootContext>(root) {
+                context.end_batch();
+            }
+        };
+
+        calculate_ui(&mut world);
+        end_batch(&mut world);
+        calculate_ui(&mut world);
+        assert!(world.get::<DirtyNode>(label).is_some());
+        assert_eq!(
+            Some(0),
+            world
+                .get::<KayakRootContext>(root)
+                .map(|context| context.get_generation(label))
+        );
+
+        end_batch(&mut world);
+        calculate_ui(&mut world);
+        assert!(world.get::<DirtyNode>(label).is_none());
+        let context = world.get::<KayakRootContext>(root).unwrap();
+        assert_eq!(1, context.get_generation(label));
+        assert_eq!(1, context.last_stats().nodes_rebuilt);
+    }
+
+    #[test]
+    fn dirty_nodes_should_be_counted_once_per_frame() {
+        let label = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Hello"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node(
+                "root",
+                KStyle {
+                    render_command: StyleProp::Value(RenderCommand::Quad),
+                    ..KStyle::initial()
+                },
+            )
+            .leaf("label", label)
+            .build();
+        let label = tree.entity("label");
+        let mut world = std::mem::take(&mut tree.world);
+        let context = std::mem::take(&mut tree.context);
+        world.insert_resource(HeadlessAssets::default());
+        world.init_resource::<Time>();
+
+        // The label stays dirty through every pass, since its parent is never laid out
+        world.entity_mut(label).insert(DirtyNode);
+        let root = world.spawn((EventDispatcher::default(), context)).id();
+        calculate_ui(&mut world);
+
+        assert!(world.get::<DirtyNode>(label).is_some());
+        let context = world.get::<KayakRootContext>(root).unwrap();
+        assert_eq!(1, context.last_stats().dirty_render_count);
+    }
+
+    #[test]
+    fn focus_at_should_focus_closest_focusable_ancestor() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("button", KStyle::initial(), |button| {
+                button.leaf("label", KStyle::initial())
+            })
+            .leaf("footer", KStyle::initial())
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("button", rect(100.0, 100.0, 200.0, 50.0));
+        tree.set_layout("label", rect(110.0, 110.0, 100.0, 30.0));
+        tree.set_layout("footer", rect(0.0, 500.0, 800.0, 100.0));
+        let (root, button) = (tree.entity("root"), tree.entity("button"));
+        if let (Ok(node_tree), Ok(mut focus_tree)) = (
+            tree.context.tree.try_read(),
+            tree.context.focus_tree.try_write(),
+        ) {
+            focus_tree.add(WrappedIndex(root), &node_tree);
+            focus_tree.add(WrappedIndex(button), &node_tree);
+        }
+
+        tree.with_nodes(|context, _, nodes| {
+            // Clicking the label focuses the button it's in
+            assert_eq!(Some(button), context.focus_at(nodes, (120.0, 120.0)));
+            assert_eq!(Some(button), context.get_current_focus());
+
+            // Widgets without a focusable ancestor fall back to the root
+            assert_eq!(Some(root), context.focus_at(nodes, (10.0, 550.0)));
+            assert_eq!(None, context.focus_at(nodes, (900.0, 900.0)));
+
+            // Nothing outside of a focus trap can be focused
+            context.push_focus_trap(button);
+            assert_eq!(None, context.focus_at(nodes, (10.0, 550.0)));
+            assert_eq!(Some(button), context.get_current_focus());
+        });
+    }
+
+    #[test]
+    fn frozen_layout_should_be_written_back_as_pixels() {
+        let sized = |width, height| KStyle {
+            width: StyleProp::Value(width),
+            height: StyleProp::Value(height),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(Units::Pixels(800.0), Units::Pixels(600.0)))
+            .leaf("header", sized(Units::Stretch(1.0), Units::Pixels(100.0)))
+            .leaf(
+                "panel",
+                sized(Units::Percentage(50.0), Units::Pixels(200.0)),
+            )
+            .build();
+        tree.calculate_nodes();
+        tree.layout();
+        let panel_layout = tree.layout_of("panel");
+        assert_eq!(
+            (0.0, 100.0, 400.0),
+            (panel_layout.posx, panel_layout.posy, panel_layout.width)
+        );
+
+        let panel = tree.entity("panel");
+        tree.context.freeze_layout();
+        tree.calculate_nodes();
+        let styles = tree
+            .world
+            .get::<Node>(panel)
+            .unwrap()
+            .resolved_styles
+            .clone();
+        assert_eq!(StyleProp::Value(Units::Pixels(400.0)), styles.width);
+        assert_eq!(StyleProp::Value(Units::Pixels(0.0)), styles.left);
+        assert_eq!(StyleProp::Value(Units::Pixels(100.0)), styles.top);
+        tree.layout();
+        assert_eq!(panel_layout, tree.layout_of("panel"));
+
+        tree.context.unfreeze_layout();
+        assert!(!tree.context.is_layout_frozen());
+        tree.calculate_nodes();
+        let styles = tree
+            .world
+            .get::<Node>(panel)
+            .unwrap()
+            .resolved_styles
+            .clone();
+        assert_eq!(StyleProp::Value(Units::Percentage(50.0)), styles.width);
+    }
+
+    #[test]
+    fn backdrop_should_be_drawn_between_shadow_and_content() {
+        let quad = |extra: KStyle| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            background_color: StyleProp::Value(Color::WHITE),
+            ..extra
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("background", quad(KStyle::initial()))
+            .leaf(
+                "glass",
+                quad(KStyle {
+                    box_shadow: StyleProp::Value(BoxShadow::default()),
+                    backdrop_filter: StyleProp::Value(BackdropFilter {
+                        blur: 8.0,
+                        tint: Color::rgba(1.0, 1.0, 1.0, 0.2),
+                    }),
+                    ..KStyle::initial()
+                }),
+            )
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("background", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("glass", rect(100.0, 100.0, 200.0, 100.0));
+
+        let names = tree
+            .render_primitives()
+            .iter()
+            .filter(|primitive| !matches!(primitive, RenderPrimitive::Empty))
+            .map(|primitive| primitive.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(vec!["Quad", "BoxShadow", "Backdrop", "Quad"], names);
+    }
+
+    #[test]
+    fn orphans_should_be_found_and_pruned() {
+        let mut world = World::new();
+        let mut context = KayakRootContext::new();
+
+        let root = world.spawn_empty().id();
+        let child = world.spawn_empty().id();
+        let orphan = world.spawn_empty().id();
+        let orphan_child = world.spawn_empty().id();
+        context.add_widget(None, root);
+        context.add_widget(Some(root), child);
+        context.add_widget(Some(root), orphan);
+        context.add_widget(Some(orphan), orphan_child);
+        assert!(context.find_orphans().is_empty());
+
+        // Detaching a widget from its parent's children leaves it and its children behind
+        if let Ok(mut tree) = context.tree.try_write() {
+            tree.children
+                .get_mut(&WrappedIndex(root))
+                .unwrap()
+                .retain(|index| index.0 != orphan);
+        }
+        let mut orphans = vec![orphan, orphan_child];
+        orphans.sort();
+        assert_eq!(orphans, context.find_orphans());
+
+        let mut command_queue = CommandQueue::default();
+        let mut commands = Commands::new(&mut command_queue, &world);
+        assert_eq!(orphans, context.prune_orphans(&mut commands));
+        command_queue.apply(&mut world);
+
+        assert!(context.find_orphans().is_empty());
+        assert!(world.get_entity(orphan).is_none());
+        assert!(world.get_entity(orphan_child).is_none());
+        assert!(world.get_entity(child).is_some());
+        if let Ok(tree) = context.tree.try_read() {
+            assert!(!tree.contains(WrappedIndex(orphan)));
+            assert!(tree.contains(WrappedIndex(child)));
+        }
+    }
+
+    #[test]
+    fn layers_should_be_drawn_and_picked_above_the_root() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("content", KStyle::initial())
+            .layer("overlay", KStyle::initial(), |overlay| {
+                overlay.leaf("dialog", KStyle::initial())
+            })
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("content", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("overlay", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("dialog", rect(200.0, 200.0, 400.0, 200.0));
+        let (overlay, dialog) = (tree.entity("overlay"), tree.entity("dialog"));
+
+        tree.with_nodes(|context, _, nodes| {
+            let mut order = Vec::new();
+            context.for_each_in_render_order(nodes, |entity, _, _| order.push(entity));
+            assert_eq!(Some(&dialog), order.last());
+
+            // The overlay covers the whole window, so only its own widgets are picked
+            assert_eq!(Some(dialog), context.widget_at(nodes, (300.0, 300.0)));
+            assert_eq!(Some(overlay), context.widget_at(nodes, (10.0, 10.0)));
+        });
+    }
+
+    #[test]
+    fn effective_clip_should_intersect_enclosing_clips() {
+        let clip = |scroll| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Clip),
+            scroll: StyleProp::Value(scroll),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("outer", clip((0.0, 0.0)), |outer| {
+                outer.child("inner", clip((0.0, -50.0)), |inner| {
+                    inner.leaf("item", KStyle::initial())
+                })
+            })
+            .leaf("free", KStyle::initial())
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("outer", rect(100.0, 100.0, 200.0, 200.0));
+        tree.set_layout("inner", rect(200.0, 150.0, 200.0, 200.0));
+        tree.set_layout("item", rect(200.0, 150.0, 50.0, 50.0));
+        tree.set_layout("free", rect(0.0, 0.0, 50.0, 50.0));
+        let (inner, item, free) = (
+            tree.entity("inner"),
+            tree.entity("item"),
+            tree.entity("free"),
+        );
+
+        tree.with_nodes(|context, _, nodes| {
+            assert_eq!(
+                Some(rect(200.0, 150.0, 100.0, 150.0)),
+                context.effective_clip(item, nodes)
+            );
+            // A widget's own clip region doesn't apply to itself
+            assert_eq!(
+                Some(rect(100.0, 100.0, 200.0, 200.0)),
+                context.effective_clip(inner, nodes)
+            );
+            assert_eq!(
+                Some(rect(0.0, 0.0, 800.0, 600.0)),
+                context.effective_clip(free, nodes)
+            );
+        });
+
+        // Moving the scroll area out of its clip region clips it away entirely
+        tree.set_layout("inner", rect(400.0, 150.0, 200.0, 200.0));
+        tree.with_nodes(|context, _, nodes| {
+            assert_eq!(None, context.effective_clip(item, nodes));
+        });
+    }
+
+    #[test]
+    fn content_bounds_should_cover_transformed_descendants() {
+        let translated = KStyle {
+            transform: StyleProp::Value(KTransform::from_translation(Vec2::new(0.0, 100.0))),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("list", KStyle::initial(), |list| {
+                list.leaf("first", KStyle::initial())
+                    .child("second", translated, |second| {
+                        second.leaf("nested", KStyle::initial())
+                    })
+            })
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("list", rect(10.0, 10.0, 200.0, 100.0));
+        tree.set_layout("first", rect(10.0, 10.0, 200.0, 50.0));
+        tree.set_layout("second", rect(10.0, 60.0, 200.0, 50.0));
+        tree.set_layout("nested", rect(10.0, 60.0, 300.0, 50.0));
+        let (list, first) = (tree.entity("list"), tree.entity("first"));
+
+        tree.with_nodes(|context, _, nodes| {
+            // The nested widget overflows the list, and is moved down along with its parent
+            assert_eq!(
+                Some(rect(10.0, 10.0, 300.0, 200.0)),
+                context.content_bounds(list, nodes)
+            );
+            assert_eq!(None, context.content_bounds(first, nodes));
+        });
+    }
+
+    #[test]
+    fn reactivated_subtree_should_rerender() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("panel", KStyle::initial(), |panel| {
+                panel.leaf("field", KStyle::initial())
+            })
+            .leaf("footer", KStyle::initial())
+            .build();
+        let (panel, field, footer) = (
+            tree.entity("panel"),
+            tree.entity("field"),
+            tree.entity("footer"),
+        );
+
+        tree.with_nodes(|context, commands, _| {
+            context.set_subtree_active(commands, panel, false);
+            assert!(!context.is_active(panel));
+            assert!(!context.is_active(field));
+            assert!(context.is_active(footer));
+            let inactive = context.inactive_widgets();
+            assert!(inactive.contains(&WrappedIndex(panel)));
+            assert!(inactive.contains(&WrappedIndex(field)));
+            assert!(!inactive.contains(&WrappedIndex(footer)));
+        });
+        assert!(tree.world.get::<Mounted>(field).is_none());
+
+        tree.with_nodes(|context, commands, _| {
+            context.set_subtree_active(commands, panel, true);
+            assert!(context.is_active(field));
+        });
+        assert!(tree.world.get::<Mounted>(panel).is_some());
+        assert!(tree.world.get::<Mounted>(field).is_some());
+        assert!(tree.world.get::<Mounted>(footer).is_none());
+    }
+
+    #[test]
+    fn inactive_subtree_should_not_render_with_its_parent() {
+        #[derive(Resource, Default)]
+        struct Renders(Vec<Entity>);
+
+        fn update(In(_): In<(KayakWidgetContext, Entity, Entity)>) -> bool {
+            true
+        }
+
+        fn render(
+            In((_, entity)): In<(KayakWidgetContext, Entity)>,
+            mut renders: ResMut<Renders>,
+        ) -> bool {
+            renders.0.push(entity);
+            false
+        }
+
+        let mut world = World::new();
+        world.init_resource::<Renders>();
+        let mut context = KayakRootContext::new();
+        context.add_widget_system("Widget", update, render);
+        let [root, panel, field, footer] =
+            [(); 4].map(|_| world.spawn(WidgetName(String::from("Widget"))).id());
+        context.add_widget(None, root);
+        context.add_widget(Some(root), panel);
+        context.add_widget(Some(panel), field);
+        context.add_widget(Some(root), footer);
+
+        context.set_subtree_active(
+            &mut Commands::new(&mut CommandQueue::default(), &world),
+            panel,
+            false,
+        );
+        let camera = world.spawn(context).id();
+
+        let rendered = |world: &mut World| std::mem::take(&mut world.resource_mut::<Renders>().0);
+        update_widgets_sys(&mut world);
+        let renders = rendered(&mut world);
+        // The root re-renders, but the paused panel and field under it don't
+        assert!(renders.contains(&root));
+        assert!(renders.contains(&footer));
+        assert!(!renders.contains(&panel));
+        assert!(!renders.contains(&field));
+
+        let mut context = world
+            .entity_mut(camera)
+            .remove::<KayakRootContext>()
+            .unwrap();
+        let mut command_queue = CommandQueue::default();
+        context.set_subtree_active(&mut Commands::new(&mut command_queue, &world), panel, true);
+        command_queue.apply(&mut world);
+        world.entity_mut(camera).insert(context);
+        update_widgets_sys(&mut world);
+        let renders = rendered(&mut world);
+        assert!(renders.contains(&panel));
+        assert!(renders.contains(&field));
+    }
+
+    #[test]
+    fn mark_dirty_should_only_mark_given_widgets() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("first", KStyle::initial())
+            .leaf("second", KStyle::initial())
+            .leaf("third", KStyle::initial())
+            .build();
+        let (root, first, second, third) = (
+            tree.entity("root"),
+            tree.entity("first"),
+            tree.entity("second"),
+            tree.entity("third"),
+        );
+        let outside = tree.world.spawn_empty().id();
+
+        tree.with_nodes(|context, commands, _| {
+            assert!(context.mark_dirty(commands, [first, third, outside]));
+        });
+
+        assert!(tree.world.get::<DirtyNode>(first).is_some());
+        assert!(tree.world.get::<DirtyNode>(third).is_some());
+        assert!(tree.world.get::<DirtyNode>(second).is_none());
+        assert!(tree.world.get::<DirtyNode>(root).is_none());
+        assert!(tree.world.get::<DirtyNode>(outside).is_none());
+    }
+
+    #[test]
+    fn mark_dirty_should_report_a_locked_tree() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("label", KStyle::initial())
+            .build();
+        let label = tree.entity("label");
+
+        tree.with_nodes(|context, commands, _| {
+            let node_tree = context.tree.clone();
+            let _lock = node_tree.write();
+            assert!(!context.mark_dirty(commands, [label]));
+        });
+        assert!(tree.world.get::<DirtyNode>(label).is_none());
+    }
+
+    #[test]
+    fn reparent_should_move_widget_without_recreating_it() {
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("first_list", KStyle::initial(), |list| {
+                list.leaf("item", KStyle::initial())
+            })
+            .child("second_list", KStyle::initial(), |list| {
+                list.leaf("other", KStyle::initial())
+            })
+            .build();
+        let (root, first_list, second_list, item, other) = (
+            tree.entity("root"),
+            tree.entity("first_list"),
+            tree.entity("second_list"),
+            tree.entity("item"),
+            tree.entity("other"),
+        );
+
+        tree.with_nodes(|context, commands, _| {
+            assert!(context.reparent(commands, item, second_list, 0));
+            // A widget can't become its own ancestor, and roots can't be moved
+            assert!(!context.reparent(commands, second_list, item, 0));
+            assert!(!context.reparent(commands, root, first_list, 0));
+        });
+
+        if let Ok(node_tree) = tree.context.tree.try_read() {
+            assert_eq!(
+                Some(WrappedIndex(second_list)),
+                node_tree.get_parent(WrappedIndex(item))
+            );
+            assert_eq!(
+                vec![item, other],
+                node_tree
+                    .child_iter(WrappedIndex(second_list))
+                    .map(|index| index.0)
+                    .collect::<Vec<_>>()
+            );
+            assert_eq!(None, node_tree.get_first_child(WrappedIndex(first_list)));
+        }
+        assert!(tree.world.get::<DirtyNode>(item).is_some());
+        assert!(tree.world.get::<DirtyNode>(first_list).is_some());
+        assert!(tree.world.get::<DirtyNode>(other).is_none());
+    }
+
+    #[test]
+    fn layout_cache_report_should_show_recomputed_widgets() {
+        let sized = |width, height| KStyle {
+            width: StyleProp::Value(Units::Pixels(width)),
+            height: StyleProp::Value(Units::Pixels(height)),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(800.0, 600.0))
+            .child("first_panel", sized(200.0, 100.0), |panel| {
+                panel.leaf("first_label", sized(50.0, 20.0))
+            })
+            .child("second_panel", sized(200.0, 100.0), |panel| {
+                panel.leaf("second_label", sized(50.0, 20.0))
+            })
+            .build();
+        let recomputed = |tree: &TestTree| {
+            tree.context
+                .layout_cache_report()
+                .into_iter()
+                .filter(|(_, _, recomputed)| *recomputed)
+                .map(|(entity, _, _)| entity)
+                .collect::<Vec<_>>()
+        };
+
+        tree.layout();
+        let report = tree.context.layout_cache_report();
+        assert_eq!(5, report.len());
+        assert_eq!(
+            (tree.entity("first_label"), tree.layout_of("first_label")),
+            (report[2].0, report[2].1)
+        );
+        assert_eq!(5, recomputed(&tree).len());
+
+        // Only the fixed size panel around the label has to be laid out again
+        let first_label = WrappedIndex(tree.entity("first_label"));
+        tree.context.dirty_layout_nodes.insert(first_label);
+        tree.relayout();
+        assert_eq!(
+            vec![tree.entity("first_panel"), tree.entity("first_label")],
+            recomputed(&tree)
+        );
+    }
+
+    #[test]
+    fn transformed_widgets_should_be_hit_and_scaled_where_they_are_drawn() {
+        let scaled = KStyle {
+            transform: StyleProp::Value(KTransform::from_scale(Vec2::splat(2.0))),
+            ..KStyle::initial()
+        };
+        let label = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Hello"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("card", scaled, |card| card.leaf("label", label))
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("card", rect(100.0, 100.0, 100.0, 100.0));
+        tree.set_layout("label", rect(100.0, 100.0, 50.0, 20.0));
+        let (root, card, label) = (
+            tree.entity("root"),
+            tree.entity("card"),
+            tree.entity("label"),
+        );
+
+        tree.with_nodes(|context, _, nodes| {
+            // The card is drawn twice as big around its center, and the label along with it
+            assert_eq!(Some(card), context.widget_at(nodes, (240.0, 240.0)));
+            assert_eq!(Some(label), context.widget_at(nodes, (60.0, 60.0)));
+            assert_eq!(Some(root), context.widget_at(nodes, (40.0, 40.0)));
+        });
+
+        let text_scale = tree
+            .render_primitives()
+            .iter()
+            .find_map(|primitive| match primitive {
+                RenderPrimitive::Text { scale, .. } => Some(*scale),
+                _ => None,
+            });
+        assert_eq!(Some(Vec2::splat(2.0)), text_scale);
+    }
+
+    #[test]
+    fn widget_at_should_hit_the_rendered_layout() {
+        let list = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Clip),
+            scroll: StyleProp::Value((0.0, -50.0)),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("list", list, |list| {
+                list.leaf("item", KStyle::initial())
+                    .leaf("hidden", KStyle::initial())
+            })
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("list", rect(0.0, 0.0, 100.0, 200.0));
+        tree.set_layout("item", rect(0.0, 100.0, 100.0, 50.0));
+        tree.set_layout("hidden", rect(0.0, 260.0, 100.0, 50.0));
+        let (root, list, item) = (
+            tree.entity("root"),
+            tree.entity("list"),
+            tree.entity("item"),
+        );
+
+        tree.with_nodes(|context, _, nodes| {
+            // The item is drawn scrolled up, out of its own layout
+            assert_eq!(Some(item), context.widget_at(nodes, (10.0, 60.0)));
+            assert_eq!(Some(list), context.widget_at(nodes, (10.0, 120.0)));
+            // The last item is scrolled past the bottom of the list, where it's clipped away
+            assert_eq!(Some(root), context.widget_at(nodes, (10.0, 215.0)));
+        });
+    }
+
+    #[test]
+    fn widget_at_should_hit_visible_overflow_outside_of_clips() {
+        let popup = KStyle {
+            overflow: StyleProp::Value(Overflow::Visible),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child(
+                "list",
+                KStyle {
+                    render_command: StyleProp::Value(RenderCommand::Clip),
+                    ..KStyle::initial()
+                },
+                |list| list.leaf("item", KStyle::initial()).leaf("popup", popup),
+            )
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("list", rect(0.0, 0.0, 100.0, 200.0));
+        tree.set_layout("item", rect(0.0, 150.0, 100.0, 100.0));
+        tree.set_layout("popup", rect(0.0, 250.0, 100.0, 100.0));
+        let (root, item, popup) = (
+            tree.entity("root"),
+            tree.entity("item"),
+            tree.entity("popup"),
+        );
+
+        tree.with_nodes(|context, _, nodes| {
+            // The popup escapes the list's clip region, just like when it's rendered
+            assert_eq!(Some(popup), context.widget_at(nodes, (10.0, 300.0)));
+            // While the part of the item outside of the list is clipped away
+            assert_eq!(Some(item), context.widget_at(nodes, (10.0, 180.0)));
+            assert_eq!(Some(root), context.widget_at(nodes, (10.0, 220.0)));
+        });
+    }
+
+    #[test]
+    fn visible_overflow_should_escape_the_clip_in_primitives() {
+        let quad = |overflow| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            background_color: StyleProp::Value(Color::WHITE),
+            overflow,
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child(
+                "list",
+                KStyle {
+                    render_command: StyleProp::Value(RenderCommand::Clip),
+                    ..KStyle::initial()
+                },
+                |list| {
+                    list.leaf("popup", quad(StyleProp::Value(Overflow::Visible)))
+                        .leaf("item", quad(StyleProp::Default))
+                },
+            )
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("list", rect(0.0, 0.0, 100.0, 200.0));
+        tree.set_layout("popup", rect(0.0, 250.0, 300.0, 100.0));
+        tree.set_layout("item", rect(0.0, 150.0, 100.0, 100.0));
+
+        // The clip region each quad is drawn in, by the width of the quad
+        let mut clip_width = None;
+        let mut clips = Vec::new();
+        for primitive in tree.render_primitives() {
+            match primitive {
+                RenderPrimitive::Clip { layout, .. } => clip_width = Some(layout.width),
+                RenderPrimitive::Quad { layout, .. } => clips.push((layout.width, clip_width)),
+                _ => {}
+            }
+        }
+        // The popup is drawn within the root instead of the list, unlike the item next to it
+        assert_eq!(vec![(300.0, Some(800.0)), (100.0, Some(100.0))], clips);
+    }
+
+    #[test]
+    fn visible_overflow_should_escape_to_its_layer() {
+        let quad = |overflow| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            background_color: StyleProp::Value(Color::WHITE),
+            overflow,
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .layer("dialog", KStyle::initial(), |dialog| {
+                dialog.child(
+                    "list",
+                    KStyle {
+                        render_command: StyleProp::Value(RenderCommand::Clip),
+                        ..KStyle::initial()
+                    },
+                    |list| {
+                        list.leaf("popup", quad(StyleProp::Value(Overflow::Visible)))
+                            .leaf("item", quad(StyleProp::Default))
+                    },
+                )
+            })
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("dialog", rect(100.0, 100.0, 400.0, 300.0));
+        tree.set_layout("list", rect(100.0, 100.0, 100.0, 200.0));
+        tree.set_layout("popup", rect(100.0, 250.0, 300.0, 100.0));
+        tree.set_layout("item", rect(100.0, 150.0, 100.0, 100.0));
+
+        let mut clip_width = None;
+        let mut clips = Vec::new();
+        for primitive in tree.render_primitives() {
+            match primitive {
+                RenderPrimitive::Clip { layout, .. } => clip_width = Some(layout.width),
+                RenderPrimitive::Quad { layout, .. } => clips.push((layout.width, clip_width)),
+                _ => {}
+            }
+        }
+        // The popup is still drawn within its layer, rather than the root beneath it
+        assert_eq!(vec![(300.0, Some(400.0)), (100.0, Some(100.0))], clips);
+    }
+
+    #[test]
+    fn focus_at_should_hit_widgets_where_they_are_drawn() {
+        let moved = KStyle {
+            transform: StyleProp::Value(KTransform::from_translation(Vec2::new(300.0, 0.0))),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("panel", moved, |panel| {
+                panel.child("button", KStyle::initial(), |button| {
+                    button.leaf("label", KStyle::initial())
+                })
+            })
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("panel", rect(0.0, 0.0, 400.0, 200.0));
+        tree.set_layout("button", rect(100.0, 100.0, 200.0, 50.0));
+        tree.set_layout("label", rect(110.0, 110.0, 100.0, 30.0));
+        let (root, button) = (tree.entity("root"), tree.entity("button"));
+        if let (Ok(node_tree), Ok(mut focus_tree)) = (
+            tree.context.tree.try_read(),
+            tree.context.focus_tree.try_write(),
+        ) {
+            focus_tree.add(WrappedIndex(root), &node_tree);
+            focus_tree.add(WrappedIndex(button), &node_tree);
+        }
+
+        tree.with_nodes(|context, _, nodes| {
+            // The label's own layout is under the panel, which isn't focusable
+            assert_eq!(Some(root), context.focus_at(nodes, (120.0, 120.0)));
+            // While it's drawn moved over to the right along with the panel
+            assert_eq!(Some(button), context.focus_at(nodes, (420.0, 120.0)));
+        });
+    }
+
+    #[test]
+    fn text_selection_should_follow_the_scroll_position() {
+        let list = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Clip),
+            scroll: StyleProp::Value((0.0, -50.0)),
+            ..KStyle::initial()
+        };
+        let label = KStyle {
+            font_size: StyleProp::Value(20.0),
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Hello"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("list", list, |list| list.leaf("label", label))
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("list", rect(0.0, 0.0, 200.0, 200.0));
+        tree.set_layout("label", rect(0.0, 100.0, 100.0, 20.0));
+        tree.calculate_nodes();
+        let label = tree.entity("label");
+
+        tree.with_nodes(|context, _, nodes| {
+            let rects = context.text_selection_rects(label, nodes, 0, 5);
+            assert_eq!(1, rects.len());
+            assert_eq!(50.0, rects[0].posy);
+        });
+    }
+
+    #[test]
+    fn text_hit_should_follow_transforms() {
+        let moved = KStyle {
+            transform: StyleProp::Value(KTransform::from_translation(Vec2::new(100.0, 0.0))),
+            ..KStyle::initial()
+        };
+        let label = KStyle {
+            font_size: StyleProp::Value(20.0),
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Hello"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .child("panel", moved, |panel| panel.leaf("label", label))
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("panel", rect(0.0, 0.0, 200.0, 200.0));
+        tree.set_layout("label", rect(0.0, 0.0, 100.0, 20.0));
+        tree.calculate_nodes();
+        let label = tree.entity("label");
+
+        tree.with_nodes(|context, _, nodes| {
+            // The label is drawn starting at 100, so clicking there is before the first char
+            assert_eq!(Some(0), context.text_hit(label, nodes, (101.0, 10.0)));
+            assert_eq!(Some(5), context.text_hit(label, nodes, (190.0, 10.0)));
+        });
+    }
+
+    #[test]
+    fn cursor_at_should_use_the_topmost_overlapping_widget() {
+        let cursor = |icon| KStyle {
+            cursor: StyleProp::Value(KCursorIcon(icon)),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("card", cursor(CursorIcon::Hand))
+            .child("field", cursor(CursorIcon::Text), |field| {
+                field.leaf("label", KStyle::initial())
+            })
+            .build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        tree.set_layout("card", rect(0.0, 0.0, 200.0, 200.0));
+        tree.set_layout("field", rect(100.0, 100.0, 200.0, 200.0));
+        tree.set_layout("label", rect(100.0, 100.0, 100.0, 50.0));
+
+        tree.with_nodes(|context, _, nodes| {
+            // The field is drawn after the card, so it's on top where they overlap
+            assert_eq!(
+                Some(CursorIcon::Text),
+                context.cursor_at(nodes, (150.0, 180.0))
+            );
+            assert_eq!(
+                Some(CursorIcon::Hand),
+                context.cursor_at(nodes, (50.0, 50.0))
+            );
+            // The label has no cursor of its own, so it falls through to the field's
+            assert_eq!(
+                Some(CursorIcon::Text),
+                context.cursor_at(nodes, (110.0, 110.0))
+            );
+            assert_eq!(
+                Some(CursorIcon::Default),
+                context.cursor_at(nodes, (500.0, 500.0))
+            );
+        });
+    }
+
+    #[test]
+    fn measure_should_match_the_laid_out_size() {
+        let sized = |width, height| KStyle {
+            width: StyleProp::Value(width),
+            height: StyleProp::Value(height),
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            ..KStyle::initial()
+        };
+        let label = KStyle {
+            font_size: StyleProp::Value(20.0),
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Hello"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(Units::Pixels(800.0), Units::Pixels(600.0)))
+            .leaf(
+                "panel",
+                sized(Units::Percentage(50.0), Units::Pixels(100.0)),
+            )
+            .leaf("card", sized(Units::ViewportWidth(25.0), Units::Rem(2.0)))
+            .leaf("label", label)
+            .build();
+        tree.context.set_root_font_size(20.0);
+        tree.context.set_viewport_size((1280.0, 720.0));
+
+        // Text is measured once its parent has been laid out
+        for _ in 0..2 {
+            tree.calculate_nodes();
+            tree.layout();
+        }
+
+        // Widgets are measured before the layout catches up with the new sizes
+        tree.context.set_root_font_size(10.0);
+        tree.context.set_viewport_size((640.0, 480.0));
+        let names = ["panel", "card", "label"];
+        let measured = {
+            let fonts = HeadlessAssets::default();
+            let mut state =
+                SystemState::<(Query<&Node>, Query<&ComputedStyles>, Query<&PseudoStyles>)>::new(
+                    &mut tree.world,
+                );
+            let (nodes, all_styles, pseudo_styles) = state.get(&tree.world);
+            names.map(|name| {
+                tree.context.measure(
+                    tree.entity(name),
+                    &nodes,
+                    &fonts,
+                    &all_styles,
+                    &pseudo_styles,
+                )
+            })
+        };
+        assert_eq!(Some((160.0, 20.0)), measured[1]);
+
+        tree.calculate_nodes();
+        tree.layout();
+        for (name, measured) in names.into_iter().zip(measured) {
+            let layout = tree.layout_of(name);
+            assert_eq!(
+                Some((layout.width, layout.height)),
+                measured,
+                "{} was measured differently than it was laid out",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn assets_ready_callbacks_should_run_once() {
+        let tree = TreeBuilder::new()
+            .node("root", KStyle::initial())
+            .leaf("label", KStyle::initial())
+            .build();
+        let label = WrappedIndex(tree.entity("label"));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        tree.context.on_assets_ready(move || {
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+        let notify = |context: &KayakRootContext| {
+            if let Ok(node_tree) = context.tree.clone().try_read() {
+                context.notify_assets_ready(&node_tree);
+            }
+        };
+
+        tree.context.set_asset_pending(label, true);
+        // A widget that was removed while its font was loading doesn't hold anything up
+        tree.context
+            .set_asset_pending(WrappedIndex(Entity::from_raw(99)), true);
+        notify(&tree.context);
+        assert_eq!(0, calls.load(Ordering::SeqCst));
+
+        tree.context.set_asset_pending(label, false);
+        notify(&tree.context);
+        notify(&tree.context);
+        assert_eq!(1, calls.load(Ordering::SeqCst));
+        assert!(!tree.context.has_pending_assets());
+    }
+
+    #[test]
+    fn culled_primitives_should_only_keep_nodes_in_the_viewport() {
+        let row = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            background_color: StyleProp::Value(Color::WHITE),
+            ..KStyle::initial()
+        };
+        let mut builder = TreeBuilder::new().node("root", KStyle::initial());
+        for index in 0..6 {
+            builder = builder.leaf(&format!("row {}", index), row.clone());
+        }
+        let mut tree = builder.build();
+        tree.set_layout("root", rect(0.0, 0.0, 800.0, 600.0));
+        for index in 0..6 {
+            let posy = index as f32 * 100.0;
+            tree.set_layout(&format!("row {}", index), rect(0.0, posy, 800.0, 100.0));
+        }
+
+        let mut state = SystemState::<(Query<&Node>, Query<&WidgetName>)>::new(&mut tree.world);
+        let (nodes, widget_names) = state.get(&tree.world);
+        let rows = |primitives: Vec<RenderPrimitive>| {
+            primitives
+                .iter()
+                .filter_map(|primitive| match primitive {
+                    RenderPrimitive::Quad { layout, .. } => Some(layout.posy),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Only the rows overlapping the viewport are kept
+        let viewport = rect(0.0, 50.0, 800.0, 200.0);
+        assert_eq!(
+            vec![0.0, 100.0, 200.0],
+            rows(
+                tree.context
+                    .build_render_primitives_culled(&nodes, &widget_names, viewport)
+            )
+        );
+        assert_eq!(
+            6,
+            rows(tree.context.build_render_primitives(&nodes, &widget_names)).len()
+        );
+    }
+}
@@ -37,4 +37,8 @@ impl ContextEntities {
         let inner = self.ce.get(&parent_id).unwrap();
         inner.get(&T::default().type_id()).map(|e| *e)
     }
+
+    pub fn remove_context_entities(&self, parent_id: Entity) {
+        self.ce.remove(&parent_id);
+    }
 }
@@ -7,7 +7,7 @@ use crate::{
     context::KayakRootContext,
     cursor::{CursorEvent, PointerEvents, ScrollEvent, ScrollUnit},
     event::{Event, EventType},
-    focus_tree::FocusTree,
+    focus_tree::{is_focusable, FocusTree},
     input_event::{InputEvent, InputEventCategory},
     keyboard_event::{KeyboardEvent, KeyboardModifiers},
     layout::Rect,
@@ -15,7 +15,6 @@ use crate::{
     on_event::OnEvent,
     prelude::KayakWidgetContext,
     styles::{ComputedStyles, KStyle, RenderCommand},
-    Focusable,
 };
 
 type EventMap = HashMap<WrappedIndex, HashSet<EventType>>;
@@ -129,8 +128,8 @@ impl EventDispatcher {
     /// Returns true if the cursor is currently over a valid widget
     ///
     /// For the purposes of this method, a valid widget is one which has the means to display a visual component on its own.
-    /// This means widgets specified with [`RenderCommand::Empty`], [`RenderCommand::Layout`], or [`RenderCommand::Clip`]
-    /// do not meet the requirements to "contain" the cursor.
+    /// This means widgets specified with [`RenderCommand::Empty`], [`RenderCommand::Layout`], [`RenderCommand::Clip`],
+    /// or [`RenderCommand::ClipMask`] do not meet the requirements to "contain" the cursor.
     #[allow(dead_code)]
     pub fn contains_cursor(&self) -> bool {
         self.contains_cursor.unwrap_or_default()
@@ -183,6 +182,7 @@ impl EventDispatcher {
     ) {
         let events = { self.build_event_stream(input_events, context, world) };
         self.dispatch_events(events, context, world);
+        context.record_focus_change();
     }
 
     /// Dispatch an [Event](crate::Event)
@@ -245,6 +245,7 @@ impl EventDispatcher {
                             context.widget_state.clone(),
                             context.order_tree.clone(),
                             context.index.clone(),
+                            context.unmount_callbacks.clone(),
                             None,
                         );
                         node_event.run_on_change(world, widget_context);
@@ -315,11 +316,9 @@ impl EventDispatcher {
         let mut states: HashMap<EventType, EventState> = HashMap::new();
 
         if let Ok(node_tree) = context.tree.try_read() {
-            let root = if let Some(root) = node_tree.root_node {
-                root
-            } else {
+            if node_tree.root_node.is_none() {
                 return event_stream;
-            };
+            }
 
             // === Setup Cursor States === //
             let old_hovered = self.hovered;
@@ -380,7 +379,7 @@ impl EventDispatcher {
                 }
             } else {
                 // No capturing widget -> process cursor events as normal
-                let mut stack: Vec<TreeNode> = vec![(root, 0)];
+                let mut stack: Vec<TreeNode> = node_tree.roots().map(|root| (root, 0)).collect();
                 while !stack.is_empty() {
                     let (current, depth) = stack.pop().unwrap();
                     let mut enter_children = true;
@@ -451,6 +450,11 @@ impl EventDispatcher {
                 // These events are ones that require a specific target and need the tree to be evaluated before selecting the best match
                 for (event_type, state) in states {
                     if let Some(node) = state.best_match {
+                        if matches!(event_type, EventType::Focus) && !focus_tree.can_focus(node) {
+                            // Focus is trapped elsewhere (e.g. within a modal)
+                            continue;
+                        }
+
                         event_stream.push(Event::new(node.0, event_type));
 
                         match event_type {
@@ -566,7 +570,7 @@ impl EventDispatcher {
                     }
 
                     if self.wants_cursor.is_none() || !self.wants_cursor.unwrap_or_default() {
-                        let focusable = world.get::<Focusable>(node.0).is_some();
+                        let focusable = is_focusable(world, node.0);
                         // Check if the cursor moved onto a focusable widget (i.e. one that would want it)
                         if focusable {
                             self.wants_cursor = Some(is_contained);
@@ -596,7 +600,7 @@ impl EventDispatcher {
                             EventType::MouseDown(cursor_event),
                         );
 
-                        if world.get::<Focusable>(node.0).is_some() {
+                        if is_focusable(world, node.0) {
                             Self::update_state(states, (node, depth), &layout, EventType::Focus);
                         }
 
@@ -779,12 +783,15 @@ impl EventDispatcher {
     /// Checks if the given widget is eligible to "contain" the cursor (i.e. the cursor is considered contained when hovering over it)
     ///
     /// Currently a valid widget is defined as one where:
-    /// * RenderCommands is neither `Empty` nor `Layout` nor `Clip`
+    /// * RenderCommands is neither `Empty` nor `Layout` nor `Clip` nor `ClipMask`
     fn can_contain_cursor(widget_styles: &KStyle) -> bool {
         let cmds = widget_styles.render_command.resolve();
         !matches!(
             cmds,
-            RenderCommand::Empty | RenderCommand::Layout | RenderCommand::Clip
+            RenderCommand::Empty
+                | RenderCommand::Layout
+                | RenderCommand::Clip
+                | RenderCommand::ClipMask { .. }
         )
     }
 
@@ -818,6 +825,13 @@ impl EventDispatcher {
                             focus_tree.focus(index);
                         }
                         self.dispatch_events(events, context, world);
+                    } else if let Some(current_focus) = current_focus {
+                        // Focus left the tree without wrapping around
+                        self.dispatch_events(
+                            vec![Event::new(current_focus.0, EventType::Blur)],
+                            context,
+                            world,
+                        );
                     }
                 }
                 _ => {}
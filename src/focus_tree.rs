@@ -1,18 +1,65 @@
 use bevy::{
-    prelude::{Component, Reflect, ReflectComponent},
+    prelude::{Component, Entity, Reflect, ReflectComponent, World},
     utils::HashMap,
 };
 
-use crate::{node::WrappedIndex, prelude::Tree};
+use std::cmp::Ordering;
+
+use crate::{
+    node::WrappedIndex,
+    prelude::Tree,
+    styles::{ComputedStyles, StyleProp},
+    tree::UpwardIterator,
+};
 
 #[derive(Component, Reflect, Default, Clone, Copy)]
 #[reflect(Component)]
 pub struct Focusable;
 
-#[derive(Debug, Default, PartialEq, Eq)]
+/// Checks if the given widget can currently receive focus
+///
+/// This is true for [`Focusable`] widgets that aren't [disabled](crate::styles::KStyle::disabled).
+pub(crate) fn is_focusable(world: &World, entity: Entity) -> bool {
+    let is_disabled = world.get::<ComputedStyles>(entity).map_or(false, |styles| {
+        matches!(styles.0.disabled, StyleProp::Value(true))
+    });
+    world.get::<Focusable>(entity).is_some() && !is_disabled
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct FocusTree {
     tree: Tree,
     current_focus: Option<WrappedIndex>,
+    /// The stack of focus traps, the last of which is active
+    traps: Vec<WrappedIndex>,
+    /// Whether traversal wraps around when moving past either end of the tree
+    wrap: bool,
+    /// The focus as of the last call to [`FocusTree::take_change`]
+    reported_focus: Option<WrappedIndex>,
+    /// The [focus index](crate::styles::KStyle::focus_index) of each widget that has one
+    focus_indices: HashMap<WrappedIndex, i32>,
+}
+
+/// A change in which widget has focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FocusChange {
+    /// The widget that lost focus, if any
+    pub lost: Option<Entity>,
+    /// The widget that gained focus, if any
+    pub gained: Option<Entity>,
+}
+
+impl Default for FocusTree {
+    fn default() -> Self {
+        Self {
+            tree: Tree::default(),
+            current_focus: None,
+            traps: Vec::default(),
+            wrap: true,
+            reported_focus: None,
+            focus_indices: HashMap::default(),
+        }
+    }
 }
 
 /// A struct used to track and calculate widget focusability, based on the following rule:
@@ -39,20 +86,119 @@ impl FocusTree {
         while let Some(parent) = widget_context.get_parent(current_index) {
             current_index = parent;
             if self.contains(parent) {
-                self.tree.add(index, Some(parent));
+                self.insert_ordered(index, parent, widget_context);
                 return;
             }
         }
 
-        if self.tree.root_node.is_none() {
+        if let Some(root) = self.tree.root_node {
+            // Focusables in another root layer have no focusable ancestor
+            if widget_context.layers.contains(&current_index) {
+                self.insert_ordered(index, root, widget_context);
+            }
+        } else {
             // Set root node
             self.tree.add(index, None);
             self.focus(index);
         }
     }
 
+    /// Inserts the index under the given focusable parent, keeping siblings in focus order
+    ///
+    /// Any focusable siblings that are descendants of the index (in the widget tree) are moved
+    /// under it, so focusables can be toggled anywhere in the tree without a full rebuild.
+    fn insert_ordered(&mut self, index: WrappedIndex, parent: WrappedIndex, widget_context: &Tree) {
+        let siblings = self.tree.children.remove(&parent).unwrap_or_default();
+        let (adopted, mut siblings): (Vec<_>, Vec<_>) = siblings
+            .into_iter()
+            .partition(|sibling| widget_context.is_descendant(*sibling, index));
+
+        let position = siblings
+            .iter()
+            .position(|sibling| {
+                self.cmp_focus_order(index, *sibling, widget_context) == Ordering::Less
+            })
+            .unwrap_or(siblings.len());
+        siblings.insert(position, index);
+        self.tree.children.insert(parent, siblings);
+        self.tree.parents.insert(index, parent);
+
+        if !adopted.is_empty() {
+            for child in adopted.iter() {
+                self.tree.parents.insert(*child, index);
+            }
+            self.tree.children.insert(index, adopted);
+        }
+    }
+
+    /// Moves the focusables within the given widget's subtree to their new place, once the widget
+    /// has been moved to another parent in the widget tree
+    pub fn reparent(&mut self, index: WrappedIndex, widget_context: &Tree) {
+        let within =
+            |node: WrappedIndex| node == index || widget_context.is_descendant(node, index);
+        let moved = self
+            .tree
+            .parents
+            .iter()
+            .filter(|(child, parent)| within(**child) && !within(**parent))
+            .map(|(child, _)| *child)
+            .collect::<Vec<_>>();
+
+        for child in moved {
+            if let Some(parent) = self.tree.parents.remove(&child) {
+                if let Some(siblings) = self.tree.children.get_mut(&parent) {
+                    siblings.retain(|sibling| *sibling != child);
+                }
+            }
+
+            let new_parent = UpwardIterator::new(widget_context, Some(child), false)
+                .find(|ancestor| self.contains(*ancestor))
+                .or(self.tree.root_node);
+            if let Some(new_parent) = new_parent {
+                self.insert_ordered(child, new_parent, widget_context);
+            }
+        }
+    }
+
+    /// Sets the [focus index](crate::styles::KStyle::focus_index) of the given widget
+    ///
+    /// If the widget is already in the tree, it's moved to its new place among its siblings.
+    pub fn set_focus_index(
+        &mut self,
+        index: WrappedIndex,
+        focus_index: Option<i32>,
+        widget_context: &Tree,
+    ) {
+        let changed = match focus_index {
+            Some(focus_index) => self.focus_indices.insert(index, focus_index) != Some(focus_index),
+            None => self.focus_indices.remove(&index).is_some(),
+        };
+        if !changed {
+            return;
+        }
+
+        if let Some(parent) = self.tree.get_parent(index) {
+            if let Some(mut siblings) = self.tree.children.remove(&parent) {
+                siblings.sort_by(|a, b| self.cmp_focus_order(*a, *b, widget_context));
+                self.tree.children.insert(parent, siblings);
+            }
+        }
+    }
+
+    /// Compares two widgets by their focus index, then by their order in the widget tree
+    ///
+    /// Widgets without a focus index come after those with one.
+    fn cmp_focus_order(&self, a: WrappedIndex, b: WrappedIndex, widget_context: &Tree) -> Ordering {
+        let focus_index = |index| self.focus_indices.get(&index).copied().unwrap_or(i32::MAX);
+        focus_index(a)
+            .cmp(&focus_index(b))
+            .then_with(|| widget_context.cmp_order(a, b))
+    }
+
     /// Remove the given focusable index from the tree
     pub fn remove(&mut self, index: WrappedIndex) {
+        self.traps.retain(|trap| *trap != index);
+        self.focus_indices.remove(&index);
         if self.current_focus == Some(index) {
             self.blur();
         }
@@ -69,22 +215,79 @@ impl FocusTree {
         self.tree.contains(index)
     }
 
-    /// Clear the tree and remove the current focus
+    /// Clear the tree, any focus traps, and remove the current focus
     pub fn clear(&mut self) {
         self.tree = Tree::default();
+        self.traps.clear();
+        self.focus_indices.clear();
         self.blur();
     }
 
     /// Set the current focus
+    ///
+    /// While a focus trap is active, indices outside of it can't be focused.
     pub fn focus(&mut self, index: WrappedIndex) {
-        self.current_focus = Some(index);
+        if self.can_focus(index) {
+            self.current_focus = Some(index);
+        }
     }
 
     /// Remove the current focus
     ///
-    /// This returns focus to the root node
+    /// This returns focus to the root node, or the root of the active focus trap
     pub fn blur(&mut self) {
-        self.current_focus = self.tree.root_node;
+        self.current_focus = self.trap().or(self.tree.root_node);
+    }
+
+    /// Constrains focus to the given focusable index and its focusable descendants
+    ///
+    /// Traps can be nested, only the most recently pushed trap is active. This is useful for
+    /// keeping focus within a modal dialog. If focus is currently outside of the trap, it is
+    /// moved to the trap's root.
+    pub fn push_trap(&mut self, root: WrappedIndex) {
+        if !self.contains(root) {
+            log::warn!("Cannot trap focus in unfocusable node: {}", root.0.index());
+            return;
+        }
+
+        self.traps.push(root);
+        if !self
+            .current_focus
+            .map_or(false, |index| self.can_focus(index))
+        {
+            self.current_focus = Some(root);
+        }
+    }
+
+    /// Removes the active focus trap, returning its root
+    pub fn pop_trap(&mut self) -> Option<WrappedIndex> {
+        self.traps.pop()
+    }
+
+    /// Get the root of the active focus trap
+    pub fn trap(&self) -> Option<WrappedIndex> {
+        self.traps.last().copied()
+    }
+
+    /// Checks if the given index can be focused under the active focus trap
+    pub fn can_focus(&self, index: WrappedIndex) -> bool {
+        match self.trap() {
+            Some(trap) => index == trap || self.tree.is_descendant(index, trap),
+            None => true,
+        }
+    }
+
+    /// Set whether traversal wraps around when moving past either end of the tree
+    ///
+    /// When disabled, moving past the last (or first) focusable index returns `None`, allowing
+    /// focus to leave the tree. This applies to the active focus trap as well. Enabled by default.
+    pub fn set_focus_wrap(&mut self, wrap: bool) {
+        self.wrap = wrap;
+    }
+
+    /// Checks if traversal wraps around when moving past either end of the tree
+    pub fn focus_wrap(&self) -> bool {
+        self.wrap
     }
 
     /// Get the currently focused index
@@ -92,6 +295,22 @@ impl FocusTree {
         self.current_focus
     }
 
+    /// Returns how the focus changed since this was last called, if it changed at all
+    ///
+    /// Any intermediate changes are combined into a single change.
+    pub fn take_change(&mut self) -> Option<FocusChange> {
+        if self.current_focus == self.reported_focus {
+            return None;
+        }
+
+        let change = FocusChange {
+            lost: self.reported_focus.map(|index| index.0),
+            gained: self.current_focus.map(|index| index.0),
+        };
+        self.reported_focus = self.current_focus;
+        Some(change)
+    }
+
     /// Change focus to the next focusable index
     pub fn next(&mut self) -> Option<WrappedIndex> {
         self.current_focus = self.peek_next();
@@ -106,7 +325,57 @@ impl FocusTree {
 
     /// Peek the next focusable index without actually changing focus
     pub fn peek_next(&self) -> Option<WrappedIndex> {
-        if let Some(index) = self.current_focus {
+        self.peek_trapped(Self::next_from)
+    }
+
+    /// Peek the previous focusable index without actually changing focus
+    pub fn peek_prev(&self) -> Option<WrappedIndex> {
+        self.peek_trapped(Self::prev_from)
+    }
+
+    /// Steps through the tree from the current focus until reaching an index within the active trap
+    fn peek_trapped(
+        &self,
+        step: fn(&Self, Option<WrappedIndex>) -> Option<WrappedIndex>,
+    ) -> Option<WrappedIndex> {
+        let mut next = step(self, self.current_focus);
+        if !self.wrap && self.current_focus.is_some() {
+            // Focusables within a trap are contiguous, so leaving it means passing one of its ends
+            return next.filter(|index| self.can_focus(*index));
+        }
+
+        for _ in 0..self.tree.len() {
+            match next {
+                Some(index) if !self.can_focus(index) => next = step(self, Some(index)),
+                _ => break,
+            }
+        }
+        next.filter(|index| self.can_focus(*index))
+            .or_else(|| self.trap())
+    }
+
+    /// Returns every index that can currently be focused, in the order [`next`](Self::next) visits them
+    ///
+    /// The order starts at the root, or the root of the active focus trap, and follows any
+    /// focus indices. Calling [`prev`](Self::prev) visits them in reverse.
+    pub fn order(&self) -> Vec<WrappedIndex> {
+        let mut order = Vec::new();
+        let mut stack = self
+            .trap()
+            .or(self.tree.root_node)
+            .into_iter()
+            .collect::<Vec<_>>();
+        while let Some(index) = stack.pop() {
+            order.push(index);
+            if let Some(children) = self.tree.children.get(&index) {
+                stack.extend(children.iter().rev());
+            }
+        }
+        order
+    }
+
+    fn next_from(&self, current: Option<WrappedIndex>) -> Option<WrappedIndex> {
+        if let Some(index) = current {
             // === Enter Children === //
             if let Some(child) = self.tree.get_first_child(index) {
                 return Some(child);
@@ -125,15 +394,18 @@ impl FocusTree {
                 }
                 next = parent;
             }
+
+            if !self.wrap {
+                return None;
+            }
         }
 
         // Default to root node to begin the cycle again
         self.tree.root_node
     }
 
-    /// Peek the previous focusable index without actually changing focus
-    pub fn peek_prev(&self) -> Option<WrappedIndex> {
-        if let Some(index) = self.current_focus {
+    fn prev_from(&self, current: Option<WrappedIndex>) -> Option<WrappedIndex> {
+        if let Some(index) = current {
             // === Enter Siblings === //
             if let Some(sibling) = self.tree.get_prev_sibling(index) {
                 let mut next = sibling;
@@ -148,6 +420,10 @@ impl FocusTree {
                 return Some(parent);
             }
 
+            if !self.wrap {
+                return None;
+            }
+
             // === Go Back Down === //
             let mut next = index;
             while let Some(child) = self.tree.get_last_child(next) {
@@ -220,10 +496,11 @@ impl FocusTracker {
 
 #[cfg(test)]
 mod tests {
-    use crate::focus_tree::FocusTree;
+    use crate::focus_tree::{is_focusable, FocusChange, FocusTree, Focusable};
     use crate::node::WrappedIndex;
+    use crate::styles::{ComputedStyles, KStyle, StyleProp};
     use crate::tree::Tree;
-    use bevy::prelude::Entity;
+    use bevy::prelude::{Entity, World};
 
     #[test]
     fn next_should_cycle() {
@@ -308,4 +585,249 @@ mod tests {
 
         // etc.
     }
+
+    #[test]
+    fn toggling_focusable_should_keep_order() {
+        let mut focus_tree = FocusTree::default();
+        let mut tree = Tree::default();
+
+        let a = WrappedIndex(Entity::from_raw(0));
+        tree.add(a, None);
+        let a_a = WrappedIndex(Entity::from_raw(1));
+        tree.add(a_a, Some(a));
+        let a_b = WrappedIndex(Entity::from_raw(2));
+        tree.add(a_b, Some(a));
+        let a_a_a = WrappedIndex(Entity::from_raw(3));
+        tree.add(a_a_a, Some(a_a));
+        let a_a_b = WrappedIndex(Entity::from_raw(4));
+        tree.add(a_a_b, Some(a_a));
+
+        // The mid-level node starts out unfocusable
+        focus_tree.add(a, &tree);
+        focus_tree.add(a_b, &tree);
+        focus_tree.add(a_a_a, &tree);
+        focus_tree.add(a_a_b, &tree);
+        focus_tree.focus(a_a_b);
+
+        assert_eq!(Some(a_b), focus_tree.next());
+        focus_tree.focus(a_a_b);
+
+        // Becomes focusable
+        focus_tree.add(a_a, &tree);
+        assert_eq!(Some(a_a), focus_tree.tree().get_parent(a_a_a));
+        assert_eq!(Some(a_a_b), focus_tree.current());
+        focus_tree.focus(a);
+        assert_eq!(Some(a_a), focus_tree.next());
+        assert_eq!(Some(a_a_a), focus_tree.next());
+        assert_eq!(Some(a_a_b), focus_tree.next());
+        assert_eq!(Some(a_b), focus_tree.next());
+
+        // Becomes unfocusable again
+        focus_tree.remove(a_a);
+        assert!(!focus_tree.contains(a_a));
+        focus_tree.focus(a);
+        assert_eq!(Some(a_a_a), focus_tree.next());
+        assert_eq!(Some(a_a_b), focus_tree.next());
+        assert_eq!(Some(a_b), focus_tree.next());
+    }
+
+    #[test]
+    fn trap_should_constrain_traversal() {
+        let mut focus_tree = FocusTree::default();
+        let mut tree = Tree::default();
+
+        let a = WrappedIndex(Entity::from_raw(0));
+        tree.add(a, None);
+        let a_a = WrappedIndex(Entity::from_raw(1));
+        tree.add(a_a, Some(a));
+        let a_b = WrappedIndex(Entity::from_raw(2));
+        tree.add(a_b, Some(a));
+        let a_b_a = WrappedIndex(Entity::from_raw(3));
+        tree.add(a_b_a, Some(a_b));
+        let a_b_b = WrappedIndex(Entity::from_raw(4));
+        tree.add(a_b_b, Some(a_b));
+
+        focus_tree.add(a, &tree);
+        focus_tree.add(a_a, &tree);
+        focus_tree.add(a_b, &tree);
+        focus_tree.add(a_b_a, &tree);
+        focus_tree.add(a_b_b, &tree);
+
+        // Focus outside of the trap moves into it
+        focus_tree.focus(a_a);
+        focus_tree.push_trap(a_b);
+        assert_eq!(Some(a_b), focus_tree.current());
+
+        assert_eq!(Some(a_b_a), focus_tree.next());
+        assert_eq!(Some(a_b_b), focus_tree.next());
+        assert_eq!(Some(a_b), focus_tree.next());
+        assert_eq!(Some(a_b_b), focus_tree.prev());
+
+        // Can't focus outside of the trap
+        focus_tree.focus(a_a);
+        assert_eq!(Some(a_b_b), focus_tree.current());
+
+        assert_eq!(Some(a_b), focus_tree.pop_trap());
+        assert_eq!(Some(a), focus_tree.next());
+    }
+
+    #[test]
+    fn disabled_wrap_should_stop_at_ends() {
+        let mut focus_tree = FocusTree::default();
+        let mut tree = Tree::default();
+        focus_tree.set_focus_wrap(false);
+
+        // Empty trees have nothing to focus
+        assert_eq!(None, focus_tree.peek_next());
+        assert_eq!(None, focus_tree.peek_prev());
+
+        let a = WrappedIndex(Entity::from_raw(0));
+        tree.add(a, None);
+        let a_a = WrappedIndex(Entity::from_raw(1));
+        tree.add(a_a, Some(a));
+        let a_b = WrappedIndex(Entity::from_raw(2));
+        tree.add(a_b, Some(a));
+        let a_b_a = WrappedIndex(Entity::from_raw(3));
+        tree.add(a_b_a, Some(a_b));
+
+        focus_tree.add(a, &tree);
+        focus_tree.add(a_a, &tree);
+        focus_tree.add(a_b, &tree);
+        focus_tree.add(a_b_a, &tree);
+
+        assert_eq!(Some(a), focus_tree.current());
+        assert_eq!(None, focus_tree.peek_prev());
+        assert_eq!(Some(a_a), focus_tree.next());
+        assert_eq!(Some(a_b), focus_tree.next());
+        assert_eq!(Some(a_b_a), focus_tree.next());
+        assert_eq!(None, focus_tree.peek_next());
+        assert_eq!(Some(a_b), focus_tree.prev());
+
+        // Traps stop at their own ends
+        focus_tree.push_trap(a_b);
+        assert_eq!(None, focus_tree.peek_prev());
+        assert_eq!(Some(a_b_a), focus_tree.next());
+        assert_eq!(None, focus_tree.next());
+    }
+
+    #[test]
+    fn disabled_widgets_should_not_be_focusable() {
+        let mut world = World::new();
+        let disabled_style = |disabled| {
+            ComputedStyles(KStyle {
+                disabled: StyleProp::Value(disabled),
+                ..Default::default()
+            })
+        };
+
+        let button = world.spawn((Focusable, disabled_style(true))).id();
+        let label = world.spawn(disabled_style(false)).id();
+        assert!(!is_focusable(&world, button));
+        assert!(!is_focusable(&world, label));
+
+        // Re-enabling restores focusability
+        world.entity_mut(button).insert(disabled_style(false));
+        assert!(is_focusable(&world, button));
+    }
+
+    #[test]
+    fn take_change_should_report_net_focus_changes() {
+        let mut focus_tree = FocusTree::default();
+        let mut tree = Tree::default();
+
+        let a = WrappedIndex(Entity::from_raw(0));
+        tree.add(a, None);
+        let a_a = WrappedIndex(Entity::from_raw(1));
+        tree.add(a_a, Some(a));
+
+        focus_tree.add(a, &tree);
+        focus_tree.add(a_a, &tree);
+        assert_eq!(
+            Some(FocusChange {
+                lost: None,
+                gained: Some(a.0),
+            }),
+            focus_tree.take_change()
+        );
+        assert_eq!(None, focus_tree.take_change());
+
+        focus_tree.next();
+        assert_eq!(
+            Some(FocusChange {
+                lost: Some(a.0),
+                gained: Some(a_a.0),
+            }),
+            focus_tree.take_change()
+        );
+
+        // Rebuilding the tree and restoring focus isn't a change
+        focus_tree.clear();
+        focus_tree.add(a, &tree);
+        focus_tree.add(a_a, &tree);
+        focus_tree.focus(a_a);
+        assert_eq!(None, focus_tree.take_change());
+    }
+
+    #[test]
+    fn focus_index_should_override_tree_order() {
+        let mut focus_tree = FocusTree::default();
+        let mut tree = Tree::default();
+
+        let form = WrappedIndex(Entity::from_raw(0));
+        tree.add(form, None);
+        let name = WrappedIndex(Entity::from_raw(1));
+        tree.add(name, Some(form));
+        let email = WrappedIndex(Entity::from_raw(2));
+        tree.add(email, Some(form));
+        let submit = WrappedIndex(Entity::from_raw(3));
+        tree.add(submit, Some(form));
+
+        focus_tree.add(form, &tree);
+        focus_tree.add(name, &tree);
+        focus_tree.set_focus_index(submit, Some(0), &tree);
+        focus_tree.add(submit, &tree);
+        focus_tree.add(email, &tree);
+
+        assert_eq!(Some(submit), focus_tree.next());
+        assert_eq!(Some(name), focus_tree.next());
+        assert_eq!(Some(email), focus_tree.next());
+
+        // Changing the index of a widget already in the tree moves it
+        focus_tree.set_focus_index(email, Some(1), &tree);
+        focus_tree.focus(form);
+        assert_eq!(Some(submit), focus_tree.next());
+        assert_eq!(Some(email), focus_tree.next());
+        assert_eq!(Some(name), focus_tree.next());
+    }
+
+    #[test]
+    fn order_should_match_traversal() {
+        let mut focus_tree = FocusTree::default();
+        let mut tree = Tree::default();
+
+        let form = WrappedIndex(Entity::from_raw(0));
+        tree.add(form, None);
+        let fields = WrappedIndex(Entity::from_raw(1));
+        tree.add(fields, Some(form));
+        let name = WrappedIndex(Entity::from_raw(2));
+        tree.add(name, Some(fields));
+        let email = WrappedIndex(Entity::from_raw(3));
+        tree.add(email, Some(fields));
+        let submit = WrappedIndex(Entity::from_raw(4));
+        tree.add(submit, Some(form));
+
+        for index in [form, fields, name, email, submit] {
+            focus_tree.add(index, &tree);
+        }
+        focus_tree.set_focus_index(email, Some(0), &tree);
+
+        let order = focus_tree.order();
+        assert_eq!(vec![form, fields, email, name, submit], order);
+        for expected in order.iter().skip(1) {
+            assert_eq!(Some(*expected), focus_tree.next());
+        }
+
+        focus_tree.push_trap(fields);
+        assert_eq!(vec![fields, email, name], focus_tree.order());
+    }
 }
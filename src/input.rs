@@ -11,6 +11,7 @@ use crate::{
     context::{CustomEventReader, KayakRootContext},
     event_dispatcher::EventDispatcher,
     input_event::InputEvent,
+    node::Node,
 };
 
 pub(crate) fn process_events(world: &mut World) {
@@ -124,6 +125,16 @@ pub(crate) fn process_events(world: &mut World) {
     for (entity, mut event_dispatcher, mut context) in context_data.drain(..) {
         event_dispatcher.process_events(&input_events, &mut context, world);
 
+        let pointer = event_dispatcher.current_mouse_position();
+        let pressed = event_dispatcher.is_mouse_pressed();
+        query_world::<(Commands, Query<&'static Node>), _, _>(
+            |(mut commands, nodes)| {
+                context.set_pointer(&mut commands, &nodes, Some(pointer));
+                context.set_pointer_pressed(&mut commands, pressed);
+            },
+            world,
+        );
+
         world.entity_mut(entity).insert((event_dispatcher, context));
     }
 }
@@ -2,13 +2,17 @@ use std::collections::hash_map::Iter;
 use std::collections::HashMap;
 
 use bevy::{
-    prelude::{Entity, Query},
+    prelude::{Component, Entity, Query},
     reflect::{FromReflect, Reflect},
+    utils::HashSet,
 };
 use morphorm::Cache;
 pub use morphorm::GeometryChanged;
 
-use crate::node::WrappedIndex;
+use crate::context::is_within;
+use crate::node::{Node, WrappedIndex};
+use crate::styles::{AlignItems, Corner, KPositionType, LayoutType, StyleProp, Units};
+use crate::tree::{Tree, VisibleTree};
 
 #[derive(Debug, Reflect, FromReflect, Default, Clone, Copy, PartialEq)]
 pub struct Rect {
@@ -17,6 +21,8 @@ pub struct Rect {
     pub width: f32,
     pub height: f32,
     pub z_index: f32,
+    /// The position of the primitive drawn in this layout in the paint order of its context
+    pub paint_order: u32,
 }
 
 impl Rect {
@@ -24,8 +30,97 @@ impl Rect {
         (point.0 >= self.posx && point.0 <= self.posx + self.width)
             && (point.1 >= self.posy && point.1 <= self.posy + self.height)
     }
+
+    /// Checks if the point is inside this rect with the given corners rounded off
+    pub fn contains_rounded(&self, point: &(f32, f32), radius: &Corner<f32>) -> bool {
+        if !self.contains(point) {
+            return false;
+        }
+
+        // Each corner with the direction it points in
+        let corners = [
+            (radius.top_left, -1.0, -1.0),
+            (radius.top_right, 1.0, -1.0),
+            (radius.bottom_left, -1.0, 1.0),
+            (radius.bottom_right, 1.0, 1.0),
+        ];
+        let center = (self.posx + self.width / 2.0, self.posy + self.height / 2.0);
+        corners.into_iter().all(|(radius, x, y)| {
+            // The center of the corner's circle is `radius` in from both edges
+            let corner_x = center.0 + x * (self.width / 2.0 - radius);
+            let corner_y = center.1 + y * (self.height / 2.0 - radius);
+            let (dx, dy) = (point.0 - corner_x, point.1 - corner_y);
+            // Only points past the circle's center on both axes are in the rounded off part
+            let in_corner = dx * x > 0.0 && dy * y > 0.0;
+            radius <= 0.0 || !in_corner || dx * dx + dy * dy <= radius * radius
+        })
+    }
+
+    /// Returns true if this rect overlaps the other rect
+    pub fn intersects(&self, other: &Rect) -> bool {
+        self.posx < other.posx + other.width
+            && self.posx + self.width > other.posx
+            && self.posy < other.posy + other.height
+            && self.posy + self.height > other.posy
+    }
+
+    /// Returns the area covered by both this rect and the other rect, if they overlap
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        let (left, top) = (self.posx.max(other.posx), self.posy.max(other.posy));
+        let right = (self.posx + self.width).min(other.posx + other.width);
+        let bottom = (self.posy + self.height).min(other.posy + other.height);
+        Some(Rect {
+            posx: left,
+            posy: top,
+            width: right - left,
+            height: bottom - top,
+            z_index: self.z_index,
+            paint_order: self.paint_order,
+        })
+    }
+
+    /// Returns the smallest rect that covers both this rect and the other rect
+    pub fn union(&self, other: &Rect) -> Rect {
+        let (left, top) = (self.posx.min(other.posx), self.posy.min(other.posy));
+        let right = (self.posx + self.width).max(other.posx + other.width);
+        let bottom = (self.posy + self.height).max(other.posy + other.height);
+        Rect {
+            posx: left,
+            posy: top,
+            width: right - left,
+            height: bottom - top,
+            z_index: self.z_index,
+            paint_order: self.paint_order,
+        }
+    }
+
+    /// Returns this rect with its edges rounded to whole pixels
+    ///
+    /// The edges are rounded rather than the size, so rects that share an edge still do.
+    pub fn snapped(&self) -> Rect {
+        let (left, top) = (self.posx.round(), self.posy.round());
+        Rect {
+            posx: left,
+            posy: top,
+            width: (self.posx + self.width).round() - left,
+            height: (self.posy + self.height).round() - top,
+            z_index: self.z_index,
+            paint_order: self.paint_order,
+        }
+    }
 }
 
+/// The smallest size a widget can be laid out at, no matter how little space it's given
+///
+/// Widgets can insert this on their entity to report it, such as a button that should never be
+/// narrower than its label. It's used as a floor for the widget's `min_width` and `min_height`
+/// when they're unset or in pixels. Text reports its own, which is the size of its widest word.
+#[derive(Component, Debug, Default, Clone, Copy, PartialEq)]
+pub struct IntrinsicSize(pub (f32, f32));
+
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
 pub struct Space {
     pub left: f32,
@@ -41,7 +136,7 @@ pub struct Size {
 }
 
 #[derive(Default, Debug)]
-pub(crate) struct LayoutCache {
+pub struct LayoutCache {
     // Computed Outputs
     pub rect: HashMap<WrappedIndex, Rect>,
 
@@ -72,6 +167,9 @@ pub(crate) struct LayoutCache {
     /// If a node does not have any flags set, then they should be removed from the map.
     pub(crate) geometry_changed: HashMap<WrappedIndex, GeometryChanged>,
 
+    /// The nodes that were laid out by the last layout pass
+    pub(crate) recomputed: HashSet<WrappedIndex>,
+
     visible: HashMap<WrappedIndex, bool>,
 }
 
@@ -106,6 +204,45 @@ impl LayoutCache {
         self.visible.insert(node_index, true);
     }
 
+    /// Removes every cached value of the given node
+    pub fn remove(&mut self, node_index: WrappedIndex) {
+        self.rect.remove(&node_index);
+
+        self.space.remove(&node_index);
+        self.size.remove(&node_index);
+
+        self.child_width_max.remove(&node_index);
+        self.child_height_max.remove(&node_index);
+        self.child_width_sum.remove(&node_index);
+        self.child_height_sum.remove(&node_index);
+
+        self.grid_row_max.remove(&node_index);
+        self.grid_col_max.remove(&node_index);
+
+        self.horizontal_free_space.remove(&node_index);
+        self.horizontal_stretch_sum.remove(&node_index);
+
+        self.vertical_free_space.remove(&node_index);
+        self.vertical_stretch_sum.remove(&node_index);
+
+        self.stack_first_child.remove(&node_index);
+        self.stack_last_child.remove(&node_index);
+
+        self.geometry_changed.remove(&node_index);
+        self.recomputed.remove(&node_index);
+
+        self.visible.remove(&node_index);
+    }
+
+    /// Checks if any value is cached for the given node
+    pub fn contains(&self, node_index: WrappedIndex) -> bool {
+        self.rect.contains_key(&node_index)
+            || self.space.contains_key(&node_index)
+            || self.size.contains_key(&node_index)
+            || self.visible.contains_key(&node_index)
+            || self.geometry_changed.contains_key(&node_index)
+    }
+
     /// Attempts to initialize the node if it hasn't already been initialized.
     fn try_init(&mut self, node: WrappedIndex) {
         self.rect.entry(node).or_default();
@@ -117,6 +254,191 @@ impl LayoutCache {
     }
 }
 
+/// Computes the layout of every node in a widget tree
+///
+/// The default engine is [`MorphormLayoutEngine`]. A different engine can be used for a context
+/// with [`KayakRootContext::with_layout_engine`](crate::prelude::KayakRootContext::with_layout_engine),
+/// such as a deterministic one for tests.
+///
+/// Engines read the resolved styles of each node from its [`Node`](crate::Node), and store the
+/// layouts in [`LayoutCache::rect`] by the [`WrappedIndex`](crate::WrappedIndex) of the node.
+pub trait LayoutEngine: Send + Sync {
+    /// Lays out the given tree, storing the resulting layout of each node in the cache
+    ///
+    /// The subtrees in [`Tree::hidden`] should be left out, as if they weren't in the tree.
+    fn layout(&self, cache: &mut LayoutCache, node_tree: &Tree, nodes: &Query<&'static Node>);
+
+    /// Lays out the descendants of `root`, whose own layout must already be in the cache
+    ///
+    /// The default implementation runs [`LayoutEngine::layout`] over a copy of the subtree.
+    fn layout_subtree(
+        &self,
+        cache: &mut LayoutCache,
+        node_tree: &Tree,
+        root: WrappedIndex,
+        nodes: &Query<&'static Node>,
+    ) {
+        self.layout(cache, &node_tree.subtree(root), nodes);
+    }
+}
+
+/// Finds the nodes to lay out again so that every layout-dirty node is up to date
+///
+/// Any change to a node can move its siblings and resize its parent, so each dirty node is laid
+/// out starting from the nearest ancestor whose size doesn't depend on its children: either the
+/// root, or a node with a fixed pixel width and height. Nodes that are within the subtree of
+/// another returned node are left out.
+pub(crate) fn relayout_roots(
+    node_tree: &Tree,
+    dirty_nodes: &HashSet<WrappedIndex>,
+    nodes: &Query<&'static Node>,
+) -> Vec<WrappedIndex> {
+    let has_fixed_size = |index: WrappedIndex| {
+        nodes.get(index.0).map_or(false, |node| {
+            matches!(
+                (&node.resolved_styles.width, &node.resolved_styles.height),
+                (
+                    StyleProp::Value(Units::Pixels(_)),
+                    StyleProp::Value(Units::Pixels(_))
+                )
+            )
+        })
+    };
+
+    let mut roots = HashSet::new();
+    for dirty in dirty_nodes.iter() {
+        // Hidden subtrees aren't laid out
+        if !node_tree.contains(*dirty) || is_within(node_tree, &node_tree.hidden, *dirty) {
+            continue;
+        }
+
+        let mut current = node_tree.get_parent(*dirty).unwrap_or(*dirty);
+        while let Some(parent) = node_tree.get_parent(current) {
+            if has_fixed_size(current) {
+                break;
+            }
+            current = parent;
+        }
+        roots.insert(current);
+    }
+
+    roots
+        .iter()
+        .filter(|root| {
+            let mut ancestor = node_tree.get_parent(**root);
+            while let Some(current) = ancestor {
+                if roots.contains(&current) {
+                    return false;
+                }
+                ancestor = node_tree.get_parent(current);
+            }
+            true
+        })
+        .copied()
+        .collect()
+}
+
+/// Moves the children of rows with [`AlignItems::Baseline`] down so that their text baselines
+/// line up
+///
+/// Only the nodes within the given roots are aligned. Rows are aligned deepest first, so a
+/// child's baseline is final by the time its own parent is aligned.
+pub(crate) fn align_baselines(
+    cache: &mut LayoutCache,
+    node_tree: &Tree,
+    roots: &[WrappedIndex],
+    nodes: &Query<&'static Node>,
+) {
+    let is_baseline_row = |index: WrappedIndex| {
+        nodes.get(index.0).map_or(false, |node| {
+            matches!(
+                node.resolved_styles.align_items,
+                StyleProp::Value(AlignItems::Baseline)
+            ) && matches!(
+                node.resolved_styles.layout_type,
+                StyleProp::Value(LayoutType::Row)
+            )
+        })
+    };
+
+    let rows = roots
+        .iter()
+        .flat_map(|root| node_tree.visible_nodes_at(*root))
+        .filter(|index| is_baseline_row(*index))
+        .collect::<Vec<_>>();
+    for row in rows.into_iter().rev() {
+        let children = node_tree
+            .visible_children(row)
+            .filter(|child| {
+                nodes.get(child.0).map_or(false, |node| {
+                    !matches!(
+                        node.resolved_styles.position_type,
+                        StyleProp::Value(KPositionType::SelfDirected)
+                    )
+                })
+            })
+            .filter_map(|child| Some((child, baseline(cache, node_tree, nodes, child)?)))
+            .collect::<Vec<_>>();
+        let lowest = children
+            .iter()
+            .map(|(_, baseline)| *baseline)
+            .fold(f32::MIN, f32::max);
+        for (child, baseline) in children {
+            let offset = lowest - baseline;
+            if offset == 0.0 {
+                continue;
+            }
+            for index in node_tree.down_iter_at(child, true) {
+                if let Some(rect) = cache.rect.get_mut(&index) {
+                    rect.posy += offset;
+                    // Moved after morphorm compared the layouts, so it didn't see this change
+                    cache
+                        .geometry_changed
+                        .entry(index)
+                        .or_default()
+                        .insert(GeometryChanged::POSY_CHANGED);
+                }
+            }
+        }
+    }
+}
+
+/// Finds the baseline of the first line of text within the given node, in window coordinates
+fn baseline(
+    cache: &LayoutCache,
+    node_tree: &Tree,
+    nodes: &Query<&'static Node>,
+    index: WrappedIndex,
+) -> Option<f32> {
+    let own_baseline = nodes
+        .get(index.0)
+        .ok()
+        .and_then(|node| node.primitive.baseline());
+    if let Some(own_baseline) = own_baseline {
+        return Some(cache.rect.get(&index)?.posy + own_baseline);
+    }
+    node_tree
+        .children
+        .get(&index)?
+        .iter()
+        .find_map(|child| baseline(cache, node_tree, nodes, *child))
+}
+
+/// The default [`LayoutEngine`], backed by [morphorm](https://github.com/geom3trik/morphorm)
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MorphormLayoutEngine;
+
+impl LayoutEngine for MorphormLayoutEngine {
+    fn layout(&self, cache: &mut LayoutCache, node_tree: &Tree, nodes: &Query<&'static Node>) {
+        let mut data_cache = DataCache {
+            cache,
+            query: nodes,
+        };
+        // Hidden subtrees are left out, so they take up no space
+        morphorm::layout(&mut data_cache, &VisibleTree(node_tree), nodes);
+    }
+}
+
 pub(crate) struct DataCache<'borrow, 'world, 'state> {
     pub query: &'borrow Query<'world, 'state, &'static crate::node::Node>,
     pub cache: &'borrow mut LayoutCache,
@@ -434,6 +756,7 @@ impl From<Layout> for Rect {
             width: layout.width,
             height: layout.height,
             z_index: layout.z,
+            paint_order: 0,
         }
     }
 }
@@ -476,3 +799,301 @@ impl LayoutEvent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use bevy::{
+        ecs::system::SystemState,
+        prelude::{Query, World},
+        utils::HashSet,
+    };
+
+    use super::{relayout_roots, LayoutCache, LayoutEngine, MorphormLayoutEngine, Rect};
+    use crate::{
+        node::{Node, NodeBuilder, WrappedIndex},
+        render_primitive::RenderPrimitive,
+        styles::{KStyle, LayoutType, RenderCommand, StyleProp, Units},
+        test_utils::TreeBuilder,
+        tree::Tree,
+    };
+
+    const PANELS: usize = 8;
+
+    fn sized(width: Units, height: Units) -> KStyle {
+        KStyle {
+            width: StyleProp::Value(width),
+            height: StyleProp::Value(height),
+            ..Default::default()
+        }
+    }
+
+    /// Builds a root with fixed size panels, each holding a chain of content sized nodes
+    /// `depth` deep that ends in a fixed size leaf. Returns the tree and the leaves.
+    fn spawn_deep_tree(world: &mut World, depth: usize) -> (Tree, Vec<WrappedIndex>) {
+        let mut tree = Tree::default();
+        let mut spawn = |tree: &mut Tree, parent: Option<WrappedIndex>, styles: KStyle| {
+            let index = WrappedIndex(world.spawn_empty().id());
+            world.entity_mut(index.0).insert(
+                NodeBuilder::empty()
+                    .with_id(index)
+                    .with_styles(styles, None)
+                    .build(),
+            );
+            tree.add(index, parent);
+            index
+        };
+
+        let root = spawn(
+            &mut tree,
+            None,
+            sized(Units::Pixels(1000.0), Units::Pixels(1000.0)),
+        );
+        let mut leaves = Vec::new();
+        for _ in 0..PANELS {
+            let mut parent = spawn(
+                &mut tree,
+                Some(root),
+                sized(Units::Pixels(100.0), Units::Pixels(100.0)),
+            );
+            for _ in 0..depth {
+                parent = spawn(&mut tree, Some(parent), sized(Units::Auto, Units::Auto));
+            }
+            leaves.push(spawn(
+                &mut tree,
+                Some(parent),
+                sized(Units::Pixels(10.0), Units::Pixels(10.0)),
+            ));
+        }
+        (tree, leaves)
+    }
+
+    fn new_cache(tree: &Tree) -> LayoutCache {
+        let mut cache = LayoutCache::default();
+        for index in tree.flatten() {
+            cache.add(index);
+        }
+        cache.rect.insert(
+            tree.root_node.unwrap(),
+            Rect {
+                width: 1000.0,
+                height: 1000.0,
+                ..Default::default()
+            },
+        );
+        cache
+    }
+
+    fn resize(world: &mut World, index: WrappedIndex, size: f32) {
+        let mut node = world.get_mut::<Node>(index.0).unwrap();
+        node.resolved_styles.width = StyleProp::Value(Units::Pixels(size));
+    }
+
+    #[test]
+    fn relayout_should_start_at_nearest_fixed_size_ancestor() {
+        let mut world = World::new();
+        let (tree, leaves) = spawn_deep_tree(&mut world, 3);
+        let mut state = SystemState::<Query<&'static Node>>::new(&mut world);
+        let nodes = state.get(&world);
+
+        let dirty = leaves.iter().take(2).copied().collect::<HashSet<_>>();
+        let mut roots = relayout_roots(&tree, &dirty, &nodes);
+        roots.sort_by_key(|root| root.0);
+        let mut panels = tree.children[&tree.root_node.unwrap()]
+            .iter()
+            .take(2)
+            .copied()
+            .collect::<Vec<_>>();
+        panels.sort_by_key(|panel| panel.0);
+        assert_eq!(panels, roots);
+
+        // A change to a panel itself moves its siblings
+        let dirty = panels.iter().copied().chain(leaves).collect::<HashSet<_>>();
+        assert_eq!(tree.root_node, relayout_roots(&tree, &dirty, &nodes).pop());
+    }
+
+    #[test]
+    fn scoped_relayout_should_match_full_layout() {
+        let mut world = World::new();
+        let (tree, leaves) = spawn_deep_tree(&mut world, 4);
+        let engine = MorphormLayoutEngine;
+        let mut full = new_cache(&tree);
+        let mut scoped = new_cache(&tree);
+        {
+            let mut state = SystemState::<Query<&'static Node>>::new(&mut world);
+            let nodes = state.get(&world);
+            engine.layout(&mut full, &tree, &nodes);
+            engine.layout(&mut scoped, &tree, &nodes);
+        }
+
+        resize(&mut world, leaves[3], 40.0);
+        let mut state = SystemState::<Query<&'static Node>>::new(&mut world);
+        let nodes = state.get(&world);
+        engine.layout(&mut full, &tree, &nodes);
+        let dirty = [leaves[3]].into_iter().collect::<HashSet<_>>();
+        for root in relayout_roots(&tree, &dirty, &nodes) {
+            engine.layout_subtree(&mut scoped, &tree, root, &nodes);
+        }
+
+        assert_eq!(full.rect, scoped.rect);
+    }
+
+    /// Lays out two 40px boxes in a row, where the second is pulled 10px over the first.
+    /// Returns the x position of each box's quad, in the order they are drawn.
+    fn overlapping_boxes(first_z_index: Option<i32>) -> Vec<f32> {
+        let quad = |left: Units, z_index: Option<i32>| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            left: StyleProp::Value(left),
+            z_index: z_index.map_or(StyleProp::Default, StyleProp::Value),
+            ..sized(Units::Pixels(40.0), Units::Pixels(40.0))
+        };
+        let mut tree = TreeBuilder::new()
+            .node(
+                "root",
+                KStyle {
+                    layout_type: StyleProp::Value(LayoutType::Row),
+                    ..sized(Units::Pixels(200.0), Units::Pixels(100.0))
+                },
+            )
+            .leaf("first", quad(Units::Pixels(0.0), first_z_index))
+            .leaf("second", quad(Units::Pixels(-10.0), None))
+            .build();
+        tree.layout();
+
+        assert_eq!(0.0, tree.layout_of("first").posx);
+        assert_eq!(30.0, tree.layout_of("second").posx);
+        tree.render_primitives()
+            .into_iter()
+            .filter_map(|primitive| match primitive {
+                RenderPrimitive::Quad { layout, .. } => Some(layout.posx),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn negative_offset_should_overlap_siblings() {
+        // Later siblings are drawn over earlier ones
+        assert_eq!(vec![0.0, 30.0], overlapping_boxes(None));
+        // Unless the earlier sibling has a higher z-index
+        assert_eq!(vec![30.0, 0.0], overlapping_boxes(Some(1)));
+    }
+
+    /// Compares full and scoped relayout of a deep tree.
+    ///
+    /// Run with `cargo test --release scoped_relayout_benchmark -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn scoped_relayout_benchmark() {
+        const ITERATIONS: u32 = 100;
+
+        let mut world = World::new();
+        let (tree, leaves) = spawn_deep_tree(&mut world, 250);
+        let engine = MorphormLayoutEngine;
+        let mut cache = new_cache(&tree);
+        let mut state = SystemState::<Query<&'static Node>>::new(&mut world);
+        let nodes = state.get(&world);
+        engine.layout(&mut cache, &tree, &nodes);
+        let dirty = [leaves[0]].into_iter().collect::<HashSet<_>>();
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            engine.layout(&mut cache, &tree, &nodes);
+        }
+        let full = start.elapsed() / ITERATIONS;
+
+        let start = Instant::now();
+        for _ in 0..ITERATIONS {
+            for root in relayout_roots(&tree, &dirty, &nodes) {
+                engine.layout_subtree(&mut cache, &tree, root, &nodes);
+            }
+        }
+        let scoped = start.elapsed() / ITERATIONS;
+
+        println!(
+            "{} nodes: full relayout {:?}, scoped relayout {:?}",
+            tree.len(),
+            full,
+            scoped
+        );
+    }
+
+    #[test]
+    fn pixel_snapping_should_round_layouts() {
+        let label = KStyle {
+            width: StyleProp::Value(Units::Pixels(100.4)),
+            height: StyleProp::Value(Units::Pixels(20.6)),
+            top: StyleProp::Value(Units::Pixels(0.0)),
+            bottom: StyleProp::Value(Units::Pixels(0.0)),
+            ..KStyle::initial()
+        };
+        let build = || {
+            TreeBuilder::new()
+                .node(
+                    "root",
+                    KStyle {
+                        width: StyleProp::Value(Units::Pixels(800.0)),
+                        height: StyleProp::Value(Units::Pixels(600.0)),
+                        ..KStyle::initial()
+                    },
+                )
+                .leaf("first", label.clone())
+                .leaf("second", label.clone())
+                .build()
+        };
+
+        let mut tree = build();
+        tree.layout();
+        assert_eq!(100.4, tree.layout_of("first").width);
+
+        let mut tree = build();
+        tree.context.set_pixel_snapping(true);
+        tree.layout();
+        let (first, second) = (tree.layout_of("first"), tree.layout_of("second"));
+        assert_eq!(100.0, first.width);
+        assert_eq!(21.0, first.height);
+        // The second label starts at 20.6, so its bottom edge rounds from 41.2 down to 41
+        assert_eq!(first.posy + first.height, second.posy);
+        assert_eq!(20.0, second.height);
+    }
+
+    /// Places every node at the same layout, wherever the tree would put it
+    struct FixedLayoutEngine;
+
+    impl LayoutEngine for FixedLayoutEngine {
+        fn layout(
+            &self,
+            cache: &mut LayoutCache,
+            node_tree: &Tree,
+            _nodes: &Query<&'static crate::Node>,
+        ) {
+            for index in node_tree.flatten() {
+                cache.rect.insert(index, fixed_layout());
+            }
+        }
+    }
+
+    fn fixed_layout() -> Rect {
+        Rect {
+            posx: 1.0,
+            posy: 2.0,
+            width: 3.0,
+            height: 4.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn custom_layout_engines_should_be_used() {
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(Units::Pixels(800.0), Units::Pixels(600.0)))
+            .leaf("label", sized(Units::Pixels(100.0), Units::Pixels(20.0)))
+            .build();
+        tree.context = std::mem::take(&mut tree.context).with_layout_engine(FixedLayoutEngine);
+
+        tree.calculate_nodes();
+        tree.layout();
+        assert_eq!(fixed_layout(), tree.layout_of("label"));
+    }
+}
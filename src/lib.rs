@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+mod accessibility;
 mod calculate_nodes;
 mod camera;
 mod children;
@@ -19,9 +20,14 @@ mod node;
 mod on_change;
 mod on_event;
 mod on_layout;
+mod on_unmount;
 pub(crate) mod render;
 mod render_primitive;
+mod render_stats;
+mod snapshot;
 mod styles;
+#[cfg(test)]
+mod test_utils;
 mod tree;
 mod widget;
 mod widget_context;
@@ -37,7 +43,11 @@ pub use camera::*;
 /// The default font name used by Kayak
 pub const DEFAULT_FONT: &str = "Kayak-Default";
 
+/// The default font size that [`Units::Rem`](crate::prelude::Units::Rem) is relative to
+pub const DEFAULT_ROOT_FONT_SIZE: f32 = 14.0;
+
 pub mod prelude {
+    pub use crate::accessibility::{AccessNode, AccessNodeTree};
     pub use crate::camera::UICameraBundle;
     pub use crate::children::KChildren;
     pub use crate::clone_component::PreviousWidget;
@@ -45,7 +55,7 @@ pub mod prelude {
     pub use crate::cursor::*;
     pub use crate::event::*;
     pub use crate::event_dispatcher::{EventDispatcher, EventDispatcherContext};
-    pub use crate::focus_tree::Focusable;
+    pub use crate::focus_tree::{FocusChange, Focusable};
     pub use crate::input_event::*;
     pub use crate::keyboard_event::*;
     pub use crate::layout::*;
@@ -53,7 +63,11 @@ pub mod prelude {
     pub use crate::on_change::OnChange;
     pub use crate::on_event::OnEvent;
     pub use crate::on_layout::OnLayout;
-    pub use crate::render::font::FontMapping;
+    pub use crate::on_unmount::UnmountCallback;
+    pub use crate::render::font::{FontMapping, FontProvider, HeadlessAssets, LoadedFonts};
+    pub use crate::render_primitive::RenderPrimitive;
+    pub use crate::render_stats::RenderStats;
+    pub use crate::snapshot::{NodeSnapshot, TreeSnapshot};
     pub use crate::styles::*;
     pub use crate::tree::*;
     pub use crate::widget::*;
@@ -65,6 +79,7 @@ pub mod prelude {
 }
 
 pub use focus_tree::Focusable;
+pub use node::{Node, WrappedIndex};
 
 pub trait KayakUIPlugin {
     fn build(&self, context: &mut KayakRootContext);
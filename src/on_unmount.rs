@@ -0,0 +1,89 @@
+use std::sync::{Arc, RwLock};
+
+use bevy::{
+    prelude::{Entity, World},
+    utils::HashMap,
+};
+
+/// A callback that is ran when a widget is removed from the tree
+pub type UnmountCallback = Box<dyn Fn(&mut World, Entity) + Send + Sync>;
+
+/// Stores the unmount callbacks registered for each widget entity.
+#[derive(Default, Clone)]
+pub struct UnmountCallbacks {
+    callbacks: Arc<RwLock<HashMap<Entity, Vec<UnmountCallback>>>>,
+}
+
+impl UnmountCallbacks {
+    /// Registers a callback to run when the given widget is removed from the tree.
+    pub fn add(
+        &self,
+        widget_entity: Entity,
+        callback: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) {
+        match self.callbacks.write() {
+            Ok(mut callbacks) => callbacks
+                .entry(widget_entity)
+                .or_default()
+                .push(Box::new(callback)),
+            Err(_) => log::error!(
+                "Couldn't register an unmount callback for {:?}, since the callbacks are poisoned",
+                widget_entity
+            ),
+        }
+    }
+
+    /// Runs and removes all callbacks registered for the given widget.
+    ///
+    /// The callbacks are taken out of the registry before being ran so they are free
+    /// to register new callbacks for other widgets.
+    pub fn run(&self, world: &mut World, widget_entity: Entity) {
+        let callbacks = match self.callbacks.write() {
+            Ok(mut callbacks) => callbacks.remove(&widget_entity),
+            Err(_) => {
+                log::error!(
+                    "Couldn't run the unmount callbacks of {:?}, since the callbacks are poisoned",
+                    widget_entity
+                );
+                None
+            }
+        };
+
+        for callback in callbacks.unwrap_or_default() {
+            callback(world, widget_entity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use bevy::prelude::World;
+
+    use super::UnmountCallbacks;
+
+    #[test]
+    fn callbacks_should_run_exactly_once() {
+        let mut world = World::new();
+        let widget = world.spawn_empty().id();
+        let other = world.spawn_empty().id();
+        let callbacks = UnmountCallbacks::default();
+
+        let runs = Arc::new(AtomicUsize::new(0));
+        let counter = runs.clone();
+        callbacks.add(widget, move |_, entity| {
+            assert_eq!(widget, entity);
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        callbacks.run(&mut world, other);
+        assert_eq!(0, runs.load(Ordering::SeqCst));
+        callbacks.run(&mut world, widget);
+        callbacks.run(&mut world, widget);
+        assert_eq!(1, runs.load(Ordering::SeqCst));
+    }
+}
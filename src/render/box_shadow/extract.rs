@@ -0,0 +1,58 @@
+use crate::{
+    render::unified::pipeline::{ExtractQuadBundle, ExtractedQuad, UIQuadType},
+    render_primitive::RenderPrimitive,
+};
+use bevy::{
+    math::Vec2,
+    prelude::{Entity, Rect},
+};
+
+pub fn extract_box_shadow(
+    camera_entity: Entity,
+    render_primitive: &RenderPrimitive,
+    dpi: f32,
+) -> Vec<ExtractQuadBundle> {
+    let (color, offset, blur, spread, border_radius, layout) = match render_primitive {
+        RenderPrimitive::BoxShadow {
+            color,
+            offset,
+            blur,
+            spread,
+            border_radius,
+            layout,
+        } => (*color, *offset, *blur, *spread, *border_radius, *layout),
+        _ => panic!(""),
+    };
+
+    let blur = blur.max(0.0) * dpi;
+    // The quad is grown by the blur radius so the falloff has room to fade out.
+    let grow = spread * dpi + blur;
+    let min = Vec2::new(layout.posx + offset.0 * dpi, layout.posy + offset.1 * dpi);
+    let max = min + Vec2::new(layout.width * dpi, layout.height * dpi);
+
+    vec![ExtractQuadBundle {
+        extracted_quad: ExtractedQuad {
+            camera_entity,
+            rect: Rect {
+                min: min - Vec2::splat(grow),
+                max: max + Vec2::splat(grow),
+            },
+            color,
+            vertex_index: 0,
+            char_id: 0,
+            z_index: layout.z_index,
+            paint_order: layout.paint_order,
+            rotation: 0.0,
+            font_handle: None,
+            quad_type: UIQuadType::BoxShadow,
+            type_index: 0,
+            border_radius: border_radius * dpi,
+            image: None,
+            // The shader reads the blur radius from the uvs.
+            uv_min: Some(Vec2::splat(blur)),
+            uv_max: Some(Vec2::splat(blur)),
+            corner_colors: None,
+            clip: None,
+        },
+    }]
+}
@@ -5,16 +5,19 @@ use crate::{
     styles::Corner,
 };
 use bevy::{
-    prelude::{Assets, Camera, Color, Commands, Entity, Image, Plugin, Query, Rect, Res, Vec2},
+    prelude::{
+        Assets, Camera, Color, Commands, Entity, Handle, Image, Plugin, Query, Rect, Res, Vec2,
+    },
     render::{Extract, RenderApp, RenderStage},
     window::Windows,
 };
 use kayak_font::KayakFont;
 
 use super::{
+    box_shadow,
     font::{self, FontMapping},
-    image, nine_patch, texture_atlas,
-    unified::pipeline::{ExtractQuadBundle, ExtractedQuad, UIQuadType},
+    gradient, image, nine_patch, path, texture_atlas,
+    unified::pipeline::{ExtractQuadBundle, ExtractedQuad, QuadClip, UIQuadType},
 };
 
 // mod nine_patch;
@@ -56,42 +59,43 @@ pub fn extract(
     }
 
     let mut extracted_quads = Vec::new();
+    let mut active_clip: Option<(Entity, QuadClip)> = None;
     for (camera_entity, dpi, render_primitive) in render_primitives {
-        match render_primitive {
+        let mut quads = match render_primitive {
             RenderPrimitive::Text { .. } => {
-                let text_quads = font::extract_texts(
-                    camera_entity,
-                    &render_primitive,
-                    &fonts,
-                    &font_mapping,
-                    dpi,
-                );
-                extracted_quads.extend(text_quads);
+                font::extract_texts(camera_entity, &render_primitive, &fonts, &font_mapping, dpi)
             }
             RenderPrimitive::Image { .. } => {
-                let image_quads = image::extract_images(camera_entity, &render_primitive, dpi);
-                extracted_quads.extend(image_quads);
+                image::extract_images(camera_entity, &render_primitive, &images, dpi)
             }
             RenderPrimitive::Quad { .. } => {
-                let quad_quads = super::quad::extract_quads(camera_entity, &render_primitive, 1.0);
-                extracted_quads.extend(quad_quads);
+                super::quad::extract_quads(camera_entity, &render_primitive, 1.0)
+            }
+            RenderPrimitive::BoxShadow { .. } => {
+                box_shadow::extract_box_shadow(camera_entity, &render_primitive, 1.0)
+            }
+            RenderPrimitive::Gradient { .. } => {
+                gradient::extract_gradient(camera_entity, &render_primitive, 1.0)
             }
             RenderPrimitive::NinePatch { .. } => {
-                let nine_patch_quads =
-                    nine_patch::extract_nine_patch(camera_entity, &render_primitive, &images, dpi);
-                extracted_quads.extend(nine_patch_quads);
+                nine_patch::extract_nine_patch(camera_entity, &render_primitive, &images, dpi)
             }
             RenderPrimitive::TextureAtlas { .. } => {
-                let texture_atlas_quads = texture_atlas::extract_texture_atlas(
-                    camera_entity,
-                    &render_primitive,
-                    &images,
-                    dpi,
-                );
-                extracted_quads.extend(texture_atlas_quads);
+                texture_atlas::extract_texture_atlas(camera_entity, &render_primitive, &images, dpi)
+            }
+            RenderPrimitive::Path { .. } => {
+                path::extract_path(camera_entity, &render_primitive, dpi)
             }
-            RenderPrimitive::Clip { layout } => {
-                extracted_quads.push(ExtractQuadBundle {
+            RenderPrimitive::Clip { layout, .. } | RenderPrimitive::ClipMask { layout, .. } => {
+                // The scissor rect clips to the region, while the quads drawn inside of it carry
+                // the region along so the shader can apply its mask.
+                active_clip = clip_region(&render_primitive, |mask| images.contains(mask))
+                    .map(|clip| (camera_entity, clip));
+                let border_radius = match &render_primitive {
+                    RenderPrimitive::Clip { radius, .. } => *radius,
+                    _ => Corner::default(),
+                };
+                vec![ExtractQuadBundle {
                     extracted_quad: ExtractedQuad {
                         camera_entity,
                         rect: Rect {
@@ -103,20 +107,94 @@ pub fn extract(
                         vertex_index: 0,
                         char_id: 0,
                         z_index: layout.z_index,
+                        paint_order: layout.paint_order,
+                        rotation: 0.0,
                         font_handle: None,
                         quad_type: UIQuadType::Clip,
                         type_index: 0,
-                        border_radius: Corner::default(),
+                        border_radius,
                         image: None,
                         uv_min: None,
                         uv_max: None,
+                        corner_colors: None,
+                        clip: None,
                     },
-                });
+                }]
+            }
+            _ => Vec::new(),
+        };
+
+        if let Some((clip_camera, clip)) = &active_clip {
+            if *clip_camera == camera_entity {
+                for quad in quads.iter_mut() {
+                    if quad.extracted_quad.quad_type != UIQuadType::Clip {
+                        quad.extracted_quad.clip = Some(clip.clone());
+                    }
+                }
             }
-            _ => {}
         }
+        extracted_quads.extend(quads);
     }
 
     // dbg!(&extracted_quads);
     commands.spawn_batch(extracted_quads);
 }
+
+/// The region a clip primitive restricts the quads drawn after it to. Masks that have not loaded
+/// yet are left out, so their content is only clipped by the region.
+fn clip_region(
+    render_primitive: &RenderPrimitive,
+    is_loaded: impl Fn(&Handle<Image>) -> bool,
+) -> Option<QuadClip> {
+    let (layout, radius, mask) = match render_primitive {
+        RenderPrimitive::Clip { layout, radius, .. } => (layout, *radius, None),
+        RenderPrimitive::ClipMask { layout, handle } => (
+            layout,
+            Corner::default(),
+            Some(handle.clone_weak()).filter(|handle| is_loaded(handle)),
+        ),
+        _ => return None,
+    };
+    Some(QuadClip {
+        rect: Rect {
+            min: Vec2::new(layout.posx, layout.posy),
+            max: Vec2::new(layout.posx + layout.width, layout.posy + layout.height),
+        },
+        radius,
+        mask,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Handle;
+
+    use super::clip_region;
+    use crate::{layout::Rect, render_primitive::RenderPrimitive};
+
+    #[test]
+    fn clip_masks_should_wait_for_their_image() {
+        let layout = Rect {
+            posx: 10.0,
+            posy: 20.0,
+            width: 30.0,
+            height: 40.0,
+            z_index: 0.0,
+            paint_order: 0,
+        };
+        let mask = RenderPrimitive::ClipMask {
+            layout,
+            handle: Handle::default(),
+        };
+
+        let clip = clip_region(&mask, |_| true).unwrap();
+        assert!(clip.mask.is_some());
+        assert_eq!(10.0, clip.rect.min.x);
+        assert_eq!(60.0, clip.rect.max.y);
+
+        let clip = clip_region(&mask, |_| false).unwrap();
+        assert!(clip.mask.is_none());
+
+        assert!(clip_region(&RenderPrimitive::Empty, |_| true).is_none());
+    }
+}
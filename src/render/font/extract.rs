@@ -2,12 +2,12 @@ use bevy::{
     math::Vec2,
     prelude::{Assets, Entity, Rect, Res},
 };
-use kayak_font::KayakFont;
+use kayak_font::{GlyphRect, KayakFont, TextLayout, TextProperties};
 
 use crate::{
     render::unified::pipeline::{ExtractQuadBundle, ExtractedQuad, UIQuadType},
     render_primitive::RenderPrimitive,
-    styles::Corner,
+    styles::{Corner, TextDecoration, TextDirection},
 };
 
 use super::font_mapping::FontMapping;
@@ -20,8 +20,19 @@ pub fn extract_texts(
     _dpi: f32,
 ) -> Vec<ExtractQuadBundle> {
     let mut extracted_texts = Vec::new();
-    let (background_color, text_layout, layout, font, properties, subpixel) = match render_primitive
-    {
+    let (
+        background_color,
+        text_layout,
+        mut layout,
+        font,
+        properties,
+        subpixel,
+        direction,
+        decoration,
+        outline,
+        outline_in_bounds,
+        scale,
+    ) = match render_primitive {
         RenderPrimitive::Text {
             color,
             text_layout,
@@ -29,54 +40,243 @@ pub fn extract_texts(
             font,
             properties,
             subpixel,
+            direction,
+            decoration,
+            outline,
+            outline_in_bounds,
+            scale,
             ..
-        } => (color, text_layout, layout, font, *properties, subpixel),
+        } => (
+            color,
+            text_layout,
+            *layout,
+            font,
+            *properties,
+            subpixel,
+            *direction,
+            *decoration,
+            *outline,
+            *outline_in_bounds,
+            *scale,
+        ),
         _ => panic!(""),
     };
 
-    let font_handle = font_mapping.get_handle(font.clone()).unwrap();
-    let font = match fonts.get(&font_handle) {
-        Some(font) => font,
+    let font_handle = match font_mapping.get_loaded_handle(font, fonts) {
+        Some(font_handle) => font_handle,
         None => {
             return Vec::new();
         }
     };
+    let font = fonts.get(&font_handle).unwrap();
 
     let forced = font_mapping.get_subpixel_forced(&font_handle);
 
-    let base_position = Vec2::new(layout.posx, layout.posy + properties.font_size);
+    // Outlines measured as part of the text are kept within its layout
+    if let (true, Some((_, width))) = (outline_in_bounds, outline) {
+        layout.posx += width * scale.x;
+        layout.posy += width * scale.y;
+    }
+
+    // Glyphs are laid out unscaled, so they are scaled to match the transformed layout here
+    let base_position = Vec2::new(layout.posx, layout.posy + properties.font_size * scale.y);
+
+    let mut glyphs = text_layout.glyphs().to_vec();
+    if direction == TextDirection::Rtl {
+        mirror_lines(text_layout, &mut glyphs);
+    }
 
-    for glyph_rect in text_layout.glyphs() {
-        let mut position = Vec2::from(glyph_rect.position);
-        position += base_position;
+    // The outline is drawn first, as copies of each glyph shifted around the glyph itself
+    let passes = outline
+        .iter()
+        .flat_map(|(color, width)| {
+            outline_offsets(*width)
+                .into_iter()
+                .map(move |offset| (*color, offset))
+        })
+        .chain(Some((*background_color, Vec2::ZERO)));
+    for (color, offset) in passes {
+        for glyph_rect in glyphs.iter() {
+            // Glyphs that the font doesn't have can't be drawn
+            let char_id = match font.get_char_id(glyph_rect.content) {
+                Some(char_id) => char_id,
+                None => continue,
+            };
+            let mut position = Vec2::from(glyph_rect.position) * scale;
+            position += base_position + offset * scale;
 
-        let size = Vec2::from(glyph_rect.size);
+            let size = Vec2::from(glyph_rect.size) * scale;
 
+            extracted_texts.push(ExtractQuadBundle {
+                extracted_quad: ExtractedQuad {
+                    camera_entity,
+                    font_handle: Some(font_handle.clone()),
+                    rect: Rect {
+                        min: position,
+                        max: position + size,
+                    },
+                    color,
+                    vertex_index: 0,
+                    char_id,
+                    z_index: layout.z_index,
+                    paint_order: layout.paint_order,
+                    rotation: 0.0,
+                    quad_type: if *subpixel || forced {
+                        UIQuadType::TextSubpixel
+                    } else {
+                        UIQuadType::Text
+                    },
+                    type_index: 0,
+                    border_radius: Corner::default(),
+                    image: None,
+                    uv_max: None,
+                    uv_min: None,
+                    corner_colors: None,
+                    clip: None,
+                },
+            });
+        }
+    }
+
+    for line in decoration_lines(text_layout, &glyphs, properties, decoration) {
         extracted_texts.push(ExtractQuadBundle {
             extracted_quad: ExtractedQuad {
                 camera_entity,
-                font_handle: Some(font_handle.clone()),
+                font_handle: None,
                 rect: Rect {
-                    min: position,
-                    max: position + size,
+                    min: line.min * scale + Vec2::new(layout.posx, layout.posy),
+                    max: line.max * scale + Vec2::new(layout.posx, layout.posy),
                 },
                 color: *background_color,
                 vertex_index: 0,
-                char_id: font.get_char_id(glyph_rect.content).unwrap(),
+                char_id: 0,
                 z_index: layout.z_index,
-                quad_type: if *subpixel || forced {
-                    UIQuadType::TextSubpixel
-                } else {
-                    UIQuadType::Text
-                },
+                paint_order: layout.paint_order,
+                rotation: 0.0,
+                quad_type: UIQuadType::Quad,
                 type_index: 0,
                 border_radius: Corner::default(),
                 image: None,
                 uv_max: None,
                 uv_min: None,
+                corner_colors: None,
+                clip: None,
             },
         });
     }
 
     extracted_texts
 }
+
+/// The offsets that copies of the glyphs are drawn at to outline them with the given width
+fn outline_offsets(width: f32) -> Vec<Vec2> {
+    if width <= 0.0 {
+        return Vec::new();
+    }
+    (-1..=1)
+        .flat_map(|x| (-1..=1).map(move |y| Vec2::new(x as f32, y as f32)))
+        .filter(|direction| *direction != Vec2::ZERO)
+        .map(|direction| direction.normalize() * width)
+        .collect()
+}
+
+/// Computes the rects of the decoration lines of each line of text, relative to the text's layout
+fn decoration_lines(
+    text_layout: &TextLayout,
+    glyphs: &[GlyphRect],
+    properties: TextProperties,
+    decoration: TextDecoration,
+) -> Vec<Rect> {
+    let mut offsets = Vec::new();
+    if decoration.has_underline() {
+        offsets.push(properties.font_size * 0.15);
+    }
+    if decoration.has_strikethrough() {
+        offsets.push(properties.font_size * -0.3);
+    }
+    let thickness = (properties.font_size / 14.0).max(1.0);
+
+    let mut lines = Vec::new();
+    for (index, line) in text_layout.lines().iter().enumerate() {
+        let start = line.glyph_index().min(glyphs.len());
+        let end = (start + line.total_glyphs()).min(glyphs.len());
+        let left = match glyphs[start..end]
+            .iter()
+            .map(|glyph| glyph.position.0)
+            .reduce(f32::min)
+        {
+            Some(left) => left,
+            None => continue,
+        };
+
+        let baseline = properties.font_size + properties.line_height * index as f32;
+        for offset in offsets.iter() {
+            let min = Vec2::new(left, baseline + offset - thickness / 2.0);
+            lines.push(Rect {
+                min,
+                max: min + Vec2::new(line.width(), thickness),
+            });
+        }
+    }
+    lines
+}
+
+/// Mirrors the glyphs of each line in place, so right-to-left text reads from the right edge
+fn mirror_lines(text_layout: &TextLayout, glyphs: &mut [GlyphRect]) {
+    for line in text_layout.lines() {
+        let start = line.glyph_index();
+        let end = (start + line.total_glyphs()).min(glyphs.len());
+        let line_glyphs = &mut glyphs[start..end];
+
+        let left = line_glyphs
+            .iter()
+            .map(|glyph| glyph.position.0)
+            .fold(f32::MAX, f32::min);
+        let right = line_glyphs
+            .iter()
+            .map(|glyph| glyph.position.0 + glyph.size.0)
+            .fold(f32::MIN, f32::max);
+        for glyph in line_glyphs.iter_mut() {
+            glyph.position.0 = left + right - glyph.position.0 - glyph.size.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Handle;
+    use kayak_font::{Alignment, ImageType, KayakFont, Sdf, TextProperties};
+
+    use super::decoration_lines;
+    use crate::styles::TextDecoration;
+
+    #[test]
+    fn decoration_should_draw_a_line_per_text_line() {
+        let font = KayakFont::new(
+            Sdf::from_bytes(include_bytes!("../../../assets/roboto.kayak_font")),
+            ImageType::Atlas(Handle::default()),
+        );
+        let properties = TextProperties {
+            font_size: 14.0,
+            line_height: 20.0,
+            alignment: Alignment::Start,
+            max_size: (100000.0, 100000.0),
+            tab_size: 4,
+        };
+        let text_layout = font.measure("Hello\nworld", properties);
+        let glyphs = text_layout.glyphs();
+
+        let lines = decoration_lines(&text_layout, glyphs, properties, TextDecoration::None);
+        assert!(lines.is_empty());
+
+        let lines = decoration_lines(&text_layout, glyphs, properties, TextDecoration::Underline);
+        assert_eq!(2, lines.len());
+        assert!(lines[0].min.y > properties.font_size);
+        assert!(lines[1].min.y > lines[0].max.y);
+        assert_eq!(text_layout.lines()[0].width(), lines[0].width());
+
+        let lines = decoration_lines(&text_layout, glyphs, properties, TextDecoration::Both);
+        assert_eq!(4, lines.len());
+        assert!(lines[1].max.y < properties.font_size);
+    }
+}
@@ -1,8 +1,8 @@
 use bevy::{
-    prelude::{Handle, Resource},
+    prelude::{Assets, Handle, Resource},
     utils::{HashMap, HashSet},
 };
-use kayak_font::KayakFont;
+use kayak_font::{KayakFont, Sdf};
 
 // use crate::context::Context;
 
@@ -41,6 +41,8 @@ pub struct FontMapping {
     font_handles: HashMap<String, Handle<KayakFont>>,
     new_fonts: Vec<String>,
     subpixel: HashSet<Handle<KayakFont>>,
+    /// The metrics of each font the last time it was loaded
+    font_metrics: HashMap<String, Sdf>,
 }
 
 impl FontMapping {
@@ -68,6 +70,77 @@ impl FontMapping {
         self.font_handles.get(&id).cloned()
     }
 
+    /// Get the handle for the given font name if it's loaded, otherwise the default font's handle
+    ///
+    /// Returns `None` if neither font is loaded.
+    pub fn get_loaded_handle(
+        &self,
+        id: &str,
+        fonts: &Assets<KayakFont>,
+    ) -> Option<Handle<KayakFont>> {
+        let is_loaded = |handle: &&Handle<KayakFont>| fonts.get(handle).is_some();
+        if let Some(handle) = self.font_handles.get(id).filter(is_loaded) {
+            return Some(handle.clone());
+        }
+
+        let fallback = self.font_handles.get(crate::DEFAULT_FONT).filter(is_loaded);
+        if fallback.is_some() && id != crate::DEFAULT_FONT {
+            log::trace!(
+                "Font {} is not loaded, falling back to the default font",
+                id
+            );
+        }
+        fallback.cloned()
+    }
+
+    /// Checks if the given font is still waiting to be loaded
+    ///
+    /// Fonts that were never added are only considered loading until the default font is loaded,
+    /// since that's what they'll fall back to.
+    pub fn is_loading(&self, id: &str, fonts: &Assets<KayakFont>) -> bool {
+        match self.font_handles.get(id) {
+            Some(handle) => fonts.get(handle).is_none(),
+            None => self.get_loaded_handle(id, fonts).is_none(),
+        }
+    }
+
+    /// Records the metrics of a (re)loaded font
+    ///
+    /// Returns the name of the font if its metrics changed since it was last loaded.
+    pub(crate) fn update_metrics(
+        &mut self,
+        font: &Handle<KayakFont>,
+        loaded: &KayakFont,
+    ) -> Option<String> {
+        let key = self.font_ids.get(font)?;
+        if self.font_metrics.get(key) == Some(&loaded.sdf) {
+            return None;
+        }
+
+        self.font_metrics.insert(key.clone(), loaded.sdf.clone());
+        Some(key.clone())
+    }
+
+    /// Finds the first loaded font, out of the given font and its fallbacks, that has a glyph for
+    /// every character of the content
+    ///
+    /// Returns `None` if none of the fonts cover all of the content.
+    pub fn find_covering_font(
+        &self,
+        content: &str,
+        id: &str,
+        fallbacks: &[String],
+        fonts: &Assets<KayakFont>,
+    ) -> Option<String> {
+        let candidates = std::iter::once(id)
+            .chain(fallbacks.iter().map(String::as_str))
+            .filter_map(|name| {
+                let font = fonts.get(self.font_handles.get(name)?)?;
+                Some((name, font))
+            });
+        select_covering_font(content, candidates).map(str::to_string)
+    }
+
     /// Get the font name for the given handle
     pub fn get(&self, font: &Handle<KayakFont>) -> Option<String> {
         self.font_ids.get(font).cloned()
@@ -105,3 +178,46 @@ impl FontMapping {
     //     }
     // }
 }
+
+/// Picks the first of the given named fonts that covers all of the content
+fn select_covering_font<'a>(
+    content: &str,
+    candidates: impl IntoIterator<Item = (&'a str, &'a KayakFont)>,
+) -> Option<&'a str> {
+    candidates
+        .into_iter()
+        .find(|(_, font)| font.covers(content))
+        .map(|(name, _)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Handle;
+    use kayak_font::{Glyph, ImageType, KayakFont, Sdf};
+
+    use super::select_covering_font;
+
+    fn roboto() -> Sdf {
+        Sdf::from_bytes(include_bytes!("../../../assets/roboto.kayak_font"))
+    }
+
+    #[test]
+    fn should_fall_back_to_font_covering_cjk() {
+        let latin = KayakFont::new(roboto(), ImageType::Atlas(Handle::default()));
+        let mut cjk_sdf = roboto();
+        for unicode in ['漢', '字'] {
+            cjk_sdf.glyphs.push(Glyph {
+                unicode,
+                advance: 1.0,
+                atlas_bounds: None,
+                plane_bounds: None,
+            });
+        }
+        let cjk = KayakFont::new(cjk_sdf, ImageType::Atlas(Handle::default()));
+        let fonts = || [("latin", &latin), ("cjk", &cjk)];
+
+        assert_eq!(Some("latin"), select_covering_font("Hello", fonts()));
+        assert_eq!(Some("cjk"), select_covering_font("Hello 漢字", fonts()));
+        assert_eq!(None, select_covering_font("Hello 😀", fonts()));
+    }
+}
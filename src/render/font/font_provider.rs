@@ -0,0 +1,112 @@
+use bevy::prelude::{Assets, Handle, Resource};
+use kayak_font::{ImageType, KayakFont, Sdf};
+
+use super::FontMapping;
+
+/// Looks up the fonts used to measure text
+///
+/// Layout only needs a font's glyph metrics, so it doesn't care where they come from. Normally
+/// they are the loaded [`KayakFont`] assets, but [`HeadlessAssets`] can stand in for them when
+/// there is nothing to load fonts with.
+pub trait FontProvider: Send + Sync {
+    /// Returns the font with the given name, or the font to fall back to if it isn't available
+    fn font(&self, name: &str) -> Option<&KayakFont>;
+
+    /// Checks if the given font is still waiting to be loaded
+    fn is_loading(&self, name: &str) -> bool;
+
+    /// Finds the first font, out of the given font and its fallbacks, that has a glyph for
+    /// every character of the content
+    fn find_covering_font(&self, content: &str, name: &str, fallbacks: &[String])
+        -> Option<String>;
+}
+
+/// The fonts loaded through the [`FontMapping`]
+pub struct LoadedFonts<'a> {
+    pub font_mapping: &'a FontMapping,
+    pub fonts: &'a Assets<KayakFont>,
+}
+
+impl<'a> FontProvider for LoadedFonts<'a> {
+    fn font(&self, name: &str) -> Option<&KayakFont> {
+        let handle = self.font_mapping.get_loaded_handle(name, self.fonts)?;
+        self.fonts.get(&handle)
+    }
+
+    fn is_loading(&self, name: &str) -> bool {
+        self.font_mapping.is_loading(name, self.fonts)
+    }
+
+    fn find_covering_font(
+        &self,
+        content: &str,
+        name: &str,
+        fallbacks: &[String],
+    ) -> Option<String> {
+        self.font_mapping
+            .find_covering_font(content, name, fallbacks, self.fonts)
+    }
+}
+
+/// A stand-in for the font assets that measures every font as the same monospace font
+///
+/// When this resource exists, text is measured with it instead of the fonts in the
+/// [`FontMapping`], so layouts are deterministic and don't wait on any assets. Combined with a
+/// renderer that has no backend, this allows running the widget systems, the layout and
+/// [`KayakRootContext::build_render_primitives`](crate::prelude::KayakRootContext::build_render_primitives)
+/// without a GPU, such as in CI:
+///
+/// ```ignore
+/// App::new()
+///     .insert_resource(WgpuSettings {
+///         backends: None,
+///         ..Default::default()
+///     })
+///     .add_plugins(DefaultPlugins)
+///     .add_plugin(KayakContextPlugin)
+///     .add_plugin(KayakWidgets)
+///     // Every glyph is half as wide as the font size
+///     .insert_resource(HeadlessAssets::new(0.5))
+///     .add_startup_system(startup)
+///     .run();
+/// ```
+///
+/// Text can't be rendered with these fonts since they don't have an atlas.
+#[derive(Resource)]
+pub struct HeadlessAssets {
+    font: KayakFont,
+}
+
+impl HeadlessAssets {
+    /// Creates fonts where every glyph has the given advance, relative to the font size
+    pub fn new(advance: f32) -> Self {
+        Self {
+            font: KayakFont::new(Sdf::monospace(advance), ImageType::Atlas(Handle::default())),
+        }
+    }
+}
+
+impl Default for HeadlessAssets {
+    fn default() -> Self {
+        Self::new(0.5)
+    }
+}
+
+impl FontProvider for HeadlessAssets {
+    fn font(&self, _name: &str) -> Option<&KayakFont> {
+        Some(&self.font)
+    }
+
+    fn is_loading(&self, _name: &str) -> bool {
+        false
+    }
+
+    fn find_covering_font(
+        &self,
+        _content: &str,
+        _name: &str,
+        _fallbacks: &[String],
+    ) -> Option<String> {
+        None
+    }
+}
@@ -1,12 +1,23 @@
-use bevy::prelude::{Added, Entity, Plugin, Query, ResMut};
+use bevy::prelude::{
+    Added, AssetEvent, Assets, Commands, Entity, EventReader, Plugin, Query, Res, ResMut,
+};
+use kayak_font::KayakFont;
 
 mod extract;
 mod font_mapping;
+mod font_provider;
+mod selection;
 
 pub use extract::extract_texts;
 pub use font_mapping::*;
+pub use font_provider::*;
+pub(crate) use selection::{caret_index, selection_rects};
 
-use crate::context::KayakRootContext;
+use crate::{
+    context::KayakRootContext,
+    node::{DirtyNode, Node},
+    render_primitive::RenderPrimitive,
+};
 
 #[derive(Default)]
 pub struct TextRendererPlugin;
@@ -14,7 +25,8 @@ pub struct TextRendererPlugin;
 impl Plugin for TextRendererPlugin {
     fn build(&self, app: &mut bevy::prelude::App) {
         app.init_resource::<FontMapping>()
-            .add_system(process_loaded_fonts);
+            .add_system(process_loaded_fonts)
+            .add_system(process_font_events);
     }
 }
 
@@ -26,3 +38,38 @@ fn process_loaded_fonts(
         font_mapping.mark_all_as_new();
     }
 }
+
+/// Re-measures text when a font is loaded or reloaded with different metrics
+///
+/// Reloads that don't change the font's metrics don't cause a re-layout.
+fn process_font_events(
+    mut commands: Commands,
+    mut font_mapping: ResMut<FontMapping>,
+    mut font_events: EventReader<AssetEvent<KayakFont>>,
+    fonts: Res<Assets<KayakFont>>,
+    nodes: Query<(Entity, &Node)>,
+) {
+    for event in font_events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+        let changed_font = match fonts
+            .get(handle)
+            .and_then(|font| font_mapping.update_metrics(handle, font))
+        {
+            Some(changed_font) => changed_font,
+            None => continue,
+        };
+
+        // Any text could be falling back to the default font
+        let is_default = changed_font == crate::DEFAULT_FONT;
+        for (entity, node) in nodes.iter() {
+            if let RenderPrimitive::Text { font, .. } = &node.primitive {
+                if is_default || *font == changed_font {
+                    commands.entity(entity).insert(DirtyNode);
+                }
+            }
+        }
+    }
+}
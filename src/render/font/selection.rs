@@ -0,0 +1,193 @@
+use kayak_font::{Alignment, TextLayout};
+
+use crate::{layout::Rect, styles::TextDirection};
+
+/// Computes the highlight rects covering the chars in `start..end`, one per line of text
+///
+/// The rects are positioned within the given layout of the text, and span the full line
+/// height. Graphemes are selected whole when any of their chars are in the range. Justified
+/// lines are treated as start aligned, since the extra space between words isn't tracked.
+pub(crate) fn selection_rects(
+    text_layout: &TextLayout,
+    direction: TextDirection,
+    layout: &Rect,
+    start: usize,
+    end: usize,
+) -> Vec<Rect> {
+    let properties = text_layout.properties();
+    let mut rects = Vec::new();
+    if start >= end {
+        return rects;
+    }
+
+    for (index, line) in text_layout.lines().iter().enumerate() {
+        let (left, right) = match line
+            .graphemes()
+            .iter()
+            .filter(|grapheme| {
+                grapheme.char_index < end && grapheme.char_index + grapheme.char_total > start
+            })
+            .fold(None, |bounds: Option<(f32, f32)>, grapheme| {
+                let (left, right) = (grapheme.position.0, grapheme.position.0 + grapheme.size.0);
+                Some(bounds.map_or((left, right), |(min, max)| (min.min(left), max.max(right))))
+            }) {
+            Some(bounds) => bounds,
+            None => continue,
+        };
+
+        // Matches the shifting applied to the glyphs of the line
+        let shift = match properties.alignment {
+            Alignment::Start | Alignment::Justify => 0.0,
+            Alignment::Middle => (properties.max_size.0 - line.width()) / 2.0,
+            Alignment::End => properties.max_size.0 - line.width(),
+        };
+        let (left, right) = if direction == TextDirection::Rtl {
+            (line.width() - right, line.width() - left)
+        } else {
+            (left, right)
+        };
+
+        rects.push(Rect {
+            posx: layout.posx + shift + left,
+            posy: layout.posy + properties.line_height * index as f32,
+            width: right - left,
+            height: properties.line_height,
+            z_index: layout.z_index,
+            paint_order: layout.paint_order,
+        });
+    }
+    rects
+}
+
+/// Finds the char index closest to the given point, for placing a caret
+///
+/// The point picks the nearest line, then the nearest boundary between graphemes on that line.
+/// Points before the first grapheme of a line give its start, and points past the end give its
+/// end, before any line break.
+pub(crate) fn caret_index(
+    text_layout: &TextLayout,
+    direction: TextDirection,
+    layout: &Rect,
+    point: (f32, f32),
+) -> usize {
+    let properties = text_layout.properties();
+    let lines = text_layout.lines();
+    if lines.is_empty() {
+        return 0;
+    }
+
+    let row = ((point.1 - layout.posy) / properties.line_height).floor();
+    let row = (row.max(0.0) as usize).min(lines.len() - 1);
+    let line = &lines[row];
+
+    let shift = match properties.alignment {
+        Alignment::Start | Alignment::Justify => 0.0,
+        Alignment::Middle => (properties.max_size.0 - line.width()) / 2.0,
+        Alignment::End => properties.max_size.0 - line.width(),
+    };
+    let x = point.0 - layout.posx - shift;
+    let x = if direction == TextDirection::Rtl {
+        line.width() - x
+    } else {
+        x
+    };
+
+    for grapheme in line.graphemes() {
+        if x < grapheme.position.0 + grapheme.size.0 / 2.0 {
+            return grapheme.char_index;
+        }
+    }
+
+    match line.graphemes().last() {
+        // Line breaks take up no space, so the caret goes before them
+        Some(last) if row + 1 < lines.len() && last.size.0 == 0.0 => last.char_index,
+        Some(last) => last.char_index + last.char_total,
+        None => line.char_index(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Handle;
+    use kayak_font::{Alignment, ImageType, KayakFont, Sdf, TextProperties};
+
+    use super::{caret_index, selection_rects};
+    use crate::{layout::Rect, styles::TextDirection};
+
+    #[test]
+    fn selection_should_span_wrapped_lines() {
+        let font = KayakFont::new(
+            Sdf::from_bytes(include_bytes!("../../../assets/roboto.kayak_font")),
+            ImageType::Atlas(Handle::default()),
+        );
+        let properties = TextProperties {
+            font_size: 14.0,
+            line_height: 20.0,
+            alignment: Alignment::Start,
+            max_size: (60.0, 100000.0),
+            tab_size: 4,
+        };
+        let text_layout = font.measure("Hello there world", properties);
+        assert!(text_layout.lines().len() > 1);
+        let layout = Rect {
+            posx: 10.0,
+            posy: 50.0,
+            width: 60.0,
+            height: 100.0,
+            z_index: 0.0,
+            paint_order: 0,
+        };
+
+        // Everything from the "e" in "Hello" to the "t" in "there", across the wrap
+        let rects = selection_rects(&text_layout, TextDirection::Ltr, &layout, 1, 7);
+        assert_eq!(2, rects.len());
+        let first_line = &text_layout.lines()[0];
+        assert!(rects[0].posx > layout.posx);
+        assert!(
+            (rects[0].posx + rects[0].width - (layout.posx + first_line.width())).abs() < 0.001
+        );
+        assert_eq!(layout.posy, rects[0].posy);
+        assert_eq!(layout.posx, rects[1].posx);
+        assert_eq!(layout.posy + properties.line_height, rects[1].posy);
+        assert_eq!(properties.line_height, rects[1].height);
+
+        assert!(selection_rects(&text_layout, TextDirection::Ltr, &layout, 3, 3).is_empty());
+    }
+
+    #[test]
+    fn caret_should_clamp_to_line_bounds() {
+        let font = KayakFont::new(
+            Sdf::from_bytes(include_bytes!("../../../assets/roboto.kayak_font")),
+            ImageType::Atlas(Handle::default()),
+        );
+        let properties = TextProperties {
+            font_size: 14.0,
+            line_height: 20.0,
+            alignment: Alignment::Start,
+            max_size: (100000.0, 100000.0),
+            tab_size: 4,
+        };
+        let text_layout = font.measure("Hello\nworld", properties);
+        let layout = Rect {
+            posx: 10.0,
+            posy: 50.0,
+            width: 100.0,
+            height: 40.0,
+            z_index: 0.0,
+            paint_order: 0,
+        };
+        let caret = |point| caret_index(&text_layout, TextDirection::Ltr, &layout, point);
+
+        // Before the first glyph
+        assert_eq!(0, caret((0.0, 0.0)));
+        // Past the end of the first line, before its line break
+        assert_eq!(5, caret((500.0, 55.0)));
+        // Past the end of the text
+        assert_eq!(11, caret((500.0, 500.0)));
+
+        // Just past the middle of the "e" on the first line
+        let e = text_layout.lines()[0].graphemes()[1];
+        assert_eq!(2, caret((10.0 + e.position.0 + e.size.0 * 0.6, 55.0)));
+        assert_eq!(1, caret((10.0 + e.position.0 + e.size.0 * 0.4, 55.0)));
+    }
+}
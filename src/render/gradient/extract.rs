@@ -0,0 +1,221 @@
+use crate::{
+    render::unified::pipeline::{ExtractQuadBundle, ExtractedQuad, UIQuadType},
+    render_primitive::RenderPrimitive,
+    styles::{Corner, Gradient, GradientKind},
+};
+use bevy::{
+    math::Vec2,
+    prelude::{Color, Entity, Rect},
+};
+
+/// The number of cells along each axis used to approximate gradients that can't be
+/// represented exactly by blending a single quad's corner colors.
+const GRADIENT_SUBDIVISIONS: usize = 16;
+
+pub fn extract_gradient(
+    camera_entity: Entity,
+    render_primitive: &RenderPrimitive,
+    dpi: f32,
+) -> Vec<ExtractQuadBundle> {
+    let (stops, kind, layout, border_color, mut border, border_radius) = match render_primitive {
+        RenderPrimitive::Gradient {
+            stops,
+            kind,
+            layout,
+            border_color,
+            border,
+            border_radius,
+        } => (
+            stops,
+            *kind,
+            *layout,
+            *border_color,
+            *border,
+            *border_radius,
+        ),
+        _ => panic!(""),
+    };
+
+    border *= dpi;
+
+    let bounds_min = Vec2::new(layout.posx, layout.posy);
+    let bounds_max = bounds_min + Vec2::new(layout.width, layout.height) * dpi;
+    let quad = |rect: Rect, color: Color, border_radius: Corner<f32>| ExtractedQuad {
+        camera_entity,
+        rect,
+        color,
+        vertex_index: 0,
+        char_id: 0,
+        z_index: layout.z_index,
+        paint_order: layout.paint_order,
+        rotation: 0.0,
+        font_handle: None,
+        quad_type: UIQuadType::Quad,
+        type_index: 0,
+        border_radius,
+        image: None,
+        uv_max: None,
+        uv_min: None,
+        corner_colors: None,
+        clip: None,
+    };
+
+    // The border is drawn behind the gradient, the same way as the border of a quad
+    let mut extracted_quads = vec![ExtractQuadBundle {
+        extracted_quad: quad(
+            Rect {
+                min: bounds_min,
+                max: bounds_max,
+            },
+            border_color,
+            border_radius,
+        ),
+    }];
+
+    let origin = bounds_min + Vec2::new(border.left, border.top);
+    let size = (bounds_max - Vec2::new(border.right, border.bottom) - origin).max(Vec2::ZERO);
+
+    // A linear blend between two stops is exact when interpolated across a single quad.
+    let subdivisions = match kind {
+        GradientKind::Linear { .. } if stops.len() <= 2 => 1,
+        _ => GRADIENT_SUBDIVISIONS,
+    };
+
+    let color_at = |point: Vec2| -> Color {
+        Gradient::color_at(stops, Gradient::offset_at(kind, point, size))
+    };
+
+    // The cells in the corners are made large enough to hold the rounded corner, since each cell
+    // is rounded on its own.
+    let columns = cell_edges(
+        size.x,
+        subdivisions,
+        border_radius.top_left.max(border_radius.bottom_left),
+        border_radius.top_right.max(border_radius.bottom_right),
+    );
+    let rows = cell_edges(
+        size.y,
+        subdivisions,
+        border_radius.top_left.max(border_radius.top_right),
+        border_radius.bottom_left.max(border_radius.bottom_right),
+    );
+    let (last_row, last_column) = (rows.len() - 2, columns.len() - 2);
+
+    extracted_quads.reserve((rows.len() - 1) * (columns.len() - 1));
+    for (row, row_edges) in rows.windows(2).enumerate() {
+        for (column, column_edges) in columns.windows(2).enumerate() {
+            let min = Vec2::new(column_edges[0], row_edges[0]);
+            let max = Vec2::new(column_edges[1], row_edges[1]);
+            let corner = |in_corner: bool, radius: f32| if in_corner { radius } else { 0.0 };
+            let mut extracted_quad = quad(
+                Rect {
+                    min: origin + min,
+                    max: origin + max,
+                },
+                Color::WHITE,
+                Corner {
+                    top_left: corner(row == 0 && column == 0, border_radius.top_left),
+                    top_right: corner(row == 0 && column == last_column, border_radius.top_right),
+                    bottom_left: corner(row == last_row && column == 0, border_radius.bottom_left),
+                    bottom_right: corner(
+                        row == last_row && column == last_column,
+                        border_radius.bottom_right,
+                    ),
+                },
+            );
+            extracted_quad.corner_colors = Some(Corner {
+                top_left: color_at(min),
+                top_right: color_at(Vec2::new(max.x, min.y)),
+                bottom_left: color_at(Vec2::new(min.x, max.y)),
+                bottom_right: color_at(max),
+            });
+            extracted_quads.push(ExtractQuadBundle { extracted_quad });
+        }
+    }
+
+    extracted_quads
+}
+
+/// Splits `length` into cells along one axis, returning the edges between them
+///
+/// The length is divided evenly into `subdivisions` cells, except that the first and last cells
+/// span at least `start` and `end`, so that a rounded corner fits within a single cell.
+fn cell_edges(length: f32, subdivisions: usize, start: f32, end: f32) -> Vec<f32> {
+    let start = start.clamp(0.0, length);
+    let end = (length - end).clamp(start, length);
+
+    let mut edges = vec![0.0, start];
+    edges.extend(
+        (1..subdivisions)
+            .map(|cell| length * cell as f32 / subdivisions as f32)
+            .filter(|edge| *edge > start && *edge < end),
+    );
+    edges.extend([end, length]);
+    edges.dedup();
+    if edges.len() < 2 {
+        edges.push(length);
+    }
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Color, Entity, Vec2};
+
+    use super::{cell_edges, extract_gradient};
+    use crate::{
+        layout::Rect,
+        render_primitive::RenderPrimitive,
+        styles::{Corner, Edge, GradientKind},
+    };
+
+    #[test]
+    fn corner_cells_should_fit_the_border_radius() {
+        assert_eq!(
+            vec![0.0, 25.0, 50.0, 75.0, 100.0],
+            cell_edges(100.0, 4, 0.0, 0.0)
+        );
+        assert_eq!(
+            vec![0.0, 30.0, 50.0, 75.0, 90.0, 100.0],
+            cell_edges(100.0, 4, 30.0, 10.0)
+        );
+        assert_eq!(vec![0.0, 100.0], cell_edges(100.0, 1, 0.0, 0.0));
+        assert_eq!(vec![0.0, 0.0], cell_edges(0.0, 1, 0.0, 0.0));
+    }
+
+    #[test]
+    fn gradient_should_be_drawn_inside_its_rounded_border() {
+        let gradient = RenderPrimitive::Gradient {
+            stops: vec![(Color::BLACK, 0.0), (Color::WHITE, 1.0)],
+            kind: GradientKind::Linear { angle: 0.0 },
+            layout: Rect {
+                width: 100.0,
+                height: 50.0,
+                ..Default::default()
+            },
+            border_color: Color::RED,
+            border: Edge::all(2.0),
+            border_radius: Corner::all(10.0),
+        };
+
+        let quads = extract_gradient(Entity::from_raw(0), &gradient, 1.0);
+        let border = &quads[0].extracted_quad;
+        assert_eq!(Color::RED, border.color);
+        assert_eq!(Corner::all(10.0), border.border_radius);
+        assert_eq!(Vec2::new(100.0, 50.0), border.rect.size());
+
+        let cells = &quads[1..];
+        // The corners are split off into cells of their own, which are the only rounded ones
+        assert_eq!(9, cells.len());
+        let top_left = &cells[0].extracted_quad;
+        assert_eq!(Vec2::new(2.0, 2.0), top_left.rect.min);
+        assert_eq!(10.0, top_left.border_radius.top_left);
+        assert_eq!(0.0, top_left.border_radius.bottom_right);
+        let bottom_right = &cells[8].extracted_quad;
+        assert_eq!(Vec2::new(98.0, 48.0), bottom_right.rect.max);
+        assert_eq!(10.0, bottom_right.border_radius.bottom_right);
+        for edge in [1, 3, 4, 5, 7] {
+            assert_eq!(Corner::default(), cells[edge].extracted_quad.border_radius);
+        }
+    }
+}
@@ -1,51 +1,164 @@
 use crate::{
     render::unified::pipeline::{ExtractQuadBundle, ExtractedQuad, UIQuadType},
     render_primitive::RenderPrimitive,
-    styles::Corner,
+    styles::{Corner, Repeat},
 };
 use bevy::{
     math::Vec2,
-    prelude::{Entity, Rect},
+    prelude::{Assets, Entity, Image, Rect, Res},
     render::color::Color,
 };
 
 pub fn extract_images(
     camera_entity: Entity,
     render_command: &RenderPrimitive,
+    images: &Res<Assets<Image>>,
     _dpi: f32,
 ) -> Vec<ExtractQuadBundle> {
-    let (border_radius, layout, handle) = match render_command {
+    let (border_radius, layout, handle, opacity, repeat) = match render_command {
         RenderPrimitive::Image {
             border_radius,
             layout,
             handle,
-        } => (*border_radius, layout, handle),
+            opacity,
+            repeat,
+        } => (*border_radius, layout, handle, *opacity, *repeat),
         _ => panic!(""),
     };
 
-    vec![ExtractQuadBundle {
-        extracted_quad: ExtractedQuad {
-            camera_entity,
-            rect: Rect {
-                min: Vec2::new(layout.posx, layout.posy),
-                max: Vec2::new(layout.posx + layout.width, layout.posy + layout.height),
-            },
-            color: Color::WHITE,
-            vertex_index: 0,
-            char_id: 0,
-            z_index: layout.z_index,
-            font_handle: None,
-            quad_type: UIQuadType::Image,
-            type_index: 0,
-            border_radius: Corner {
-                top_left: border_radius.top_left,
-                top_right: border_radius.top_right,
-                bottom_left: border_radius.bottom_left,
-                bottom_right: border_radius.bottom_right,
-            },
-            image: Some(handle.clone_weak()),
-            uv_max: None,
-            uv_min: None,
+    let extracted_quad_template = ExtractedQuad {
+        camera_entity,
+        rect: Rect {
+            min: Vec2::new(layout.posx, layout.posy),
+            max: Vec2::new(layout.posx + layout.width, layout.posy + layout.height),
+        },
+        color: Color::rgba(1.0, 1.0, 1.0, opacity),
+        vertex_index: 0,
+        char_id: 0,
+        z_index: layout.z_index,
+        paint_order: layout.paint_order,
+        rotation: 0.0,
+        font_handle: None,
+        quad_type: UIQuadType::Image,
+        type_index: 0,
+        border_radius: Corner {
+            top_left: border_radius.top_left,
+            top_right: border_radius.top_right,
+            bottom_left: border_radius.bottom_left,
+            bottom_right: border_radius.bottom_right,
         },
-    }]
+        image: Some(handle.clone_weak()),
+        uv_max: None,
+        uv_min: None,
+        corner_colors: None,
+        clip: None,
+    };
+
+    if repeat == Repeat::NoRepeat {
+        return vec![ExtractQuadBundle {
+            extracted_quad: extracted_quad_template,
+        }];
+    }
+
+    // Tiling needs the size of the image, so nothing is drawn until it has loaded.
+    let image_size = match images.get(handle) {
+        Some(image) => Vec2::new(
+            image.texture_descriptor.size.width as f32,
+            image.texture_descriptor.size.height as f32,
+        ),
+        None => return vec![],
+    };
+
+    tile(extracted_quad_template.rect, image_size, repeat)
+        .into_iter()
+        .map(|(rect, uv_min, uv_max)| ExtractQuadBundle {
+            extracted_quad: ExtractedQuad {
+                rect,
+                // Rounded corners only make sense for a single quad
+                border_radius: Corner::default(),
+                uv_min: Some(uv_min),
+                uv_max: Some(uv_max),
+                ..extracted_quad_template.clone()
+            },
+        })
+        .collect()
+}
+
+/// Splits the given rect into tiles of the image's size along each repeated axis
+///
+/// Returns each tile's rect along with its UV coordinates. Tiles at the far edges are cut
+/// short, showing only the part of the image that fits. Axes that aren't repeated are covered
+/// by a single, stretched span.
+fn tile(rect: Rect, image_size: Vec2, repeat: Repeat) -> Vec<(Rect, Vec2, Vec2)> {
+    // Each span is (start, end, fraction of the image shown)
+    let spans = |min: f32, max: f32, size: f32, repeats: bool| {
+        if !repeats || size <= 0.0 {
+            return vec![(min, max, 1.0)];
+        }
+        let mut spans = Vec::new();
+        let mut start = min;
+        while start < max {
+            let end = (start + size).min(max);
+            spans.push((start, end, (end - start) / size));
+            start = end;
+        }
+        spans
+    };
+
+    let columns = spans(rect.min.x, rect.max.x, image_size.x, repeat.repeats_x());
+    let rows = spans(rect.min.y, rect.max.y, image_size.y, repeat.repeats_y());
+    let mut tiles = Vec::with_capacity(columns.len() * rows.len());
+    for (min_y, max_y, fraction_y) in rows.iter() {
+        for (min_x, max_x, fraction_x) in columns.iter() {
+            tiles.push((
+                Rect {
+                    min: Vec2::new(*min_x, *min_y),
+                    max: Vec2::new(*max_x, *max_y),
+                },
+                // The top of the image is at the top of the UV range
+                Vec2::new(0.0, 1.0 - fraction_y),
+                Vec2::new(*fraction_x, 1.0),
+            ));
+        }
+    }
+    tiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tile;
+    use crate::styles::Repeat;
+    use bevy::prelude::{Rect, Vec2};
+
+    #[test]
+    fn repeat_x_should_cut_the_last_tile_short() {
+        let rect = Rect {
+            min: Vec2::new(10.0, 0.0),
+            max: Vec2::new(60.0, 40.0),
+        };
+        let tiles = tile(rect, Vec2::new(20.0, 20.0), Repeat::RepeatX);
+
+        assert_eq!(3, tiles.len());
+        assert_eq!(Vec2::new(30.0, 0.0), tiles[1].0.min);
+        // The last tile only shows the left half of the image
+        assert_eq!(Vec2::new(50.0, 0.0), tiles[2].0.min);
+        assert_eq!(Vec2::new(60.0, 40.0), tiles[2].0.max);
+        assert_eq!(Vec2::new(0.5, 1.0), tiles[2].2);
+        // The image is stretched vertically
+        assert_eq!(Vec2::new(0.0, 0.0), tiles[2].1);
+    }
+
+    #[test]
+    fn repeat_should_tile_both_axes() {
+        let rect = Rect {
+            min: Vec2::ZERO,
+            max: Vec2::new(40.0, 30.0),
+        };
+        let tiles = tile(rect, Vec2::new(20.0, 20.0), Repeat::Repeat);
+
+        assert_eq!(4, tiles.len());
+        // The bottom row only shows the top half of the image
+        assert_eq!(Vec2::new(0.0, 0.5), tiles[3].1);
+        assert_eq!(Vec2::new(1.0, 1.0), tiles[3].2);
+    }
 }
@@ -14,10 +14,13 @@ use crate::{
 
 use self::{extract::BevyKayakUIExtractPlugin, ui_pass::TransparentUI};
 
+pub(crate) mod box_shadow;
 mod extract;
 pub(crate) mod font;
+pub(crate) mod gradient;
 pub(crate) mod image;
 pub(crate) mod nine_patch;
+pub(crate) mod path;
 pub(crate) mod quad;
 pub(crate) mod texture_atlas;
 mod ui_pass;
@@ -17,12 +17,13 @@ pub fn extract_nine_patch(
 ) -> Vec<ExtractQuadBundle> {
     let mut extracted_quads = Vec::new();
 
-    let (layout, handle, border) = match render_primitive {
+    let (layout, handle, border, opacity) = match render_primitive {
         RenderPrimitive::NinePatch {
             layout,
             handle,
             border,
-        } => (layout, handle, border),
+            opacity,
+        } => (layout, handle, border, *opacity),
         _ => panic!(""),
     };
 
@@ -48,10 +49,12 @@ pub fn extract_nine_patch(
             min: Vec2::ZERO,
             max: Vec2::ZERO,
         },
-        color: Color::WHITE,
+        color: Color::rgba(1.0, 1.0, 1.0, opacity),
         vertex_index: 0,
         char_id: 0,
         z_index: layout.z_index,
+        paint_order: layout.paint_order,
+        rotation: 0.0,
         font_handle: None,
         quad_type: UIQuadType::Image,
         type_index: 0,
@@ -59,6 +62,8 @@ pub fn extract_nine_patch(
         image: Some(handle.clone_weak()),
         uv_max: None,
         uv_min: None,
+        corner_colors: None,
+        clip: None,
     };
 
     let top_uv_min_y = (image_size.y - border.top) / image_size.y;
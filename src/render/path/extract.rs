@@ -0,0 +1,98 @@
+use crate::{
+    render::unified::pipeline::{ExtractQuadBundle, ExtractedQuad, UIQuadType},
+    render_primitive::RenderPrimitive,
+    styles::Corner,
+};
+use bevy::{
+    math::Vec2,
+    prelude::{Entity, Rect},
+};
+
+pub fn extract_path(
+    camera_entity: Entity,
+    render_primitive: &RenderPrimitive,
+    dpi: f32,
+) -> Vec<ExtractQuadBundle> {
+    let (thickness, color, z_index, paint_order) = match render_primitive {
+        RenderPrimitive::Path {
+            thickness,
+            color,
+            layout,
+            ..
+        } => (*thickness, *color, layout.z_index, layout.paint_order),
+        _ => panic!(""),
+    };
+
+    let half = Vec2::splat(thickness.max(0.0) * dpi / 2.0);
+    let quad = |min: Vec2, max: Vec2, rotation: f32| ExtractQuadBundle {
+        extracted_quad: ExtractedQuad {
+            camera_entity,
+            rect: Rect { min, max },
+            color,
+            vertex_index: 0,
+            char_id: 0,
+            z_index,
+            paint_order,
+            rotation,
+            font_handle: None,
+            quad_type: UIQuadType::Quad,
+            type_index: 0,
+            border_radius: Corner::default(),
+            image: None,
+            uv_min: None,
+            uv_max: None,
+            corner_colors: None,
+            clip: None,
+        },
+    };
+
+    let mut quads = Vec::new();
+    for (start, end) in render_primitive.path_segments() {
+        let start = Vec2::new(start.0, start.1) * dpi;
+        let end = Vec2::new(end.0, end.1) * dpi;
+        if start.x == end.x || start.y == end.y {
+            // Straight lines fit in a single quad
+            quads.push(quad(start.min(end) - half, start.max(end) + half, 0.0));
+            continue;
+        }
+
+        // Diagonal lines are a straight line along the x axis, rotated onto the segment
+        let direction = end - start;
+        let center = (start + end) / 2.0;
+        let extent = Vec2::new(direction.length() / 2.0, 0.0) + half;
+        quads.push(quad(
+            center - extent,
+            center + extent,
+            direction.y.atan2(direction.x),
+        ));
+    }
+
+    quads
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Color, Entity, Vec2};
+
+    use super::extract_path;
+    use crate::{layout::Rect, render_primitive::RenderPrimitive};
+
+    #[test]
+    fn diagonal_segments_should_be_a_single_rotated_quad() {
+        let path = RenderPrimitive::Path {
+            points: vec![(0.0, 0.0), (30.0, 40.0)],
+            thickness: 2.0,
+            color: Color::WHITE,
+            closed: false,
+            layout: Rect::default(),
+        };
+
+        let quads = extract_path(Entity::from_raw(0), &path, 2.0);
+        assert_eq!(1, quads.len());
+        let quad = &quads[0].extracted_quad;
+        // The segment is 50px long, with half of the thickness added to either end, at 2x dpi
+        assert_eq!(Vec2::new(104.0, 4.0), quad.rect.size());
+        assert_eq!(Vec2::new(30.0, 40.0), quad.rect.center());
+        assert!((quad.rotation - 40.0f32.atan2(30.0)).abs() < 0.0001);
+    }
+}
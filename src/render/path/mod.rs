@@ -0,0 +1,2 @@
+mod extract;
+pub use extract::extract_path;
@@ -49,6 +49,8 @@ pub fn extract_quads(
                 vertex_index: 0,
                 char_id: 0,
                 z_index: layout.z_index,
+                paint_order: layout.paint_order,
+                rotation: 0.0,
                 font_handle: None,
                 quad_type: UIQuadType::Quad,
                 type_index: 0,
@@ -61,6 +63,8 @@ pub fn extract_quads(
                 image: None,
                 uv_max: None,
                 uv_min: None,
+                corner_colors: None,
+                clip: None,
             },
         },
         ExtractQuadBundle {
@@ -77,6 +81,8 @@ pub fn extract_quads(
                 vertex_index: 0,
                 char_id: 0,
                 z_index: layout.z_index,
+                paint_order: layout.paint_order,
+                rotation: 0.0,
                 font_handle: None,
                 quad_type: UIQuadType::Quad,
                 type_index: 0,
@@ -89,6 +95,8 @@ pub fn extract_quads(
                 image: None,
                 uv_max: None,
                 uv_min: None,
+                corner_colors: None,
+                clip: None,
             },
         },
     ]
@@ -17,13 +17,14 @@ pub fn extract_texture_atlas(
 ) -> Vec<ExtractQuadBundle> {
     let mut extracted_quads = Vec::new();
 
-    let (size, position, layout, handle) = match render_primitive {
+    let (size, position, layout, handle, opacity) = match render_primitive {
         RenderPrimitive::TextureAtlas {
             size,
             position,
             layout,
             handle,
-        } => (size, position, layout, handle),
+            opacity,
+        } => (size, position, layout, handle, *opacity),
         _ => panic!(""),
     };
 
@@ -57,15 +58,19 @@ pub fn extract_texture_atlas(
                 (position.x + size.x) / image_size.x,
                 1.0 - (position.y / image_size.y),
             )),
-            color: Color::WHITE,
+            color: Color::rgba(1.0, 1.0, 1.0, opacity),
             vertex_index: 0,
             char_id: 0,
             z_index: layout.z_index,
+            paint_order: layout.paint_order,
+            rotation: 0.0,
             font_handle: None,
             quad_type: UIQuadType::Image,
             type_index: 0,
             border_radius: Corner::default(),
             image: Some(handle.clone_weak()),
+            corner_colors: None,
+            clip: None,
         },
     };
     extracted_quads.push(quad);
@@ -15,14 +15,14 @@ use bevy::utils::FloatOrd;
 use crate::CameraUIKayak;
 
 pub struct TransparentUI {
-    pub sort_key: FloatOrd,
+    pub sort_key: (FloatOrd, u32),
     pub entity: Entity,
     pub pipeline: CachedRenderPipelineId,
     pub draw_function: DrawFunctionId,
 }
 
 impl PhaseItem for TransparentUI {
-    type SortKey = FloatOrd;
+    type SortKey = (FloatOrd, u32);
 
     #[inline]
     fn sort_key(&self) -> Self::SortKey {
@@ -169,6 +169,23 @@ impl FromWorld for UnifiedPipeline {
                     ty: BindingType::Sampler(SamplerBindingType::Filtering),
                     count: None,
                 },
+                // The alpha mask of the clip region the quad is drawn inside of
+                BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Texture {
+                        multisampled: false,
+                        sample_type: TextureSampleType::Float { filterable: true },
+                        view_dimension: TextureViewDimension::D2,
+                    },
+                    count: None,
+                },
+                BindGroupLayoutEntry {
+                    binding: 3,
+                    visibility: ShaderStages::FRAGMENT,
+                    ty: BindingType::Sampler(SamplerBindingType::Filtering),
+                    count: None,
+                },
             ],
             label: Some("image_layout"),
         });
@@ -224,6 +241,14 @@ impl FromWorld for UnifiedPipeline {
                     binding: 1,
                     resource: BindingResource::Sampler(&image.sampler),
                 },
+                BindGroupEntry {
+                    binding: 2,
+                    resource: BindingResource::TextureView(&image.texture_view),
+                },
+                BindGroupEntry {
+                    binding: 3,
+                    resource: BindingResource::Sampler(&image.sampler),
+                },
             ],
             layout: &image_layout,
         });
@@ -244,7 +269,7 @@ impl SpecializedRenderPipeline for UnifiedPipeline {
 
     fn specialize(&self, _key: Self::Key) -> RenderPipelineDescriptor {
         let vertex_buffer_layout = VertexBufferLayout {
-            array_stride: 60,
+            array_stride: 96,
             step_mode: VertexStepMode::Vertex,
             attributes: vec![
                 VertexAttribute {
@@ -267,6 +292,21 @@ impl SpecializedRenderPipeline for UnifiedPipeline {
                     offset: 44,
                     shader_location: 3,
                 },
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 60,
+                    shader_location: 4,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32x4,
+                    offset: 76,
+                    shader_location: 5,
+                },
+                VertexAttribute {
+                    format: VertexFormat::Float32,
+                    offset: 92,
+                    shader_location: 6,
+                },
             ],
         };
 
@@ -336,6 +376,7 @@ pub enum UIQuadType {
     TextSubpixel,
     Image,
     Clip,
+    BoxShadow,
 }
 
 #[derive(Debug, Component, Clone)]
@@ -346,6 +387,10 @@ pub struct ExtractedQuad {
     pub vertex_index: usize,
     pub char_id: u32,
     pub z_index: f32,
+    /// The position of the primitive this quad was extracted from in paint order
+    pub paint_order: u32,
+    /// The angle in radians the quad is rotated by around its center
+    pub rotation: f32,
     pub font_handle: Option<Handle<KayakFont>>,
     pub quad_type: UIQuadType,
     pub type_index: u32,
@@ -353,6 +398,34 @@ pub struct ExtractedQuad {
     pub image: Option<Handle<Image>>,
     pub uv_min: Option<Vec2>,
     pub uv_max: Option<Vec2>,
+    /// Optional per-corner colors, blended across the quad instead of using `color`
+    pub corner_colors: Option<Corner<Color>>,
+    /// The clip region the quad is drawn inside of, if any
+    pub clip: Option<QuadClip>,
+}
+
+/// A clip region that is applied to each pixel of a quad in the fragment shader, on top of the
+/// scissor rect set by the [`UIQuadType::Clip`] quads.
+#[derive(Debug, Clone)]
+pub struct QuadClip {
+    /// The region in the same coordinates as the quads
+    pub rect: Rect,
+    /// The radius the corners of the region are rounded by
+    pub radius: Corner<f32>,
+    /// An image whose alpha is multiplied into the quads drawn inside of the region
+    pub mask: Option<Handle<Image>>,
+}
+
+impl QuadClip {
+    /// How the shader applies the clip: `0` not at all, `1` by its rounded shape and `2` by its
+    /// rounded shape and mask
+    fn mode(clip: Option<&QuadClip>) -> f32 {
+        match clip {
+            None => 0.0,
+            Some(QuadClip { mask: None, .. }) => 1.0,
+            Some(QuadClip { mask: Some(_), .. }) => 2.0,
+        }
+    }
 }
 
 #[repr(C)]
@@ -362,6 +435,9 @@ struct QuadVertex {
     pub color: [f32; 4],
     pub uv: [f32; 4],
     pub pos_size: [f32; 4],
+    pub clip_rect: [f32; 4],
+    pub clip_radius: [f32; 4],
+    pub clip_mode: f32,
 }
 
 #[repr(C)]
@@ -392,9 +468,25 @@ impl Default for QuadMeta {
     }
 }
 
+/// The image and clip mask a quad samples from
+type ImageBindGroupKey = (Option<Handle<Image>>, Option<Handle<Image>>);
+
 #[derive(Default, Resource)]
 pub struct ImageBindGroups {
-    values: HashMap<Handle<Image>, BindGroup>,
+    values: HashMap<ImageBindGroupKey, BindGroup>,
+}
+
+impl ExtractedQuad {
+    fn image_bind_group_key(&self) -> Option<ImageBindGroupKey> {
+        let mask = self.clip.as_ref().and_then(|clip| clip.mask.as_ref());
+        if self.image.is_none() && mask.is_none() {
+            return None;
+        }
+        Some((
+            self.image.as_ref().map(|image| image.clone_weak()),
+            mask.map(|mask| mask.clone_weak()),
+        ))
+    }
 }
 
 pub fn prepare_quads(
@@ -436,6 +528,13 @@ pub fn prepare_quads(
         _padding_3: 0,
     });
 
+    let box_shadow_type_offset = sprite_meta.types_buffer.push(QuadType {
+        t: 4,
+        _padding_1: 0,
+        _padding_2: 0,
+        _padding_3: 0,
+    });
+
     sprite_meta
         .types_buffer
         .write_buffer(&render_device, &render_queue);
@@ -451,73 +550,18 @@ pub fn prepare_quads(
         .filter(|es| es.quad_type != UIQuadType::Clip)
         .enumerate()
     {
-        let sprite_rect = extracted_sprite.rect;
-        let color = extracted_sprite.color.as_linear_rgba_f32();
-
         match extracted_sprite.quad_type {
             UIQuadType::Quad => extracted_sprite.type_index = quad_type_offset,
             UIQuadType::Text => extracted_sprite.type_index = text_type_offset,
             UIQuadType::TextSubpixel => extracted_sprite.type_index = text_sub_pixel_type_offset,
             UIQuadType::Image => extracted_sprite.type_index = image_type_offset,
+            UIQuadType::BoxShadow => extracted_sprite.type_index = box_shadow_type_offset,
             UIQuadType::Clip => {}
         };
 
-        let uv_min = extracted_sprite.uv_min.unwrap_or(Vec2::ZERO);
-        let uv_max = extracted_sprite.uv_max.unwrap_or(Vec2::ONE);
-
-        let bottom_left = Vec4::new(
-            uv_min.x,
-            uv_min.y,
-            extracted_sprite.char_id as f32,
-            extracted_sprite.border_radius.bottom_left,
-        );
-        let top_left = Vec4::new(
-            uv_min.x,
-            uv_max.y,
-            extracted_sprite.char_id as f32,
-            extracted_sprite.border_radius.top_left,
-        );
-        let top_right = Vec4::new(
-            uv_max.x,
-            uv_max.y,
-            extracted_sprite.char_id as f32,
-            extracted_sprite.border_radius.top_right,
-        );
-        let bottom_right = Vec4::new(
-            uv_max.x,
-            uv_min.y,
-            extracted_sprite.char_id as f32,
-            extracted_sprite.border_radius.bottom_right,
-        );
-
-        let uvs: [[f32; 4]; 6] = [
-            bottom_left.into(),
-            top_right.into(),
-            top_left.into(),
-            bottom_left.into(),
-            bottom_right.into(),
-            top_right.into(),
-        ];
-
         extracted_sprite.vertex_index = i;
-        for (index, vertex_position) in QUAD_VERTEX_POSITIONS.iter().enumerate() {
-            let world = Mat4::from_scale_rotation_translation(
-                sprite_rect.size().extend(1.0),
-                Quat::default(),
-                sprite_rect.min.extend(0.0),
-            );
-            let final_position = (world * (*vertex_position).extend(1.0)).truncate();
-            sprite_meta.vertices.push(QuadVertex {
-                position: final_position.into(),
-                color,
-                uv: uvs[index],
-                pos_size: [
-                    sprite_rect.min.x,
-                    sprite_rect.min.y,
-                    sprite_rect.size().x,
-                    sprite_rect.size().y,
-                ],
-            });
+        for vertex in quad_vertices(&extracted_sprite) {
+            sprite_meta.vertices.push(vertex);
         }
     }
     sprite_meta
@@ -525,6 +569,115 @@ pub fn prepare_quads(
         .write_buffer(&render_device, &render_queue);
 }
 
+/// Builds the vertices of the two triangles that make up a quad
+fn quad_vertices(extracted_sprite: &ExtractedQuad) -> Vec<QuadVertex> {
+    let sprite_rect = extracted_sprite.rect;
+    let color = extracted_sprite.color.as_linear_rgba_f32();
+
+    let uv_min = extracted_sprite.uv_min.unwrap_or(Vec2::ZERO);
+    let uv_max = extracted_sprite.uv_max.unwrap_or(Vec2::ONE);
+
+    let bottom_left = Vec4::new(
+        uv_min.x,
+        uv_min.y,
+        extracted_sprite.char_id as f32,
+        extracted_sprite.border_radius.bottom_left,
+    );
+    let top_left = Vec4::new(
+        uv_min.x,
+        uv_max.y,
+        extracted_sprite.char_id as f32,
+        extracted_sprite.border_radius.top_left,
+    );
+    let top_right = Vec4::new(
+        uv_max.x,
+        uv_max.y,
+        extracted_sprite.char_id as f32,
+        extracted_sprite.border_radius.top_right,
+    );
+    let bottom_right = Vec4::new(
+        uv_max.x,
+        uv_min.y,
+        extracted_sprite.char_id as f32,
+        extracted_sprite.border_radius.bottom_right,
+    );
+
+    let uvs: [[f32; 4]; 6] = [
+        bottom_left.into(),
+        top_right.into(),
+        top_left.into(),
+        bottom_left.into(),
+        bottom_right.into(),
+        top_right.into(),
+    ];
+
+    let clip_rect = extracted_sprite
+        .clip
+        .as_ref()
+        .map(|clip| {
+            [
+                clip.rect.min.x,
+                clip.rect.min.y,
+                clip.rect.max.x,
+                clip.rect.max.y,
+            ]
+        })
+        .unwrap_or_default();
+    let clip_radius = extracted_sprite
+        .clip
+        .as_ref()
+        .map(|clip| {
+            [
+                clip.radius.top_left,
+                clip.radius.top_right,
+                clip.radius.bottom_right,
+                clip.radius.bottom_left,
+            ]
+        })
+        .unwrap_or_default();
+    let clip_mode = QuadClip::mode(extracted_sprite.clip.as_ref());
+
+    let size = sprite_rect.size();
+    let rotation = Quat::from_rotation_z(extracted_sprite.rotation);
+    let world = Mat4::from_scale_rotation_translation(
+        size.extend(1.0),
+        rotation,
+        sprite_rect.center().extend(0.0) - rotation * (size / 2.0).extend(0.0),
+    );
+    let mut vertices = Vec::with_capacity(QUAD_VERTEX_POSITIONS.len());
+    for (index, vertex_position) in QUAD_VERTEX_POSITIONS.iter().enumerate() {
+        let final_position = (world * (*vertex_position).extend(1.0)).truncate();
+        // The shader finds the position within the quad from this, which stays unrotated
+        let local_position = vertex_position.truncate() * size;
+        let color = if let Some(corner_colors) = extracted_sprite.corner_colors {
+            match (vertex_position.x > 0.5, vertex_position.y > 0.5) {
+                (false, false) => corner_colors.top_left,
+                (true, false) => corner_colors.top_right,
+                (false, true) => corner_colors.bottom_left,
+                (true, true) => corner_colors.bottom_right,
+            }
+            .as_linear_rgba_f32()
+        } else {
+            color
+        };
+        vertices.push(QuadVertex {
+            position: final_position.into(),
+            color,
+            uv: uvs[index],
+            pos_size: [
+                final_position.x - local_position.x,
+                final_position.y - local_position.y,
+                size.x,
+                size.y,
+            ],
+            clip_rect,
+            clip_radius,
+            clip_mode,
+        });
+    }
+    vertices
+}
+
 pub fn queue_quads(
     draw_functions: Res<DrawFunctions<TransparentUI>>,
     render_device: Res<RenderDevice>,
@@ -571,36 +724,52 @@ pub fn queue_quads(
                 if quad.camera_entity != camera_entity {
                     continue;
                 }
-                if let Some(image_handle) = quad.image.as_ref() {
-                    if let Some(gpu_image) = gpu_images.get(image_handle) {
-                        image_bind_groups
-                            .values
-                            .entry(image_handle.clone_weak())
-                            .or_insert_with(|| {
-                                render_device.create_bind_group(&BindGroupDescriptor {
-                                    entries: &[
-                                        BindGroupEntry {
-                                            binding: 0,
-                                            resource: BindingResource::TextureView(
-                                                &gpu_image.texture_view,
-                                            ),
-                                        },
-                                        BindGroupEntry {
-                                            binding: 1,
-                                            resource: BindingResource::Sampler(&gpu_image.sampler),
-                                        },
-                                    ],
-                                    label: Some("ui_image_bind_group"),
-                                    layout: &unified_pipeline.image_layout,
-                                })
-                            });
+                if let Some(key) = quad.image_bind_group_key() {
+                    let default_image = &unified_pipeline.default_image.0;
+                    let image = match &key.0 {
+                        Some(handle) => gpu_images.get(handle),
+                        None => Some(default_image),
+                    };
+                    let mask = match &key.1 {
+                        Some(handle) => gpu_images.get(handle),
+                        None => Some(default_image),
+                    };
+                    if let (Some(gpu_image), Some(gpu_mask)) = (image, mask) {
+                        image_bind_groups.values.entry(key).or_insert_with(|| {
+                            render_device.create_bind_group(&BindGroupDescriptor {
+                                entries: &[
+                                    BindGroupEntry {
+                                        binding: 0,
+                                        resource: BindingResource::TextureView(
+                                            &gpu_image.texture_view,
+                                        ),
+                                    },
+                                    BindGroupEntry {
+                                        binding: 1,
+                                        resource: BindingResource::Sampler(&gpu_image.sampler),
+                                    },
+                                    BindGroupEntry {
+                                        binding: 2,
+                                        resource: BindingResource::TextureView(
+                                            &gpu_mask.texture_view,
+                                        ),
+                                    },
+                                    BindGroupEntry {
+                                        binding: 3,
+                                        resource: BindingResource::Sampler(&gpu_mask.sampler),
+                                    },
+                                ],
+                                label: Some("ui_image_bind_group"),
+                                layout: &unified_pipeline.image_layout,
+                            })
+                        });
                     }
                 }
                 transparent_phase.add(TransparentUI {
                     draw_function: draw_quad,
                     pipeline: spec_pipeline,
                     entity,
-                    sort_key: FloatOrd(quad.z_index),
+                    sort_key: (FloatOrd(quad.z_index), quad.paint_order),
                 });
             }
         }
@@ -702,8 +871,8 @@ impl Draw<TransparentUI> for DrawUI {
                 pass.set_bind_group(1, &unified_pipeline.empty_font_texture.1, &[]);
             }
 
-            if let Some(image_handle) = extracted_quad.image.as_ref() {
-                if let Some(bind_group) = image_bind_groups.into_inner().values.get(image_handle) {
+            if let Some(key) = extracted_quad.image_bind_group_key() {
+                if let Some(bind_group) = image_bind_groups.into_inner().values.get(&key) {
                     pass.set_bind_group(3, bind_group, &[]);
                 } else {
                     pass.set_bind_group(3, &unified_pipeline.default_image.1, &[]);
@@ -720,3 +889,118 @@ impl Draw<TransparentUI> for DrawUI {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Color, Entity, Handle, Rect, Vec2};
+
+    use super::{quad_vertices, ExtractedQuad, QuadClip, QuadVertex, UIQuadType};
+    use crate::styles::Corner;
+
+    fn quad(clip: Option<QuadClip>) -> ExtractedQuad {
+        ExtractedQuad {
+            camera_entity: Entity::from_raw(0),
+            rect: Rect {
+                min: Vec2::new(10.0, 10.0),
+                max: Vec2::new(30.0, 30.0),
+            },
+            color: Color::WHITE,
+            vertex_index: 0,
+            char_id: 0,
+            z_index: 0.0,
+            paint_order: 0,
+            rotation: 0.0,
+            font_handle: None,
+            quad_type: UIQuadType::Quad,
+            type_index: 0,
+            border_radius: Corner::default(),
+            image: None,
+            uv_min: None,
+            uv_max: None,
+            corner_colors: None,
+            clip,
+        }
+    }
+
+    #[test]
+    fn masked_quads_should_carry_their_clip_to_the_shader() {
+        let clip = QuadClip {
+            rect: Rect {
+                min: Vec2::new(0.0, 0.0),
+                max: Vec2::new(20.0, 40.0),
+            },
+            radius: Corner::default(),
+            mask: Some(Handle::default()),
+        };
+        let masked = quad(Some(clip.clone()));
+        for vertex in quad_vertices(&masked) {
+            assert_eq!(2.0, vertex.clip_mode);
+            assert_eq!([0.0, 0.0, 20.0, 40.0], vertex.clip_rect);
+        }
+        assert_eq!(
+            Some((None, Some(Handle::default()))),
+            masked.image_bind_group_key()
+        );
+
+        let clipped = quad(Some(QuadClip { mask: None, ..clip }));
+        assert!(quad_vertices(&clipped)
+            .iter()
+            .all(|vertex| vertex.clip_mode == 1.0));
+        assert_eq!(None, clipped.image_bind_group_key());
+
+        let unclipped = quad(None);
+        assert!(quad_vertices(&unclipped)
+            .iter()
+            .all(|vertex| vertex.clip_mode == 0.0));
+    }
+
+    #[test]
+    fn rounded_clips_should_pass_their_radius_to_the_shader() {
+        let clipped = quad(Some(QuadClip {
+            rect: Rect {
+                min: Vec2::ZERO,
+                max: Vec2::new(40.0, 40.0),
+            },
+            radius: Corner {
+                top_left: 1.0,
+                top_right: 2.0,
+                bottom_left: 4.0,
+                bottom_right: 3.0,
+            },
+            mask: None,
+        }));
+
+        for vertex in quad_vertices(&clipped) {
+            assert_eq!(1.0, vertex.clip_mode);
+            assert_eq!([1.0, 2.0, 3.0, 4.0], vertex.clip_radius);
+        }
+    }
+
+    #[test]
+    fn rotated_quads_should_turn_around_their_center() {
+        let rotated = ExtractedQuad {
+            rotation: std::f32::consts::FRAC_PI_2,
+            ..quad(None)
+        };
+
+        let vertices = quad_vertices(&rotated);
+        let center = vertices
+            .iter()
+            .map(|vertex| Vec2::new(vertex.position[0], vertex.position[1]))
+            .fold(Vec2::ZERO, |sum, position| sum + position)
+            / vertices.len() as f32;
+        assert!(center.abs_diff_eq(rotated.rect.center(), 0.001));
+        // The shader still sees the position within the quad as if it weren't rotated
+        let unrotated = quad_vertices(&quad(None));
+        for (vertex, unrotated) in vertices.iter().zip(unrotated.iter()) {
+            let local = |vertex: &QuadVertex| {
+                Vec2::new(
+                    vertex.position[0] - vertex.pos_size[0],
+                    vertex.position[1] - vertex.pos_size[1],
+                )
+            };
+            assert!(local(vertex).abs_diff_eq(local(unrotated), 0.001));
+            assert_eq!(unrotated.pos_size[2..], vertex.pos_size[2..]);
+        }
+    }
+}
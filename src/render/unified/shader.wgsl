@@ -23,6 +23,10 @@ struct VertexOutput {
     @location(3) size: vec2<f32>,
     @location(4) border_radius: f32,
     @location(5) pixel_position: vec2<f32>,
+    @location(6) world_position: vec2<f32>,
+    @location(7) clip_rect: vec4<f32>,
+    @location(8) clip_radius: vec4<f32>,
+    @location(9) clip_mode: f32,
 };
 
 @vertex
@@ -31,6 +35,9 @@ fn vertex(
     @location(1) vertex_color: vec4<f32>,
     @location(2) vertex_uv: vec4<f32>,
     @location(3) vertex_pos_size: vec4<f32>,
+    @location(4) vertex_clip_rect: vec4<f32>,
+    @location(5) vertex_clip_radius: vec4<f32>,
+    @location(6) vertex_clip_mode: f32,
 ) -> VertexOutput {
     var out: VertexOutput;
     out.color = vertex_color;
@@ -40,6 +47,10 @@ fn vertex(
     out.uv = vertex_uv.xyz;
     out.size = vertex_pos_size.zw;
     out.border_radius = vertex_uv.w;
+    out.world_position = vertex_position.xy;
+    out.clip_rect = vertex_clip_rect;
+    out.clip_radius = vertex_clip_radius;
+    out.clip_mode = vertex_clip_mode;
     return out;
 }
 
@@ -52,6 +63,10 @@ var font_sampler: sampler;
 var image_texture: texture_2d<f32>;
 @group(3) @binding(1)
 var image_sampler: sampler;
+@group(3) @binding(2)
+var clip_mask_texture: texture_2d<f32>;
+@group(3) @binding(3)
+var clip_mask_sampler: sampler;
 
 let RADIUS: f32 = 0.1;
 
@@ -74,8 +89,24 @@ fn range_curve(font_size: f32) -> f32 {
     return (8.528 - 9.428 * font_size + 3.428 * pow(font_size, 2.0)) + 1.0;
 }
 
-@fragment
-fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+// How much of the pixel is inside of the clip region the quad is drawn in.
+fn clip_coverage(in: VertexOutput) -> f32 {
+    var clip_size = in.clip_rect.zw - in.clip_rect.xy;
+    var half_size = clip_size * 0.5;
+    var p = in.world_position - (in.clip_rect.xy + half_size);
+    // The radius is stored as top left, top right, bottom right and bottom left.
+    var side_radius = select(in.clip_radius.xw, in.clip_radius.yz, p.x > 0.0);
+    var radius = select(side_radius.x, side_radius.y, p.y > 0.0);
+    radius = min(radius, min(half_size.x, half_size.y));
+    var dist = sdRoundBox(p, half_size, radius);
+    var coverage = 1.0 - smoothstep(0.0, fwidth(dist), dist);
+    var uv = (in.world_position - in.clip_rect.xy) / max(clip_size, vec2<f32>(1.0));
+    var mask = textureSampleLevel(clip_mask_texture, clip_mask_sampler, uv, 0.0).a;
+    coverage = select(coverage, coverage * mask, in.clip_mode > 1.5);
+    return select(1.0, coverage, in.clip_mode > 0.5);
+}
+
+fn shade(in: VertexOutput) -> vec4<f32> {
     if quad_type.t == 0 {
         var size = in.size;
         var pos = in.pos.xy * 2.0;
@@ -126,5 +157,28 @@ fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
         var color = textureSample(image_texture, image_sampler, vec2<f32>(in.uv.x, 1.0 - in.uv.y));
         return vec4<f32>(color.rgb * in.color.rgb, color.a * in.color.a * mask);
     }
+    if quad_type.t == 4 {
+        // The quad is grown by the blur radius, which is stored in the uvs.
+        var blur = in.uv.x * 2.0;
+        var size = in.size;
+        var pos = in.pos.xy * 2.0;
+        var inner_size = max(size - vec2<f32>(blur), vec2<f32>(0.0));
+        var bs = min(in.border_radius * 2.0, min(inner_size.x, inner_size.y));
+        var rect_dist = sdRoundBox(
+            pos - size,
+            inner_size,
+            bs,
+        );
+        var falloff = max(blur, fwidth(rect_dist));
+        var alpha = 1.0 - smoothstep(-falloff, falloff, rect_dist);
+        return vec4<f32>(in.color.rgb, alpha * in.color.a);
+    }
     return in.color;
 }
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var coverage = clip_coverage(in);
+    var color = shade(in);
+    return vec4<f32>(color.rgb, color.a * coverage);
+}
@@ -1,10 +1,14 @@
 use crate::{
     layout::Rect,
-    styles::{Corner, Edge, KStyle, RenderCommand},
+    styles::{
+        Corner, Edge, GradientKind, KStyle, RenderCommand, Repeat, StyleProp, TextDecoration,
+        TextDirection, TextWrap,
+    },
 };
 use bevy::{
     prelude::{Color, Handle, Image, Vec2},
     reflect::Reflect,
+    utils::FloatOrd,
 };
 use kayak_font::{TextLayout, TextProperties};
 
@@ -13,6 +17,15 @@ pub enum RenderPrimitive {
     Empty,
     Clip {
         layout: Rect,
+        /// The offset applied to the layout of all descendants inside this clip region
+        scroll: Option<(f32, f32)>,
+        /// The radius of each corner of the clip region
+        radius: Corner<f32>,
+    },
+    /// A [`RenderPrimitive::Clip`] that additionally masks its region by the alpha of a texture
+    ClipMask {
+        layout: Rect,
+        handle: Handle<Image>,
     },
     Quad {
         layout: Rect,
@@ -21,6 +34,28 @@ pub enum RenderPrimitive {
         border: Edge<f32>,
         border_radius: Corner<f32>,
     },
+    Gradient {
+        stops: Vec<(Color, f32)>,
+        kind: GradientKind,
+        layout: Rect,
+        border_color: Color,
+        border: Edge<f32>,
+        border_radius: Corner<f32>,
+    },
+    BoxShadow {
+        color: Color,
+        offset: (f32, f32),
+        blur: f32,
+        spread: f32,
+        border_radius: Corner<f32>,
+        layout: Rect,
+    },
+    /// Blurs and tints everything drawn before it within its layout
+    Backdrop {
+        blur: f32,
+        tint: Color,
+        layout: Rect,
+    },
     Text {
         color: Color,
         content: String,
@@ -30,22 +65,46 @@ pub enum RenderPrimitive {
         properties: TextProperties,
         word_wrap: bool,
         subpixel: bool,
+        /// The resolved direction of the text, either [`TextDirection::Ltr`] or [`TextDirection::Rtl`]
+        direction: TextDirection,
+        /// The lines drawn along with the text, which don't affect its measured size
+        decoration: TextDecoration,
+        /// The color and width of the outline drawn behind the glyphs, if any
+        outline: Option<(Color, f32)>,
+        /// Whether the outline is part of the measured size, in which case the glyphs are inset by it
+        outline_in_bounds: bool,
+        /// The scale of the transforms the text is drawn with, which its glyphs are scaled by
+        scale: Vec2,
     },
     Image {
         border_radius: Corner<f32>,
         layout: Rect,
         handle: Handle<Image>,
+        opacity: f32,
+        /// Whether the image is tiled across its layout, rather than stretched
+        repeat: Repeat,
     },
     TextureAtlas {
         size: Vec2,
         position: Vec2,
         layout: Rect,
         handle: Handle<Image>,
+        opacity: f32,
     },
     NinePatch {
         border: Edge<f32>,
         layout: Rect,
         handle: Handle<Image>,
+        opacity: f32,
+    },
+    /// Lines drawn between points, which are relative to the top-left corner of the layout
+    Path {
+        points: Vec<(f32, f32)>,
+        thickness: f32,
+        color: Color,
+        /// Whether the last point is joined back up with the first
+        closed: bool,
+        layout: Rect,
     },
 }
 
@@ -53,28 +112,337 @@ impl RenderPrimitive {
     pub fn set_layout(&mut self, new_layout: Rect) {
         match self {
             RenderPrimitive::Clip { layout, .. } => *layout = new_layout,
+            RenderPrimitive::ClipMask { layout, .. } => *layout = new_layout,
             RenderPrimitive::Quad { layout, .. } => *layout = new_layout,
+            RenderPrimitive::Gradient { layout, .. } => *layout = new_layout,
+            RenderPrimitive::BoxShadow { layout, .. } => *layout = new_layout,
+            RenderPrimitive::Backdrop { layout, .. } => *layout = new_layout,
             RenderPrimitive::Text { layout, .. } => *layout = new_layout,
             RenderPrimitive::Image { layout, .. } => *layout = new_layout,
             RenderPrimitive::NinePatch { layout, .. } => *layout = new_layout,
             RenderPrimitive::TextureAtlas { layout, .. } => *layout = new_layout,
+            RenderPrimitive::Path { layout, .. } => *layout = new_layout,
             _ => (),
         }
     }
 
+    /// Sets the scale of the transforms this primitive is drawn with
+    ///
+    /// Layouts are scaled by [`RenderPrimitive::set_layout`], this is for the content drawn
+    /// within them that isn't stretched to fit, such as the glyphs of text.
+    pub fn set_scale(&mut self, new_scale: Vec2) {
+        if let RenderPrimitive::Text { scale, .. } = self {
+            *scale = new_scale;
+        }
+    }
+
+    /// Returns the z-index this primitive is drawn at, if it draws anything
+    pub fn z_index(&self) -> Option<f32> {
+        match self {
+            RenderPrimitive::Clip { layout, .. }
+            | RenderPrimitive::ClipMask { layout, .. }
+            | RenderPrimitive::Quad { layout, .. }
+            | RenderPrimitive::Gradient { layout, .. }
+            | RenderPrimitive::BoxShadow { layout, .. }
+            | RenderPrimitive::Backdrop { layout, .. }
+            | RenderPrimitive::Text { layout, .. }
+            | RenderPrimitive::Image { layout, .. }
+            | RenderPrimitive::NinePatch { layout, .. }
+            | RenderPrimitive::TextureAtlas { layout, .. }
+            | RenderPrimitive::Path { layout, .. } => Some(layout.z_index),
+            RenderPrimitive::Empty => None,
+        }
+    }
+
+    /// Returns the key this primitive is drawn in order of, if it draws anything
+    ///
+    /// That's the z-base of the context that built the primitive, followed by its position in
+    /// the paint order of that context (see [`assign_paint_order`]).
+    pub fn sort_key(&self) -> Option<(FloatOrd, u32)> {
+        match self {
+            RenderPrimitive::Clip { layout, .. }
+            | RenderPrimitive::ClipMask { layout, .. }
+            | RenderPrimitive::Quad { layout, .. }
+            | RenderPrimitive::Gradient { layout, .. }
+            | RenderPrimitive::BoxShadow { layout, .. }
+            | RenderPrimitive::Backdrop { layout, .. }
+            | RenderPrimitive::Text { layout, .. }
+            | RenderPrimitive::Image { layout, .. }
+            | RenderPrimitive::NinePatch { layout, .. }
+            | RenderPrimitive::TextureAtlas { layout, .. }
+            | RenderPrimitive::Path { layout, .. } => {
+                Some((FloatOrd(layout.z_index), layout.paint_order))
+            }
+            RenderPrimitive::Empty => None,
+        }
+    }
+
+    /// Sorts primitives back into paint order, using their [`RenderPrimitive::sort_key`]
+    ///
+    /// Within a context, the order is an integer, so it stays unambiguous however large the
+    /// z-base is or however many primitives there are. [`RenderPrimitive::Empty`] draws nothing
+    /// and is sorted first.
+    pub fn sort(primitives: &mut [RenderPrimitive]) {
+        primitives.sort_by_key(|primitive| primitive.sort_key());
+    }
+
+    /// Checks if this primitive restricts the region its descendants are drawn in
+    pub fn is_clip(&self) -> bool {
+        matches!(
+            self,
+            RenderPrimitive::Clip { .. } | RenderPrimitive::ClipMask { .. }
+        )
+    }
+
+    /// Creates the [`RenderPrimitive::BoxShadow`] for the given styles, if they define a shadow
+    pub fn box_shadow(style: &KStyle, layout: Rect) -> Option<Self> {
+        if let StyleProp::Value(box_shadow) = style.box_shadow {
+            Some(Self::BoxShadow {
+                color: box_shadow.color,
+                offset: box_shadow.offset,
+                blur: box_shadow.blur,
+                spread: box_shadow.spread,
+                border_radius: style.border_radius.resolve_or_default(),
+                layout,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Creates the [`RenderPrimitive::Backdrop`] for the given styles, if they define a backdrop filter
+    pub fn backdrop(style: &KStyle, layout: Rect) -> Option<Self> {
+        if let StyleProp::Value(backdrop_filter) = style.backdrop_filter {
+            Some(Self::Backdrop {
+                blur: backdrop_filter.blur,
+                tint: backdrop_filter.tint,
+                layout,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Returns the distance from the top of a [`RenderPrimitive::Text`] to the baseline of its
+    /// first line
+    ///
+    /// Glyphs are drawn sitting on a baseline one font size below the top of each line.
+    pub fn baseline(&self) -> Option<f32> {
+        match self {
+            RenderPrimitive::Text { properties, .. } => Some(properties.font_size),
+            _ => None,
+        }
+    }
+
+    /// Returns the line segments drawn by a [`RenderPrimitive::Path`], in window coordinates
+    ///
+    /// A closed path ends with the segment joining its last point back up with its first.
+    pub fn path_segments(&self) -> Vec<((f32, f32), (f32, f32))> {
+        let (points, closed, layout) = match self {
+            RenderPrimitive::Path {
+                points,
+                closed,
+                layout,
+                ..
+            } => (points, *closed, layout),
+            _ => return Vec::new(),
+        };
+        let points = points
+            .iter()
+            .map(|(x, y)| (layout.posx + x, layout.posy + y))
+            .collect::<Vec<_>>();
+        let mut segments = points
+            .windows(2)
+            .map(|pair| (pair[0], pair[1]))
+            .collect::<Vec<_>>();
+        if closed && points.len() > 2 {
+            segments.push((points[points.len() - 1], points[0]));
+        }
+        segments
+    }
+
+    /// Rounds the corners of this primitive that sit in a rounded corner of the given clip
+    ///
+    /// The renderer already cuts content off at the rounded edge of the clip region, rounding the
+    /// primitive as well keeps its own border following that edge. Only corners that line up with
+    /// the corners of the clip region are rounded.
+    pub fn round_to_clip(&mut self, clip: &RenderPrimitive) {
+        let (clip_layout, clip_radius) = match clip {
+            RenderPrimitive::Clip { layout, radius, .. } => (layout, radius),
+            _ => return,
+        };
+        let (layout, border_radius) = match self {
+            RenderPrimitive::Quad {
+                layout,
+                border_radius,
+                ..
+            }
+            | RenderPrimitive::Gradient {
+                layout,
+                border_radius,
+                ..
+            }
+            | RenderPrimitive::Image {
+                layout,
+                border_radius,
+                ..
+            } => (*layout, border_radius),
+            _ => return,
+        };
+
+        let near = |a: f32, b: f32| (a - b).abs() < 0.5;
+        let (left, top) = (
+            near(layout.posx, clip_layout.posx),
+            near(layout.posy, clip_layout.posy),
+        );
+        let (right, bottom) = (
+            near(
+                layout.posx + layout.width,
+                clip_layout.posx + clip_layout.width,
+            ),
+            near(
+                layout.posy + layout.height,
+                clip_layout.posy + clip_layout.height,
+            ),
+        );
+        let corners = [
+            (
+                left && top,
+                &mut border_radius.top_left,
+                clip_radius.top_left,
+            ),
+            (
+                right && top,
+                &mut border_radius.top_right,
+                clip_radius.top_right,
+            ),
+            (
+                left && bottom,
+                &mut border_radius.bottom_left,
+                clip_radius.bottom_left,
+            ),
+            (
+                right && bottom,
+                &mut border_radius.bottom_right,
+                clip_radius.bottom_right,
+            ),
+        ];
+        for (in_corner, radius, clip_radius) in corners {
+            if in_corner {
+                *radius = radius.max(clip_radius);
+            }
+        }
+    }
+
+    /// Multiplies the alpha of everything drawn by this primitive by the given opacity
+    pub fn apply_opacity(&mut self, opacity: f32) {
+        if opacity >= 1.0 {
+            return;
+        }
+
+        let fade = |color: &mut Color| {
+            let alpha = color.a();
+            color.set_a(alpha * opacity);
+        };
+        match self {
+            RenderPrimitive::Quad {
+                background_color,
+                border_color,
+                ..
+            } => {
+                fade(background_color);
+                fade(border_color);
+            }
+            RenderPrimitive::Gradient {
+                stops,
+                border_color,
+                ..
+            } => {
+                stops.iter_mut().for_each(|(color, _)| fade(color));
+                fade(border_color);
+            }
+            RenderPrimitive::BoxShadow { color, .. } => fade(color),
+            RenderPrimitive::Backdrop { tint, .. } => fade(tint),
+            RenderPrimitive::Text { color, outline, .. } => {
+                fade(color);
+                if let Some((outline_color, _)) = outline {
+                    fade(outline_color);
+                }
+            }
+            RenderPrimitive::Path { color, .. } => fade(color),
+            RenderPrimitive::Image { opacity: alpha, .. }
+            | RenderPrimitive::TextureAtlas { opacity: alpha, .. }
+            | RenderPrimitive::NinePatch { opacity: alpha, .. } => *alpha *= opacity,
+            RenderPrimitive::Clip { .. }
+            | RenderPrimitive::ClipMask { .. }
+            | RenderPrimitive::Empty => (),
+        }
+    }
+
     pub fn to_string(&self) -> String {
         match self {
             RenderPrimitive::Clip { .. } => "Clip".into(),
+            RenderPrimitive::ClipMask { .. } => "ClipMask".into(),
             RenderPrimitive::Quad { .. } => "Quad".into(),
+            RenderPrimitive::Gradient { .. } => "Gradient".into(),
+            RenderPrimitive::BoxShadow { .. } => "BoxShadow".into(),
+            RenderPrimitive::Backdrop { .. } => "Backdrop".into(),
             RenderPrimitive::Text { .. } => "Text".into(),
             RenderPrimitive::Image { .. } => "Image".into(),
             RenderPrimitive::NinePatch { .. } => "NinePatch".into(),
             RenderPrimitive::TextureAtlas { .. } => "TextureAtlas".into(),
+            RenderPrimitive::Path { .. } => "Path".into(),
             RenderPrimitive::Empty { .. } => "Empty".into(),
         }
     }
 }
 
+/// Numbers every primitive by its position in paint order, and places them all at `z_base`
+///
+/// Primitives are emitted in the order they should be painted, so numbering them keeps clips
+/// and content in separate slots. Unlike depth-based z-indices, no two primitives can share a
+/// number however deep or wide the tree gets, and unlike floats added to the z-base, the
+/// numbers can't round into each other, which keeps their ordering unambiguous when sorted.
+pub(crate) fn assign_paint_order(primitives: &mut [RenderPrimitive], z_base: f32) {
+    for (order, primitive) in primitives.iter_mut().enumerate() {
+        match primitive {
+            RenderPrimitive::Clip { layout, .. }
+            | RenderPrimitive::ClipMask { layout, .. }
+            | RenderPrimitive::Quad { layout, .. }
+            | RenderPrimitive::Gradient { layout, .. }
+            | RenderPrimitive::BoxShadow { layout, .. }
+            | RenderPrimitive::Backdrop { layout, .. }
+            | RenderPrimitive::Text { layout, .. }
+            | RenderPrimitive::Image { layout, .. }
+            | RenderPrimitive::NinePatch { layout, .. }
+            | RenderPrimitive::TextureAtlas { layout, .. }
+            | RenderPrimitive::Path { layout, .. } => {
+                layout.z_index = z_base;
+                layout.paint_order = order as u32;
+            }
+            RenderPrimitive::Empty => {}
+        }
+    }
+}
+
+/// Removes clips that match the clip region that is already active
+///
+/// Clips are re-pushed between siblings to reset nested clip regions, which leaves many
+/// redundant copies in containers with lots of children.
+pub(crate) fn dedup_clips(primitives: &mut Vec<RenderPrimitive>) {
+    let mut active_clip: Option<RenderPrimitive> = None;
+    primitives.retain(|primitive| {
+        if !primitive.is_clip() {
+            return true;
+        }
+        if active_clip.as_ref() == Some(primitive) {
+            return false;
+        }
+        active_clip = Some(primitive.clone());
+        true
+    });
+}
+
 impl From<&KStyle> for RenderPrimitive {
     fn from(style: &KStyle) -> Self {
         let render_command = style.render_command.resolve();
@@ -91,47 +459,85 @@ impl From<&KStyle> for RenderPrimitive {
             .font
             .resolve_or_else(|| String::from(crate::DEFAULT_FONT));
 
-        let font_size = style.font_size.resolve_or(14.0);
+        let font_size = style.font_size.resolve_or(crate::DEFAULT_ROOT_FONT_SIZE);
 
-        let line_height = style.line_height.resolve_or(font_size * 1.2);
+        let line_height = style.line_height.resolve_or_default().resolve(font_size);
 
         match render_command {
             RenderCommand::Empty => Self::Empty,
             RenderCommand::Layout => Self::Empty,
             RenderCommand::Clip => Self::Clip {
                 layout: Rect::default(),
+                scroll: match style.scroll {
+                    StyleProp::Value(scroll) => Some(scroll),
+                    _ => None,
+                },
+                radius: style.border_radius.resolve_or_default(),
             },
-            RenderCommand::Quad => Self::Quad {
-                background_color,
-                border_color,
-                border_radius: style.border_radius.resolve(),
-                border: style.border.resolve(),
+            RenderCommand::ClipMask { handle } => Self::ClipMask {
                 layout: Rect::default(),
+                handle,
+            },
+            RenderCommand::Quad => match &style.background_gradient {
+                StyleProp::Value(gradient) => Self::Gradient {
+                    stops: gradient.stops.clone(),
+                    kind: gradient.kind,
+                    layout: Rect::default(),
+                    border_color,
+                    border: style.border.resolve(),
+                    border_radius: style.border_radius.resolve(),
+                },
+                _ => Self::Quad {
+                    background_color,
+                    border_color,
+                    border_radius: style.border_radius.resolve(),
+                    border: style.border.resolve(),
+                    layout: Rect::default(),
+                },
             },
             RenderCommand::Text {
                 content,
                 alignment,
                 word_wrap,
                 subpixel,
-            } => Self::Text {
-                color: style.color.resolve(),
-                content,
-                font,
-                text_layout: TextLayout::default(),
-                layout: Rect::default(),
-                properties: TextProperties {
-                    font_size,
-                    line_height,
-                    alignment,
-                    ..Default::default()
-                },
-                word_wrap,
-                subpixel,
-            },
+            } => {
+                let direction = style
+                    .text_direction
+                    .resolve_or_default()
+                    .resolve_for(&content);
+                Self::Text {
+                    color: style.color.resolve(),
+                    content,
+                    font,
+                    text_layout: TextLayout::default(),
+                    layout: Rect::default(),
+                    properties: TextProperties {
+                        font_size,
+                        line_height,
+                        alignment: style.text_alignment.resolve_or(direction.align(alignment)),
+                        ..Default::default()
+                    },
+                    word_wrap: match style.text_wrap {
+                        StyleProp::Value(text_wrap) => text_wrap == TextWrap::Wrap,
+                        _ => word_wrap,
+                    },
+                    subpixel,
+                    direction,
+                    decoration: style.text_decoration.resolve_or_default(),
+                    outline: match style.text_outline {
+                        StyleProp::Value(outline) => Some(outline),
+                        _ => None,
+                    },
+                    outline_in_bounds: style.text_outline_in_bounds.resolve_or(false),
+                    scale: Vec2::ONE,
+                }
+            }
             RenderCommand::Image { handle } => Self::Image {
                 border_radius: style.border_radius.resolve(),
                 layout: Rect::default(),
                 handle,
+                opacity: 1.0,
+                repeat: style.image_repeat.resolve(),
             },
             RenderCommand::TextureAtlas {
                 handle,
@@ -142,12 +548,280 @@ impl From<&KStyle> for RenderPrimitive {
                 layout: Rect::default(),
                 size,
                 position,
+                opacity: 1.0,
             },
             RenderCommand::NinePatch { handle, border } => Self::NinePatch {
                 border,
                 layout: Rect::default(),
                 handle,
+                opacity: 1.0,
+            },
+            RenderCommand::Path {
+                points,
+                thickness,
+                closed,
+            } => Self::Path {
+                points: points.iter().map(|point| (point.x, point.y)).collect(),
+                thickness,
+                color: style.color.resolve_or(Color::BLACK),
+                closed,
+                layout: Rect::default(),
             },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::{Color, Handle, Image, Vec2};
+    use kayak_font::Alignment;
+
+    use super::{dedup_clips, RenderPrimitive};
+    use crate::{
+        layout::Rect,
+        styles::{Corner, KStyle, RenderCommand, Repeat, StyleProp, TextDecoration, TextDirection},
+    };
+
+    #[test]
+    fn text_alignment_should_override_render_command() {
+        let style = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Label"),
+                alignment: Alignment::Start,
+                word_wrap: true,
+                subpixel: false,
+            }),
+            text_alignment: StyleProp::Value(Alignment::Middle),
+            ..Default::default()
+        };
+
+        match RenderPrimitive::from(&style) {
+            RenderPrimitive::Text { properties, .. } => {
+                assert_eq!(Alignment::Middle, properties.alignment)
+            }
+            primitive => panic!("Expected a text primitive, got: {}", primitive.to_string()),
+        }
+    }
+
+    #[test]
+    fn text_decoration_should_survive_into_primitive() {
+        let style = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Link"),
+                alignment: Alignment::Start,
+                word_wrap: true,
+                subpixel: false,
+            }),
+            text_decoration: StyleProp::Value(TextDecoration::Underline),
+            ..Default::default()
+        };
+
+        match RenderPrimitive::from(&style) {
+            RenderPrimitive::Text { decoration, .. } => {
+                assert_eq!(TextDecoration::Underline, decoration)
+            }
+            primitive => panic!("Expected a text primitive, got: {}", primitive.to_string()),
+        }
+    }
+
+    #[test]
+    fn text_outline_should_survive_into_primitive() {
+        let text = |text_outline| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Score"),
+                alignment: Alignment::Start,
+                word_wrap: true,
+                subpixel: false,
+            }),
+            text_outline,
+            ..Default::default()
+        };
+        let outline = |style: &KStyle| match RenderPrimitive::from(style) {
+            RenderPrimitive::Text { outline, .. } => outline,
+            primitive => panic!("Expected a text primitive, got: {}", primitive.to_string()),
+        };
+
+        assert_eq!(
+            Some((Color::BLACK, 2.0)),
+            outline(&text(StyleProp::Value((Color::BLACK, 2.0))))
+        );
+        assert_eq!(None, outline(&text(StyleProp::Default)));
+    }
+
+    #[test]
+    fn clip_mask_should_carry_its_texture() {
+        let handle = Handle::<Image>::default();
+        let style = KStyle {
+            render_command: StyleProp::Value(RenderCommand::ClipMask {
+                handle: handle.clone(),
+            }),
+            ..Default::default()
+        };
+
+        let primitive = RenderPrimitive::from(&style);
+        assert!(primitive.is_clip());
+        match primitive {
+            RenderPrimitive::ClipMask { handle: mask, .. } => assert_eq!(handle, mask),
+            primitive => panic!("Expected a clip mask, got: {}", primitive.to_string()),
+        }
+    }
+
+    #[test]
+    fn path_should_join_its_points() {
+        let path = |points: Vec<Vec2>, closed| {
+            let style = KStyle {
+                render_command: StyleProp::Value(RenderCommand::Path {
+                    points,
+                    thickness: 1.0,
+                    closed,
+                }),
+                color: StyleProp::Value(Color::GRAY),
+                ..Default::default()
+            };
+            let mut primitive = RenderPrimitive::from(&style);
+            primitive.set_layout(Rect {
+                posx: 10.0,
+                posy: 20.0,
+                width: 100.0,
+                height: 100.0,
+                z_index: 0.0,
+                paint_order: 0,
+            });
+            primitive
+        };
+
+        // A horizontal divider across the middle of the layout
+        let divider = path(vec![Vec2::new(0.0, 50.0), Vec2::new(100.0, 50.0)], false);
+        match &divider {
+            RenderPrimitive::Path { color, .. } => assert_eq!(Color::GRAY, *color),
+            primitive => panic!("Expected a path, got: {}", primitive.to_string()),
+        }
+        assert_eq!(vec![((10.0, 70.0), (110.0, 70.0))], divider.path_segments());
+
+        let triangle = path(
+            vec![
+                Vec2::new(50.0, 0.0),
+                Vec2::new(100.0, 100.0),
+                Vec2::new(0.0, 100.0),
+            ],
+            true,
+        );
+        assert_eq!(
+            vec![
+                ((60.0, 20.0), (110.0, 120.0)),
+                ((110.0, 120.0), (10.0, 120.0)),
+                ((10.0, 120.0), (60.0, 20.0)),
+            ],
+            triangle.path_segments()
+        );
+    }
+
+    #[test]
+    fn dedup_clips_should_keep_nested_resets() {
+        let clip = |width: f32| RenderPrimitive::Clip {
+            layout: Rect {
+                width,
+                height: 100.0,
+                ..Default::default()
+            },
+            scroll: None,
+            radius: Corner::default(),
+        };
+
+        let mut primitives = vec![
+            clip(100.0),
+            RenderPrimitive::Empty,
+            clip(100.0),
+            RenderPrimitive::Empty,
+            clip(50.0),
+            RenderPrimitive::Empty,
+            clip(100.0),
+            clip(100.0),
+        ];
+        dedup_clips(&mut primitives);
+
+        assert_eq!(
+            vec![
+                clip(100.0),
+                RenderPrimitive::Empty,
+                RenderPrimitive::Empty,
+                clip(50.0),
+                RenderPrimitive::Empty,
+                clip(100.0),
+            ],
+            primitives
+        );
+    }
+
+    #[test]
+    fn rtl_text_should_align_right_by_default() {
+        let text = |content: &str, direction, text_alignment| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from(content),
+                alignment: Alignment::Start,
+                word_wrap: true,
+                subpixel: false,
+            }),
+            text_direction: StyleProp::Value(direction),
+            text_alignment,
+            ..Default::default()
+        };
+        let resolve = |style: &KStyle| match RenderPrimitive::from(style) {
+            RenderPrimitive::Text {
+                properties,
+                direction,
+                ..
+            } => (properties.alignment, direction),
+            primitive => panic!("Expected a text primitive, got: {}", primitive.to_string()),
+        };
+
+        assert_eq!(
+            (Alignment::End, TextDirection::Rtl),
+            resolve(&text("Label", TextDirection::Rtl, StyleProp::Default))
+        );
+        assert_eq!(
+            (Alignment::Start, TextDirection::Ltr),
+            resolve(&text("Label", TextDirection::Auto, StyleProp::Default))
+        );
+        assert_eq!(
+            (Alignment::End, TextDirection::Rtl),
+            resolve(&text("שלום", TextDirection::Auto, StyleProp::Default))
+        );
+
+        // Explicit alignments aren't mirrored
+        assert_eq!(
+            (Alignment::Start, TextDirection::Rtl),
+            resolve(&text(
+                "Label",
+                TextDirection::Rtl,
+                StyleProp::Value(Alignment::Start)
+            ))
+        );
+    }
+
+    #[test]
+    fn image_repeat_should_propagate_to_primitive() {
+        let image = |image_repeat| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Image {
+                handle: Handle::<Image>::default(),
+            }),
+            image_repeat,
+            ..Default::default()
+        };
+
+        let repeat = |primitive: RenderPrimitive| match primitive {
+            RenderPrimitive::Image { repeat, .. } => repeat,
+            _ => panic!("Expected an image primitive"),
+        };
+        assert_eq!(
+            Repeat::RepeatX,
+            repeat(RenderPrimitive::from(&image(StyleProp::Value(
+                Repeat::RepeatX
+            ))))
+        );
+        assert_eq!(
+            Repeat::NoRepeat,
+            repeat(RenderPrimitive::from(&image(StyleProp::Default)))
+        );
+    }
+}
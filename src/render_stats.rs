@@ -0,0 +1,60 @@
+/// Statistics about the work done to render a UI in the last frame
+///
+/// These can be retrieved using [`KayakRootContext::last_stats`](crate::prelude::KayakRootContext::last_stats)
+/// and are useful for finding unexpected re-renders of the entire tree.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+    /// The number of nodes that were marked dirty, to have their styles and primitives recalculated
+    pub dirty_render_count: usize,
+    /// The number of nodes that were rebuilt
+    pub nodes_rebuilt: usize,
+    /// The number of render primitives emitted for rendering
+    pub primitives_emitted: usize,
+    /// The number of nodes in the layout
+    pub layout_nodes: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Color;
+
+    use crate::{
+        render_primitive::RenderPrimitive,
+        styles::{KStyle, RenderCommand, StyleProp, Units},
+        test_utils::TreeBuilder,
+    };
+
+    #[test]
+    fn stats_should_count_nodes_and_primitives() {
+        let quad = |width, height| KStyle {
+            width: StyleProp::Value(Units::Pixels(width)),
+            height: StyleProp::Value(Units::Pixels(height)),
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            background_color: StyleProp::Value(Color::WHITE),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", quad(800.0, 600.0))
+            .child("panel", quad(200.0, 100.0), |panel| {
+                panel.leaf("field", quad(50.0, 20.0))
+            })
+            .leaf("footer", quad(800.0, 40.0))
+            .build();
+
+        tree.calculate_nodes();
+        tree.layout();
+        let primitives = tree.render_primitives();
+
+        let stats = tree.context.last_stats();
+        assert_eq!(4, stats.nodes_rebuilt);
+        assert_eq!(4, stats.layout_nodes);
+        assert_eq!(primitives.len(), stats.primitives_emitted);
+        assert_eq!(
+            4,
+            primitives
+                .iter()
+                .filter(|primitive| matches!(primitive, RenderPrimitive::Quad { .. }))
+                .count()
+        );
+    }
+}
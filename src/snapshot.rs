@@ -0,0 +1,140 @@
+/// A serializable snapshot of a widget tree and its layout
+///
+/// Created using [`KayakRootContext::dump_tree`](crate::prelude::KayakRootContext::dump_tree).
+/// Comparing a snapshot against a stored one allows layout regressions to be caught
+/// without rendering anything. Snapshots can be serialized by enabling the `serde` feature.
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TreeSnapshot {
+    /// The nodes of the tree in depth-first traversal order
+    pub nodes: Vec<NodeSnapshot>,
+}
+
+/// A serializable snapshot of a single node in a [`TreeSnapshot`]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct NodeSnapshot {
+    /// The index of the widget's entity
+    pub index: u32,
+    /// The name of the widget
+    pub name: String,
+    /// The depth of the node within the tree, where the root is `0`
+    pub depth: usize,
+    /// The name of the node's resolved render primitive
+    pub render_command: String,
+    pub posx: f32,
+    pub posy: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// Asserts that the snapshot matches the one stored as JSON at the given path
+///
+/// A missing snapshot fails the assertion. Run the tests with `UPDATE_SNAPSHOTS=1` to write
+/// the given snapshot to the path instead, replacing any stored one.
+#[cfg(test)]
+pub(crate) fn assert_matches_snapshot(snapshot: &TreeSnapshot, path: impl AsRef<std::path::Path>) {
+    let path = path.as_ref();
+    if std::env::var("UPDATE_SNAPSHOTS").map_or(false, |update| update == "1") {
+        let json = serde_json::to_string_pretty(snapshot).expect("Failed to serialize snapshot");
+        std::fs::write(path, json).expect("Failed to write snapshot");
+        return;
+    }
+
+    let stored = std::fs::read_to_string(path).unwrap_or_else(|_| {
+        panic!(
+            "No snapshot is stored at {:?}, run with UPDATE_SNAPSHOTS=1 to create it",
+            path
+        )
+    });
+    let stored: TreeSnapshot =
+        serde_json::from_str(&stored).expect("Failed to parse stored snapshot");
+    assert_eq!(
+        &stored, snapshot,
+        "Tree does not match the snapshot stored at {:?}",
+        path
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assert_matches_snapshot, NodeSnapshot, TreeSnapshot};
+    use crate::{
+        styles::{KStyle, RenderCommand, StyleProp, Units},
+        test_utils::TreeBuilder,
+    };
+
+    fn sized(width: f32, height: f32) -> KStyle {
+        KStyle {
+            width: StyleProp::Value(Units::Pixels(width)),
+            height: StyleProp::Value(Units::Pixels(height)),
+            ..KStyle::initial()
+        }
+    }
+
+    #[test]
+    fn snapshot_should_round_trip() {
+        let snapshot = TreeSnapshot {
+            nodes: vec![NodeSnapshot {
+                index: 0,
+                name: String::from("KayakAppBundle"),
+                depth: 0,
+                render_command: String::from("Empty"),
+                posx: 0.0,
+                posy: 0.0,
+                width: 1280.0,
+                height: 720.0,
+            }],
+        };
+
+        let path = std::env::temp_dir().join(format!(
+            "kayak_snapshot_round_trip_{}.json",
+            std::process::id()
+        ));
+        let json = serde_json::to_string_pretty(&snapshot).expect("Failed to serialize snapshot");
+        std::fs::write(&path, json).expect("Failed to write snapshot");
+
+        assert_matches_snapshot(&snapshot, &path);
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    #[should_panic(expected = "No snapshot is stored")]
+    fn missing_snapshot_should_fail() {
+        let path = std::env::temp_dir().join(format!(
+            "kayak_snapshot_missing_{}.json",
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&path);
+
+        assert_matches_snapshot(&TreeSnapshot::default(), &path);
+    }
+
+    #[test]
+    fn built_tree_should_match_snapshot() {
+        let quad = |width, height| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            ..sized(width, height)
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(800.0, 600.0))
+            .child("panel", quad(200.0, 100.0), |panel| {
+                panel.leaf("label", sized(50.0, 20.0))
+            })
+            .leaf("footer", quad(800.0, 40.0))
+            .build();
+        tree.layout();
+
+        assert_matches_snapshot(
+            &tree.dump_tree(),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/src/snapshots/built_tree.json"),
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use bevy::reflect::{FromReflect, Reflect};
+
+/// Controls how the children of a row are positioned vertically
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq, Eq)]
+pub enum AlignItems {
+    /// Each child is positioned by its own spacing
+    Normal,
+    /// Children containing text are moved down so that the baselines of their first lines
+    /// line up, such as a large number next to a small label
+    ///
+    /// Children without any text are positioned by their own spacing. Only applies to widgets
+    /// with a [`LayoutType::Row`](super::LayoutType::Row) layout.
+    Baseline,
+}
+
+impl Default for AlignItems {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
@@ -0,0 +1,22 @@
+use bevy::{
+    prelude::Color,
+    reflect::{FromReflect, Reflect},
+};
+
+/// A blur applied to whatever is drawn behind a widget, such as for frosted glass
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq)]
+pub struct BackdropFilter {
+    /// The blur radius (in pixels)
+    pub blur: f32,
+    /// The color blended over the blurred backdrop
+    pub tint: Color,
+}
+
+impl Default for BackdropFilter {
+    fn default() -> Self {
+        Self {
+            blur: 0.0,
+            tint: Color::rgba(1.0, 1.0, 1.0, 0.0),
+        }
+    }
+}
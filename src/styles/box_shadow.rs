@@ -0,0 +1,30 @@
+use bevy::{
+    prelude::Color,
+    reflect::{FromReflect, Reflect},
+};
+
+/// A drop shadow drawn behind a widget
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq)]
+pub struct BoxShadow {
+    /// The color of the shadow
+    pub color: Color,
+    /// The offset of the shadow from the widget (in pixels)
+    pub offset: (f32, f32),
+    /// The blur radius of the shadow (in pixels)
+    ///
+    /// A value of `0.0` results in a shadow with hard edges.
+    pub blur: f32,
+    /// How far the shadow extends past the edges of the widget before blurring (in pixels)
+    pub spread: f32,
+}
+
+impl Default for BoxShadow {
+    fn default() -> Self {
+        Self {
+            color: Color::rgba(0.0, 0.0, 0.0, 0.5),
+            offset: (0.0, 0.0),
+            blur: 0.0,
+            spread: 0.0,
+        }
+    }
+}
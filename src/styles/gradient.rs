@@ -0,0 +1,157 @@
+use bevy::{
+    prelude::{Color, Vec2},
+    reflect::{FromReflect, Reflect},
+};
+
+/// The shape of a [`Gradient`]
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq)]
+pub enum GradientKind {
+    /// A gradient that blends along a straight line
+    ///
+    /// The angle is given in degrees and follows the CSS convention: `0.0` blends from the
+    /// bottom edge to the top edge, `90.0` from left to right, and `180.0` from top to bottom.
+    Linear { angle: f32 },
+    /// A gradient that blends outwards from the center of the widget to its corners
+    Radial,
+}
+
+impl Default for GradientKind {
+    fn default() -> Self {
+        Self::Linear { angle: 180.0 }
+    }
+}
+
+/// A color gradient used to fill the background of a widget
+///
+/// # Example
+///
+/// ```
+/// # use bevy::prelude::Color;
+/// # use kayak_ui::prelude::{Gradient, GradientKind};
+/// // A subtle top-to-bottom gradient
+/// let gradient = Gradient::new(
+///     GradientKind::Linear { angle: 180.0 },
+///     vec![(Color::rgb(0.3, 0.3, 0.35), 0.0), (Color::rgb(0.2, 0.2, 0.25), 1.0)],
+/// );
+/// ```
+#[derive(Debug, Default, Reflect, FromReflect, Clone, PartialEq)]
+pub struct Gradient {
+    /// The color stops of this gradient
+    ///
+    /// Each stop is a color paired with its offset along the gradient, from `0.0` to `1.0`.
+    pub stops: Vec<(Color, f32)>,
+    /// The shape of this gradient
+    pub kind: GradientKind,
+}
+
+impl Gradient {
+    /// Creates a new `Gradient`, sorting the given stops by their offset
+    pub fn new(kind: GradientKind, mut stops: Vec<(Color, f32)>) -> Self {
+        stops.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        Self { stops, kind }
+    }
+
+    /// Samples the color of the gradient at the given offset
+    ///
+    /// Offsets before the first stop or after the last stop are clamped to those stops' colors.
+    pub fn color_at(stops: &[(Color, f32)], offset: f32) -> Color {
+        let (first, last) = match (stops.first(), stops.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return Color::rgba(1.0, 1.0, 1.0, 0.0),
+        };
+
+        if offset <= first.1 {
+            return first.0;
+        }
+        if offset >= last.1 {
+            return last.0;
+        }
+
+        for window in stops.windows(2) {
+            let (start, end) = (window[0], window[1]);
+            if offset >= start.1 && offset <= end.1 {
+                let range = end.1 - start.1;
+                let t = if range <= f32::EPSILON {
+                    0.0
+                } else {
+                    (offset - start.1) / range
+                };
+                let a = start.0.as_rgba_f32();
+                let b = end.0.as_rgba_f32();
+                return Color::rgba(
+                    a[0] + (b[0] - a[0]) * t,
+                    a[1] + (b[1] - a[1]) * t,
+                    a[2] + (b[2] - a[2]) * t,
+                    a[3] + (b[3] - a[3]) * t,
+                );
+            }
+        }
+
+        last.0
+    }
+
+    /// Computes the offset along the gradient for a point relative to a box of the given size
+    ///
+    /// The point is in the same coordinate space as the size, with the origin at the top-left corner.
+    pub fn offset_at(kind: GradientKind, point: Vec2, size: Vec2) -> f32 {
+        let half_size = size * 0.5;
+        let relative = point - half_size;
+        match kind {
+            GradientKind::Linear { angle } => {
+                let radians = angle.to_radians();
+                let direction = Vec2::new(radians.sin(), -radians.cos());
+                // Half the length of the gradient line, which spans the box from corner to corner
+                let half_length =
+                    (half_size.x * direction.x).abs() + (half_size.y * direction.y).abs();
+                if half_length <= f32::EPSILON {
+                    return 0.0;
+                }
+                relative.dot(direction) / (half_length * 2.0) + 0.5
+            }
+            GradientKind::Radial => {
+                if half_size.x <= f32::EPSILON || half_size.y <= f32::EPSILON {
+                    return 0.0;
+                }
+                (relative / half_size).length() / std::f32::consts::SQRT_2
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Gradient, GradientKind};
+    use bevy::prelude::{Color, Vec2};
+
+    #[test]
+    fn gradient_should_clamp_outside_stops() {
+        let stops = vec![(Color::BLACK, 0.25), (Color::WHITE, 0.75)];
+        assert_eq!(Color::BLACK, Gradient::color_at(&stops, 0.0));
+        assert_eq!(Color::WHITE, Gradient::color_at(&stops, 1.0));
+    }
+
+    #[test]
+    fn gradient_should_interpolate_between_stops() {
+        let stops = vec![(Color::rgba(0.0, 0.0, 0.0, 0.0), 0.0), (Color::WHITE, 1.0)];
+        let color = Gradient::color_at(&stops, 0.5);
+        assert_eq!([0.5, 0.5, 0.5, 0.5], color.as_rgba_f32());
+    }
+
+    #[test]
+    fn linear_gradient_should_run_top_to_bottom() {
+        let kind = GradientKind::Linear { angle: 180.0 };
+        let size = Vec2::new(100.0, 50.0);
+        assert!(Gradient::offset_at(kind, Vec2::new(50.0, 0.0), size).abs() < 0.001);
+        assert!((Gradient::offset_at(kind, Vec2::new(50.0, 50.0), size) - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn radial_gradient_should_start_at_center() {
+        let size = Vec2::new(100.0, 50.0);
+        assert_eq!(
+            0.0,
+            Gradient::offset_at(GradientKind::Radial, Vec2::new(50.0, 25.0), size)
+        );
+        assert!((Gradient::offset_at(GradientKind::Radial, Vec2::ZERO, size) - 1.0).abs() < 0.001);
+    }
+}
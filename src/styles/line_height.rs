@@ -0,0 +1,29 @@
+use bevy::reflect::{FromReflect, Reflect};
+
+/// The height of each line of text
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq)]
+pub enum LineHeight {
+    /// 1.2 times the font size
+    Normal,
+    /// A multiple of the font size, like a unitless line height in CSS
+    Multiple(f32),
+    /// A fixed height in pixels
+    Pixels(f32),
+}
+
+impl Default for LineHeight {
+    fn default() -> Self {
+        Self::Normal
+    }
+}
+
+impl LineHeight {
+    /// Resolves this line height into pixels for the given font size
+    pub fn resolve(&self, font_size: f32) -> f32 {
+        match self {
+            LineHeight::Normal => font_size * 1.2,
+            LineHeight::Multiple(multiple) => font_size * multiple,
+            LineHeight::Pixels(pixels) => *pixels,
+        }
+    }
+}
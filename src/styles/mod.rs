@@ -1,18 +1,47 @@
 use bevy::{prelude::Component, reflect::Reflect};
 
+mod align_items;
+mod backdrop_filter;
+mod box_shadow;
 mod corner;
 mod edge;
+mod gradient;
+mod line_height;
 mod options_ref;
+mod pseudo_styles;
 mod render_command;
+mod repeat;
+mod role;
 mod style;
+mod text_decoration;
+mod text_direction;
+mod text_wrap;
+mod transform;
+mod transition;
 mod units;
+mod validation;
 
+pub use align_items::AlignItems;
+pub use backdrop_filter::BackdropFilter;
+pub use box_shadow::BoxShadow;
 pub use corner::Corner;
 pub use edge::Edge;
+pub use gradient::{Gradient, GradientKind};
+pub use line_height::LineHeight;
 pub use options_ref::AsRefOption;
+pub use pseudo_styles::{PseudoState, PseudoStyles};
 pub use render_command::RenderCommand;
+pub use repeat::Repeat;
+pub use role::Role;
 pub use style::*;
+pub use text_decoration::TextDecoration;
+pub use text_direction::TextDirection;
+pub use text_wrap::TextWrap;
+pub use transform::KTransform;
+pub(crate) use transition::ActiveTransition;
+pub use transition::{Transition, TransitionEasing, TransitionProperty};
 pub use units::*;
+pub use validation::StyleWarning;
 
 #[derive(Component, Reflect, Debug, Default, Clone, PartialEq)]
 pub struct ComputedStyles(pub KStyle);
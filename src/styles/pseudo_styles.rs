@@ -0,0 +1,96 @@
+use bevy::{
+    prelude::{Component, ReflectComponent},
+    reflect::Reflect,
+};
+
+use super::KStyle;
+
+/// The interaction states of a widget that can change its styles
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PseudoState {
+    /// The pointer is over the widget or one of its descendants
+    pub hovered: bool,
+    /// The widget has focus
+    pub focused: bool,
+    /// The widget is hovered while the pointer is pressed
+    pub active: bool,
+}
+
+/// Alternate styles that are layered over a widget's styles while it's in a given state
+///
+/// Only the properties that are set in a state's styles replace the widget's own, so a hover
+/// style can change just the background color. When several states apply, `active` takes
+/// precedence over `focus`, which takes precedence over `hover`.
+#[derive(Component, Reflect, Debug, Default, Clone, PartialEq)]
+#[reflect(Component)]
+pub struct PseudoStyles {
+    /// Applied while the pointer is over the widget or one of its descendants
+    pub hover: Option<KStyle>,
+    /// Applied while the widget has focus
+    pub focus: Option<KStyle>,
+    /// Applied while the widget is hovered and the pointer is pressed
+    pub active: Option<KStyle>,
+}
+
+impl PseudoStyles {
+    /// Layers the styles of every state in `state` over the given styles
+    pub fn layer(&self, styles: &KStyle, state: PseudoState) -> KStyle {
+        let mut layered = styles.clone();
+        for (enabled, state_styles) in [
+            (state.hovered, &self.hover),
+            (state.focused, &self.focus),
+            (state.active, &self.active),
+        ] {
+            if let (true, Some(state_styles)) = (enabled, state_styles) {
+                layered = state_styles.clone().with_style(&layered);
+            }
+        }
+        layered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Color;
+
+    use super::{PseudoState, PseudoStyles};
+    use crate::styles::{KStyle, StyleProp, Units};
+
+    #[test]
+    fn state_styles_should_only_override_set_properties() {
+        let styles = KStyle {
+            background_color: StyleProp::Value(Color::BLUE),
+            width: StyleProp::Value(Units::Pixels(100.0)),
+            ..Default::default()
+        };
+        let pseudo_styles = PseudoStyles {
+            hover: Some(KStyle {
+                background_color: StyleProp::Value(Color::RED),
+                ..Default::default()
+            }),
+            active: Some(KStyle {
+                background_color: StyleProp::Value(Color::GREEN),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let idle = pseudo_styles.layer(&styles, PseudoState::default());
+        assert_eq!(styles, idle);
+
+        let hovered = PseudoState {
+            hovered: true,
+            ..Default::default()
+        };
+        let layered = pseudo_styles.layer(&styles, hovered);
+        assert_eq!(StyleProp::Value(Color::RED), layered.background_color);
+        assert_eq!(styles.width, layered.width);
+
+        let active = PseudoState {
+            active: true,
+            ..hovered
+        };
+        let layered = pseudo_styles.layer(&styles, active);
+        assert_eq!(StyleProp::Value(Color::GREEN), layered.background_color);
+    }
+}
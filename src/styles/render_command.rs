@@ -12,6 +12,10 @@ pub enum RenderCommand {
     /// Represents a node that has no renderable object but contributes to the layout.
     Layout,
     Clip,
+    /// Clips descendants to the alpha of the given texture, stretched over the node's layout.
+    ClipMask {
+        handle: Handle<Image>,
+    },
     Quad,
     Text {
         content: String,
@@ -31,6 +35,16 @@ pub enum RenderCommand {
         border: Edge<f32>,
         handle: Handle<Image>,
     },
+    /// Draws lines between the given points, such as a divider or a simple chart.
+    ///
+    /// Points are relative to the top-left corner of the node's layout, and the lines are drawn
+    /// in the node's `color`. Paths don't contribute a size to the layout.
+    Path {
+        points: Vec<Vec2>,
+        thickness: f32,
+        /// Whether the last point is joined back up with the first
+        closed: bool,
+    },
 }
 
 impl Default for RenderCommand {
@@ -0,0 +1,32 @@
+use bevy::reflect::{FromReflect, Reflect};
+
+/// How an image fills a widget that is larger than the image
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq, Eq)]
+pub enum Repeat {
+    /// The image is stretched over the whole widget
+    NoRepeat,
+    /// The image is tiled horizontally and stretched vertically
+    RepeatX,
+    /// The image is tiled vertically and stretched horizontally
+    RepeatY,
+    /// The image is tiled in both directions
+    Repeat,
+}
+
+impl Default for Repeat {
+    fn default() -> Self {
+        Self::NoRepeat
+    }
+}
+
+impl Repeat {
+    /// Whether the image is tiled along the horizontal axis
+    pub fn repeats_x(self) -> bool {
+        matches!(self, Self::RepeatX | Self::Repeat)
+    }
+
+    /// Whether the image is tiled along the vertical axis
+    pub fn repeats_y(self) -> bool {
+        matches!(self, Self::RepeatY | Self::Repeat)
+    }
+}
@@ -0,0 +1,54 @@
+use bevy::reflect::{FromReflect, Reflect};
+
+use super::RenderCommand;
+
+/// The purpose of a widget, as reported to assistive technologies such as screen readers
+///
+/// When a widget doesn't set a role, one is derived from its render command.
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq, Eq)]
+pub enum Role {
+    /// A container used to group other widgets
+    Group,
+    /// A run of static text
+    Text,
+    /// A picture
+    Image,
+    /// A widget that performs an action when clicked
+    Button,
+    /// A widget that can be toggled on or off
+    CheckBox,
+    /// A field for entering text
+    TextInput,
+    /// A scrollable region
+    ScrollView,
+    /// A top level window
+    Window,
+    /// A widget that is purely presentational
+    ///
+    /// It's left out of the accessibility tree and its children take its place.
+    Presentation,
+}
+
+impl Default for Role {
+    fn default() -> Self {
+        Self::Group
+    }
+}
+
+impl Role {
+    /// Returns the role implied by the given render command
+    pub fn from_render_command(render_command: &RenderCommand) -> Self {
+        match render_command {
+            RenderCommand::Empty => Self::Presentation,
+            RenderCommand::Text { .. } => Self::Text,
+            RenderCommand::Image { .. }
+            | RenderCommand::TextureAtlas { .. }
+            | RenderCommand::NinePatch { .. }
+            | RenderCommand::Path { .. } => Self::Image,
+            RenderCommand::Layout
+            | RenderCommand::Clip
+            | RenderCommand::ClipMask { .. }
+            | RenderCommand::Quad => Self::Group,
+        }
+    }
+}
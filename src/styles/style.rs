@@ -2,20 +2,32 @@
 
 use std::ops::Add;
 
-pub use super::units::{KPositionType, LayoutType, Units};
+pub use super::units::{KPositionType, LayoutType, Overflow, Units};
 use bevy::prelude::Color;
 use bevy::prelude::Component;
 use bevy::prelude::ReflectComponent;
 use bevy::reflect::FromReflect;
 use bevy::reflect::Reflect;
 use bevy::window::CursorIcon;
+use kayak_font::Alignment;
 
 use crate::cursor::PointerEvents;
 
+pub use super::AlignItems;
 use super::AsRefOption;
+pub use super::BoxShadow;
 pub use super::Corner;
 pub use super::Edge;
+pub use super::Gradient;
+use super::KTransform;
+pub use super::LineHeight;
 use super::RenderCommand;
+pub use super::Repeat;
+pub use super::Role;
+pub use super::TextDecoration;
+pub use super::TextDirection;
+pub use super::TextWrap;
+use super::Transition;
 
 /// Just a wrapper around bevy's CursorIcon so we can define a default.
 #[derive(Debug, Reflect, Clone, PartialEq, Eq)]
@@ -49,6 +61,13 @@ pub enum StyleProp<T: Default + Clone + Reflect + FromReflect> {
     Default,
     /// Properties of this type inherit their value from their parent (determined at style resolution).
     Inherit,
+    /// Properties of this type take their value from the context's default styles (determined at
+    /// style resolution), ignoring whatever the parent has set.
+    ///
+    /// This breaks the inheritance chain for a property that would otherwise be inherited, such as
+    /// resetting the text color inside a widget with red text. If the default styles don't set
+    /// the property, it resolves like [`StyleProp::Default`].
+    Initial,
     /// Set a specific value for this property
     Value(T),
 }
@@ -76,6 +95,7 @@ where
         match self {
             StyleProp::Unset => T::default(),
             StyleProp::Default => T::default(),
+            StyleProp::Initial => T::default(),
             StyleProp::Value(value) => value.clone(),
             StyleProp::Inherit => panic!("All styles should be merged before resolving!"),
         }
@@ -137,6 +157,40 @@ impl<T: Default + Clone + Reflect + FromReflect> From<T> for StyleProp<T> {
     }
 }
 
+/// The properties that are inherited from the parent widget unless they are set, much like CSS
+///
+/// Each of these starts out as [`StyleProp::Inherit`] in [`KStyle::initial`], so a value set on
+/// a widget flows down to every descendant that doesn't set its own. Add a property's field
+/// name here to make it inherit by default.
+pub const INHERITED_PROPERTIES: &[&str] = &[
+    "color",
+    "cursor",
+    "font",
+    "font_fallback",
+    "font_size",
+    "line_height",
+    "text_alignment",
+    "text_direction",
+];
+
+/// The properties that only change how a widget is drawn, never its layout
+///
+/// Changing any other property lays the widget out again (see [`KStyle::layout_differs`]), so a
+/// property should only be added here if it can't move or resize anything.
+pub const PAINT_ONLY_PROPERTIES: &[&str] = &[
+    "backdrop_filter",
+    "background_color",
+    "background_gradient",
+    "border_color",
+    "border_radius",
+    "box_shadow",
+    "color",
+    "cursor",
+    "image_repeat",
+    "opacity",
+    "text_decoration",
+];
+
 /// A macro that simply wraps the definition struct of [`Style`], allowing
 /// some methods to be automatically defined. Otherwise, there would be a _lot_ of
 /// copying and pasting, resulting in fragile code.
@@ -178,6 +232,18 @@ macro_rules! define_styles {
                 }
             }
 
+            /// Returns a `Style` object where the fields listed in [`INHERITED_PROPERTIES`] are set to
+            /// [`StyleProp::Inherit`] and all others are set to [`StyleProp::Unset`]
+            pub fn inherited() -> Self {
+                Self {
+                    $($field: if INHERITED_PROPERTIES.contains(&stringify!($field)) {
+                        StyleProp::Inherit
+                    } else {
+                        StyleProp::Unset
+                    }),*
+                }
+            }
+
             /// If any field is set to [`StyleProp::Inherit`], its value will be taken from `other`
             pub fn inherit(&mut self, other: &Self) {
                  $(
@@ -187,6 +253,20 @@ macro_rules! define_styles {
                  )*
             }
 
+            /// If any field is set to [`StyleProp::Initial`], its value will be taken from `initial`
+            ///
+            /// Fields that `initial` doesn't have a value for are set to [`StyleProp::Default`].
+            pub fn reset(&mut self, initial: &Self) {
+                 $(
+                     if matches!(self.$field, StyleProp::Initial) {
+                         self.$field = match &initial.$field {
+                             StyleProp::Value(value) => StyleProp::Value(value.clone()),
+                             _ => StyleProp::Default,
+                         };
+                     }
+                 )*
+            }
+
             /// Applies a `Style` over this one
             ///
             /// Values from `other` are applied to any field in this one that is marked as [`StyleProp::Unset`]
@@ -200,6 +280,16 @@ macro_rules! define_styles {
                  }
             }
 
+            /// Returns true if any property that affects layout differs between the two styles
+            ///
+            /// Every property is compared except those listed in [`PAINT_ONLY_PROPERTIES`].
+            pub fn layout_differs(&self, other: &Self) -> bool {
+                $(
+                    (!PAINT_ONLY_PROPERTIES.contains(&stringify!($field))
+                        && self.$field != other.$field)
+                )||*
+            }
+
             /// Applies the given style and returns the updated style
             ///
             /// This is simply a builder-like wrapper around the [`Style::apply`] method.
@@ -251,10 +341,33 @@ define_styles! {
     #[derive(Component, Reflect, FromReflect, Debug, Default, Clone, PartialEq)]
     #[reflect(Component)]
     pub struct KStyle {
+        /// How the children of this widget are positioned vertically, when laid out in a row
+        ///
+        /// See [`AlignItems`] for the available options. This property defaults to
+        /// [`AlignItems::Normal`].
+        pub align_items: StyleProp<AlignItems>,
+        /// The ratio of this widget's width to its height
+        ///
+        /// When only one of [`width`](Self::width) or [`height`](Self::height) is set, the other is
+        /// derived from it. When neither is set, the widget fills the width of its parent and the
+        /// height is derived from that. Derived sizes take precedence over the automatic sizing of
+        /// [`RenderCommand::Text`].
+        pub aspect_ratio: StyleProp<f32>,
+        /// The blur applied to whatever is drawn behind this widget
+        ///
+        /// This is drawn just below the widget's own content, over its shadow. Renderers that
+        /// can't blur ignore it.
+        pub backdrop_filter: StyleProp<BackdropFilter>,
         /// The background color of this widget
         ///
         /// Only applies to widgets marked [`RenderCommand::Quad`]
         pub background_color : StyleProp<Color>,
+        /// The background gradient of this widget
+        ///
+        /// When set, this replaces the [`background_color`](Self::background_color) and border of the widget.
+        ///
+        /// Only applies to widgets marked [`RenderCommand::Quad`]
+        pub background_gradient: StyleProp<Gradient>,
         /// The color of the border around this widget
         ///
         /// Currently, this controls all border sides.
@@ -275,6 +388,10 @@ define_styles! {
         pub border: StyleProp<Edge<f32>>,
         /// The distance between the bottom edge of this widget and the bottom edge of its containing widget
         pub bottom: StyleProp<Units>,
+        /// The drop shadow drawn behind this widget
+        ///
+        /// The shadow uses the [`border_radius`](Self::border_radius) of this widget.
+        pub box_shadow: StyleProp<BoxShadow>,
         /// The text color for this widget
         ///
         /// This property defaults to [`StyleProp::Inherit`] meaning that setting this field to some value will
@@ -287,24 +404,65 @@ define_styles! {
         /// The cursor icon to display when hovering this widget
         #[reflect(ignore)]
         pub cursor: StyleProp<KCursorIcon>,
+        /// Whether this widget is disabled
+        ///
+        /// Disabled widgets are still rendered, but can't receive focus even if they're
+        /// [`Focusable`](crate::prelude::Focusable).
+        pub disabled: StyleProp<bool>,
+        /// The order in which this widget receives focus, relative to its focusable siblings
+        ///
+        /// Widgets with a lower focus index are focused first, and widgets with the same index are
+        /// focused in tree order. Widgets without a focus index are focused after all others, like
+        /// `tabindex` in HTML.
+        ///
+        /// Only applies to [`Focusable`](crate::prelude::Focusable) widgets.
+        pub focus_index: StyleProp<i32>,
         /// The font name for this widget
         ///
         /// Only applies to [`RenderCommand::Text`]
         pub font: StyleProp<String>,
+        /// The names of the fonts to try, in order, when `font` is missing glyphs for the text
+        ///
+        /// The first font that has a glyph for every character is used to measure and render the
+        /// whole text. If none of them do, `font` is used.
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub font_fallback: StyleProp<Vec<String>>,
         /// The font size for this widget, in pixels
         ///
         /// Only applies to [`RenderCommand::Text`]
         pub font_size: StyleProp<f32>,
+        /// The font size for this widget, as a multiple of its parent's font size
+        ///
+        /// Takes precedence over [`font_size`](KStyle::font_size). Descendants inherit the resolved
+        /// size in pixels, so nested `em` sizes compound like they do in CSS.
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub font_size_em: StyleProp<f32>,
+        /// The font size for this widget, as a multiple of the root font size
+        ///
+        /// Takes precedence over [`font_size`](KStyle::font_size) and [`font_size_em`](KStyle::font_size_em).
+        /// See [`KayakRootContext::set_root_font_size`](crate::prelude::KayakRootContext::set_root_font_size).
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub font_size_rem: StyleProp<f32>,
         /// The height of this widget
         pub height: StyleProp<Units>,
+        /// How the image fills this widget when it doesn't match the image's size
+        ///
+        /// Only applies to widgets marked [`RenderCommand::Image`]
+        pub image_repeat: StyleProp<Repeat>,
         /// The layout method for children of this widget
         pub layout_type: StyleProp<LayoutType>,
         /// The distance between the left edge of this widget and the left edge of its containing widget
         pub left: StyleProp<Units>,
-        /// The line height for this widget, in pixels
+        /// The height of each line of text in this widget
+        ///
+        /// See [`LineHeight`] for the ways it can be given. This property defaults to
+        /// [`StyleProp::Inherit`].
         ///
         /// Only applies to [`RenderCommand::Text`]
-        pub line_height: StyleProp<f32>,
+        pub line_height: StyleProp<LineHeight>,
         /// The maximum height of this widget
         pub max_height: StyleProp<Units>,
         /// The maximum width of this widget
@@ -322,7 +480,21 @@ define_styles! {
         /// For widgets with a [`position_type`](Self::position_type) of [`PositionType`](PositionType::ParentDirected)
         /// this acts like margin around the widget. For [`PositionType`](PositionType::SelfDirected) this
         /// acts as the actual position from the parent.
+        ///
+        /// Negative values are allowed, and pull the widget over its previous sibling (or the next one,
+        /// for the trailing edges). Overlapping siblings are drawn in tree order unless their
+        /// [`z_index`](Self::z_index) says otherwise.
         pub offset: StyleProp<Edge<Units>>,
+        /// The opacity of this widget and all of its descendants, from `0.0` to `1.0`
+        ///
+        /// A widget's final opacity is multiplied by the opacity of each of its ancestors.
+        pub opacity: StyleProp<f32>,
+        /// Controls whether this widget and its descendants are clipped by the clip regions of
+        /// its ancestors
+        ///
+        /// Set this to [`Overflow::Visible`] for things like tooltips that need to escape a
+        /// scrolling container.
+        pub overflow: StyleProp<Overflow>,
         /// The inner padding between the edges of this widget and its children
         ///
         /// This property has lower precedence than its more specific counterparts
@@ -366,10 +538,76 @@ define_styles! {
         pub render_command: StyleProp<RenderCommand>,
         /// The distance between the right edge of this widget and the right edge of its containing widget
         pub right: StyleProp<Units>,
+        /// The role of this widget in the accessibility tree
+        ///
+        /// When unset, the role is derived from the widget's render command.
+        pub role: StyleProp<Role>,
         /// The spacing between child widgets along the vertical axis
         pub row_between: StyleProp<Units>,
+        /// The offset (in pixels) applied to the position of all descendants of this widget
+        ///
+        /// Descendants that end up entirely outside of this widget are not rendered.
+        ///
+        /// Only applies to widgets marked [`RenderCommand::Clip`]
+        pub scroll: StyleProp<(f32, f32)>,
+        /// The horizontal alignment of text within this widget
+        ///
+        /// Overrides the alignment given by the [`RenderCommand::Text`] when set.
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub text_alignment: StyleProp<Alignment>,
+        /// The lines drawn along with text, such as an underline
+        ///
+        /// Decorations don't change the measured size of the text.
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub text_decoration: StyleProp<TextDecoration>,
+        /// The direction text is read in
+        ///
+        /// Right-to-left text is laid out from the right and mirrors the alignment given by the
+        /// [`RenderCommand::Text`]. This property defaults to [`StyleProp::Inherit`].
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub text_direction: StyleProp<TextDirection>,
+        /// The maximum number of lines that text wraps onto
+        ///
+        /// Text that wraps onto more lines is cut off at the end of the last line, which ends
+        /// with an ellipsis ("…"). The measured height only covers the lines that are kept.
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub text_max_lines: StyleProp<usize>,
+        /// The color and width of an outline drawn around each glyph, behind the text itself
+        ///
+        /// This keeps text readable over busy backgrounds. Like decorations, the outline doesn't
+        /// change the measured size of the text by default, so it extends past the text's bounds.
+        /// See `text_outline_in_bounds` to make room for it instead.
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub text_outline: StyleProp<(Color, f32)>,
+        /// Whether the measured size of text includes its `text_outline`
+        ///
+        /// When true, the text is measured with the width of the outline added to each side, and
+        /// drawn inset by that width, so the outline stays within the text's bounds.
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub text_outline_in_bounds: StyleProp<bool>,
+        /// How text that doesn't fit within the width of its parent is handled
+        ///
+        /// Overrides the `word_wrap` given by the [`RenderCommand::Text`] when set.
+        ///
+        /// Only applies to [`RenderCommand::Text`]
+        pub text_wrap: StyleProp<TextWrap>,
         /// The distance between the top edge of this widget and the top edge of its containing widget
         pub top: StyleProp<Units>,
+        /// A visual transform applied to this widget and its descendants
+        ///
+        /// Transforms are applied after layout and never affect the layout of other widgets.
+        /// See [`KTransform`] for details.
+        pub transform: StyleProp<KTransform>,
+        /// The style properties that animate when they change, and how they animate
+        ///
+        /// See [`Transition`] for details.
+        pub transition: StyleProp<Transition>,
         /// The width of this widget
         pub width: StyleProp<Units>,
         /// The z-index relative to it's parent.
@@ -405,28 +643,36 @@ impl KStyle {
     /// Returns a `Style` object where all fields are set to their own initial values
     ///
     /// This is the actual "default" to apply over any field marked as [`StyleProp::Unset`] before
-    /// resolving the style.
+    /// resolving the style. Properties listed in [`INHERITED_PROPERTIES`] are marked as
+    /// [`StyleProp::Inherit`].
     pub fn initial() -> Self {
         Self {
+            align_items: StyleProp::Default,
+            aspect_ratio: StyleProp::Default,
+            backdrop_filter: StyleProp::Default,
             background_color: StyleProp::Default,
+            background_gradient: StyleProp::Default,
             border: StyleProp::Default,
             border_color: StyleProp::Default,
             border_radius: StyleProp::Default,
             bottom: StyleProp::Default,
-            color: StyleProp::Inherit,
-            cursor: StyleProp::Inherit,
+            box_shadow: StyleProp::Default,
             col_between: StyleProp::Default,
-            font: StyleProp::Inherit,
-            font_size: StyleProp::Inherit,
+            disabled: StyleProp::Default,
+            focus_index: StyleProp::Default,
+            font_size_em: StyleProp::Default,
+            font_size_rem: StyleProp::Default,
             height: StyleProp::Default,
+            image_repeat: StyleProp::Default,
             layout_type: StyleProp::Default,
-            line_height: StyleProp::Inherit,
             left: StyleProp::Default,
             max_height: StyleProp::Default,
             max_width: StyleProp::Default,
             min_height: StyleProp::Default,
             min_width: StyleProp::Default,
             offset: StyleProp::Default,
+            opacity: StyleProp::Default,
+            overflow: StyleProp::Default,
             padding: StyleProp::Default,
             padding_bottom: StyleProp::Default,
             padding_left: StyleProp::Default,
@@ -436,8 +682,17 @@ impl KStyle {
             position_type: StyleProp::Default,
             render_command: StyleProp::Value(RenderCommand::Layout),
             right: StyleProp::Default,
+            role: StyleProp::Default,
             row_between: StyleProp::Default,
+            scroll: StyleProp::Default,
+            text_decoration: StyleProp::Default,
+            text_max_lines: StyleProp::Default,
+            text_outline: StyleProp::Default,
+            text_outline_in_bounds: StyleProp::Default,
+            text_wrap: StyleProp::Default,
             top: StyleProp::Default,
+            transform: StyleProp::Default,
+            transition: StyleProp::Default,
             width: StyleProp::Default,
             z_index: StyleProp::Default,
             grid_rows: StyleProp::Default,
@@ -446,6 +701,7 @@ impl KStyle {
             col_index: StyleProp::Default,
             row_span: StyleProp::Default,
             col_span: StyleProp::Default,
+            ..Self::inherited()
         }
     }
 }
@@ -464,7 +720,12 @@ impl Add for KStyle {
 
 #[cfg(test)]
 mod tests {
-    use super::{Edge, KStyle, StyleProp, Units};
+    use super::{Edge, KStyle, StyleProp, Units, INHERITED_PROPERTIES, PAINT_ONLY_PROPERTIES};
+    use bevy::{
+        prelude::Color,
+        reflect::{ReflectRef, Struct},
+    };
+    use kayak_font::Alignment;
 
     #[test]
     fn styles_should_equal() {
@@ -506,6 +767,31 @@ mod tests {
         let _ = style.color.resolve();
     }
 
+    #[test]
+    fn style_should_reset_initial_property() {
+        let initial = KStyle {
+            color: StyleProp::Value(Color::BLACK),
+            ..KStyle::new_default()
+        };
+        let parent = KStyle {
+            color: StyleProp::Value(Color::RED),
+            font_size: StyleProp::Value(20.0),
+            ..Default::default()
+        };
+        let mut child = KStyle {
+            color: StyleProp::Initial,
+            font_size: StyleProp::Initial,
+            ..KStyle::inherited()
+        };
+
+        child.reset(&initial);
+        child.inherit(&parent);
+
+        assert_eq!(StyleProp::Value(Color::BLACK), child.color);
+        // Not set by the initial styles either
+        assert_eq!(StyleProp::Default, child.font_size);
+    }
+
     #[test]
     fn style_should_apply_styles_on_unset_property() {
         let mut base_style = KStyle::default();
@@ -650,4 +936,118 @@ mod tests {
         assert_eq!(expected, property.resolve_or_else(|| expected));
         assert_eq!(f32::default(), property.resolve_or_default());
     }
+
+    #[test]
+    fn only_layout_properties_should_affect_layout() {
+        let style = KStyle {
+            width: StyleProp::Value(Units::Pixels(100.0)),
+            ..Default::default()
+        };
+
+        let recolored = KStyle {
+            background_color: StyleProp::Value(Color::RED),
+            ..style.clone()
+        };
+        assert!(!style.layout_differs(&recolored));
+
+        let padded = KStyle {
+            padding: StyleProp::Value(Edge::all(Units::Pixels(4.0))),
+            ..style.clone()
+        };
+        assert!(style.layout_differs(&padded));
+
+        let resized = KStyle {
+            width: StyleProp::Value(Units::Pixels(200.0)),
+            ..style.clone()
+        };
+        assert!(style.layout_differs(&resized));
+    }
+
+    #[test]
+    fn initial_should_inherit_exactly_the_inherited_properties() {
+        let initial = KStyle::initial();
+        for (index, field) in initial.iter_fields().enumerate() {
+            let name = initial.name_at(index).unwrap();
+            let is_inherit = match field.reflect_ref() {
+                ReflectRef::Enum(prop) => prop.variant_name() == "Inherit",
+                _ => false,
+            };
+            assert_eq!(
+                INHERITED_PROPERTIES.contains(&name),
+                is_inherit,
+                "unexpected initial value for `{}`",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn parent_color_should_flow_to_unstyled_child() {
+        let mut parent = KStyle {
+            color: StyleProp::Value(Color::RED),
+            text_alignment: StyleProp::Value(Alignment::Middle),
+            ..Default::default()
+        };
+        parent.apply(&KStyle::initial());
+        parent.inherit(&KStyle::new_default());
+
+        let mut label = KStyle::default();
+        label.apply(&KStyle::initial());
+        label.inherit(&parent);
+
+        assert_eq!(StyleProp::Value(Color::RED), label.color);
+        assert_eq!(StyleProp::Value(Alignment::Middle), label.text_alignment);
+        assert_eq!(StyleProp::Default, label.background_color);
+    }
+
+    #[test]
+    fn only_paint_properties_should_leave_the_layout_alone() {
+        let base = KStyle::new_default();
+        // A style that differs from the base in a single property
+        macro_rules! changing {
+            ($($field: ident),*) => {
+                [$((
+                    stringify!($field),
+                    KStyle {
+                        $field: StyleProp::Inherit,
+                        ..base.clone()
+                    },
+                )),*]
+            };
+        }
+
+        let layout = changing!(
+            align_items,
+            aspect_ratio,
+            font_size,
+            line_height,
+            overflow,
+            render_command,
+            text_wrap,
+            transform,
+            width
+        );
+        for (name, style) in layout {
+            assert!(!PAINT_ONLY_PROPERTIES.contains(&name));
+            assert!(base.layout_differs(&style), "{} should affect layout", name);
+        }
+
+        let paint = changing!(
+            background_color,
+            border_color,
+            border_radius,
+            box_shadow,
+            color,
+            cursor,
+            opacity
+        );
+        for (name, style) in paint {
+            assert!(PAINT_ONLY_PROPERTIES.contains(&name));
+            assert!(
+                !base.layout_differs(&style),
+                "{} shouldn't affect layout",
+                name
+            );
+        }
+    }
 }
@@ -0,0 +1,31 @@
+use bevy::reflect::{FromReflect, Reflect};
+
+/// The lines drawn along with text
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq, Eq)]
+pub enum TextDecoration {
+    None,
+    /// A line just below the baseline of each line of text
+    Underline,
+    /// A line through the middle of each line of text
+    Strikethrough,
+    /// Both an underline and a strikethrough
+    Both,
+}
+
+impl Default for TextDecoration {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+impl TextDecoration {
+    /// Whether this decoration draws a line below the text
+    pub fn has_underline(self) -> bool {
+        matches!(self, Self::Underline | Self::Both)
+    }
+
+    /// Whether this decoration draws a line through the text
+    pub fn has_strikethrough(self) -> bool {
+        matches!(self, Self::Strikethrough | Self::Both)
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::reflect::{FromReflect, Reflect};
+use kayak_font::Alignment;
+
+/// The direction text is read in
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq, Eq)]
+pub enum TextDirection {
+    /// Left-to-right text, such as English
+    Ltr,
+    /// Right-to-left text, such as Arabic or Hebrew
+    Rtl,
+    /// Detects the direction from the first strongly directional character of the text
+    Auto,
+}
+
+impl Default for TextDirection {
+    fn default() -> Self {
+        Self::Ltr
+    }
+}
+
+impl TextDirection {
+    /// Resolves [`TextDirection::Auto`] to a concrete direction for the given text
+    pub fn resolve_for(self, content: &str) -> Self {
+        match self {
+            Self::Auto => content
+                .chars()
+                .find_map(|c| {
+                    if is_rtl_char(c) {
+                        Some(Self::Rtl)
+                    } else if c.is_alphabetic() {
+                        Some(Self::Ltr)
+                    } else {
+                        None
+                    }
+                })
+                .unwrap_or(Self::Ltr),
+            direction => direction,
+        }
+    }
+
+    /// Mirrors the given alignment for right-to-left text
+    ///
+    /// This makes text start at the right edge of its container by default.
+    pub fn align(self, alignment: Alignment) -> Alignment {
+        match (self, alignment) {
+            (Self::Rtl, Alignment::Start) => Alignment::End,
+            (Self::Rtl, Alignment::End) => Alignment::Start,
+            (_, alignment) => alignment,
+        }
+    }
+}
+
+/// Checks if the character belongs to a right-to-left script
+fn is_rtl_char(c: char) -> bool {
+    matches!(
+        c,
+        // Hebrew, Arabic, Syriac, Thaana, NKo, Samaritan, Mandaic and Arabic Extended
+        '\u{0590}'..='\u{08FF}'
+            // Hebrew and Arabic presentation forms
+            | '\u{FB1D}'..='\u{FDFF}'
+            | '\u{FE70}'..='\u{FEFF}'
+    )
+}
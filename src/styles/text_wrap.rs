@@ -0,0 +1,21 @@
+use bevy::reflect::{FromReflect, Reflect};
+
+/// Controls how text that is wider than its container is handled
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq, Eq)]
+pub enum TextWrap {
+    /// Wraps the text onto multiple lines
+    Wrap,
+    /// Keeps the text on a single line, letting it overflow its container
+    NoWrap,
+    /// Keeps the text on a single line, cutting off anything that doesn't fit
+    Truncate {
+        /// Whether to end cut off text with an ellipsis ("…")
+        ellipsis: bool,
+    },
+}
+
+impl Default for TextWrap {
+    fn default() -> Self {
+        Self::Wrap
+    }
+}
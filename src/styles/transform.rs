@@ -0,0 +1,121 @@
+use bevy::{
+    prelude::Vec2,
+    reflect::{FromReflect, Reflect},
+};
+
+use crate::layout::Rect;
+
+/// A visual transform applied to a widget and all of its descendants
+///
+/// Transforms are applied after layout, so they only change where a widget is drawn and never
+/// move or resize its siblings. Scaling happens around the center of the widget. Widgets are drawn
+/// as axis-aligned quads, so rotation isn't supported.
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq)]
+pub struct KTransform {
+    /// The offset applied to the widget, in pixels
+    pub translation: Vec2,
+    /// The scale applied to the widget around its center
+    pub scale: Vec2,
+}
+
+impl Default for KTransform {
+    fn default() -> Self {
+        Self {
+            translation: Vec2::ZERO,
+            scale: Vec2::ONE,
+        }
+    }
+}
+
+impl KTransform {
+    /// Creates a transform that only offsets the widget
+    pub fn from_translation(translation: Vec2) -> Self {
+        Self {
+            translation,
+            ..Default::default()
+        }
+    }
+
+    /// Creates a transform that only scales the widget around its center
+    pub fn from_scale(scale: Vec2) -> Self {
+        Self {
+            scale,
+            ..Default::default()
+        }
+    }
+
+    /// Converts this transform into one that scales around the origin instead of the
+    /// center of the given layout
+    pub(crate) fn about_center(&self, layout: &Rect) -> Self {
+        let center = Vec2::new(
+            layout.posx + layout.width / 2.0,
+            layout.posy + layout.height / 2.0,
+        );
+        Self {
+            translation: center - center * self.scale + self.translation,
+            scale: self.scale,
+        }
+    }
+
+    /// Returns the transform that applies `self` followed by `parent`
+    pub(crate) fn then(&self, parent: &Self) -> Self {
+        Self {
+            translation: self.translation * parent.scale + parent.translation,
+            scale: self.scale * parent.scale,
+        }
+    }
+
+    /// Applies this transform (scaling around the origin) to a layout
+    pub(crate) fn apply(&self, layout: Rect) -> Rect {
+        Rect {
+            posx: layout.posx * self.scale.x + self.translation.x,
+            posy: layout.posy * self.scale.y + self.translation.y,
+            width: layout.width * self.scale.x,
+            height: layout.height * self.scale.y,
+            z_index: layout.z_index,
+            paint_order: layout.paint_order,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KTransform;
+    use crate::layout::Rect;
+    use bevy::prelude::Vec2;
+
+    fn rect(posx: f32, posy: f32, width: f32, height: f32) -> Rect {
+        Rect {
+            posx,
+            posy,
+            width,
+            height,
+            z_index: 0.0,
+            paint_order: 0,
+        }
+    }
+
+    #[test]
+    fn scale_should_be_around_the_center() {
+        let layout = rect(10.0, 10.0, 100.0, 50.0);
+        let transform = KTransform::from_scale(Vec2::splat(2.0)).about_center(&layout);
+
+        assert_eq!(rect(-40.0, -15.0, 200.0, 100.0), transform.apply(layout));
+    }
+
+    #[test]
+    fn child_should_follow_parent_transform() {
+        let parent = rect(0.0, 0.0, 100.0, 100.0);
+        let child = rect(50.0, 50.0, 10.0, 10.0);
+        let parent_transform = KTransform {
+            translation: Vec2::new(5.0, 0.0),
+            scale: Vec2::splat(2.0),
+        }
+        .about_center(&parent);
+        let child_transform = KTransform::from_translation(Vec2::new(0.0, 1.0))
+            .about_center(&child)
+            .then(&parent_transform);
+
+        assert_eq!(rect(55.0, 52.0, 20.0, 20.0), child_transform.apply(child));
+    }
+}
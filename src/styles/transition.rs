@@ -0,0 +1,282 @@
+use bevy::{
+    prelude::Color,
+    reflect::{FromReflect, Reflect},
+};
+
+use super::{Calc, Corner, KStyle, KTransform, StyleProp, Units};
+
+/// The easing curve used to blend a [`Transition`]
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq, Eq)]
+pub enum TransitionEasing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Default for TransitionEasing {
+    fn default() -> Self {
+        Self::EaseInOut
+    }
+}
+
+impl TransitionEasing {
+    /// Maps the linear progress of a transition (from `0.0` to `1.0`) onto this curve
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => t * (2.0 - t),
+            Self::EaseInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    -1.0 + (4.0 - 2.0 * t) * t
+                }
+            }
+        }
+    }
+}
+
+/// A style property that can be animated by a [`Transition`]
+#[derive(Debug, Reflect, FromReflect, Copy, Clone, PartialEq, Eq)]
+pub enum TransitionProperty {
+    BackgroundColor,
+    BorderColor,
+    BorderRadius,
+    Color,
+    Width,
+    Height,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    FontSize,
+    Opacity,
+    Transform,
+}
+
+/// Describes which style properties of a widget animate when they change, and how
+///
+/// Properties that can't be blended (such as switching from [`Units::Pixels`] to
+/// [`Units::Stretch`]) snap to their new value instead.
+#[derive(Debug, Default, Reflect, FromReflect, Clone, PartialEq)]
+pub struct Transition {
+    /// The properties that are animated
+    pub properties: Vec<TransitionProperty>,
+    /// How long the transition lasts, in seconds
+    pub duration: f32,
+    /// The easing curve of the transition
+    pub easing: TransitionEasing,
+}
+
+impl Transition {
+    /// Returns true if any transitioned property differs between the two styles
+    pub fn differs(&self, a: &KStyle, b: &KStyle) -> bool {
+        self.properties.iter().any(|property| match property {
+            TransitionProperty::BackgroundColor => a.background_color != b.background_color,
+            TransitionProperty::BorderColor => a.border_color != b.border_color,
+            TransitionProperty::BorderRadius => a.border_radius != b.border_radius,
+            TransitionProperty::Color => a.color != b.color,
+            TransitionProperty::Width => a.width != b.width,
+            TransitionProperty::Height => a.height != b.height,
+            TransitionProperty::Left => a.left != b.left,
+            TransitionProperty::Right => a.right != b.right,
+            TransitionProperty::Top => a.top != b.top,
+            TransitionProperty::Bottom => a.bottom != b.bottom,
+            TransitionProperty::FontSize => a.font_size != b.font_size,
+            TransitionProperty::Opacity => a.opacity != b.opacity,
+        })
+    }
+
+    /// Blends the transitioned properties of `from` towards `to`, writing them into `target`
+    ///
+    /// The progress `t` is linear and the easing curve is applied by this method.
+    pub fn blend(&self, from: &KStyle, to: &KStyle, t: f32, target: &mut KStyle) {
+        let t = self.easing.apply(t);
+        for property in self.properties.iter() {
+            match property {
+                TransitionProperty::BackgroundColor => {
+                    target.background_color =
+                        lerp_prop(&from.background_color, &to.background_color, t, lerp_color)
+                }
+                TransitionProperty::BorderColor => {
+                    target.border_color =
+                        lerp_prop(&from.border_color, &to.border_color, t, lerp_color)
+                }
+                TransitionProperty::BorderRadius => {
+                    target.border_radius =
+                        lerp_prop(&from.border_radius, &to.border_radius, t, lerp_corner)
+                }
+                TransitionProperty::Color => {
+                    target.color = lerp_prop(&from.color, &to.color, t, lerp_color)
+                }
+                TransitionProperty::Width => {
+                    target.width = lerp_prop(&from.width, &to.width, t, lerp_units)
+                }
+                TransitionProperty::Height => {
+                    target.height = lerp_prop(&from.height, &to.height, t, lerp_units)
+                }
+                TransitionProperty::Left => {
+                    target.left = lerp_prop(&from.left, &to.left, t, lerp_units)
+                }
+                TransitionProperty::Right => {
+                    target.right = lerp_prop(&from.right, &to.right, t, lerp_units)
+                }
+                TransitionProperty::Top => {
+                    target.top = lerp_prop(&from.top, &to.top, t, lerp_units)
+                }
+                TransitionProperty::Bottom => {
+                    target.bottom = lerp_prop(&from.bottom, &to.bottom, t, lerp_units)
+                }
+                TransitionProperty::FontSize => {
+                    target.font_size = lerp_prop(&from.font_size, &to.font_size, t, lerp_f32)
+                }
+                TransitionProperty::Opacity => {
+                    target.opacity = lerp_prop(&from.opacity, &to.opacity, t, lerp_f32)
+                }
+                TransitionProperty::Transform => {
+                    target.transform = lerp_prop(&from.transform, &to.transform, t, lerp_transform)
+                }
+            }
+        }
+    }
+}
+
+/// The state of an in-flight transition for a single node
+#[derive(Debug, Clone)]
+pub(crate) struct ActiveTransition {
+    /// The styles being transitioned from
+    pub from: KStyle,
+    /// The styles being transitioned to
+    pub to: KStyle,
+    /// The time the transition started, in seconds
+    pub started: f32,
+}
+
+fn lerp_prop<T: Default + Clone + Reflect + FromReflect>(
+    from: &StyleProp<T>,
+    to: &StyleProp<T>,
+    t: f32,
+    lerp: impl Fn(&T, &T, f32) -> Option<T>,
+) -> StyleProp<T> {
+    match (from, to) {
+        (StyleProp::Value(from), StyleProp::Value(to)) => {
+            lerp(from, to, t).map_or_else(|| StyleProp::Value(to.clone()), StyleProp::Value)
+        }
+        _ => to.clone(),
+    }
+}
+
+fn lerp_f32(from: &f32, to: &f32, t: f32) -> Option<f32> {
+    Some(from + (to - from) * t)
+}
+
+fn lerp_color(from: &Color, to: &Color, t: f32) -> Option<Color> {
+    let a = from.as_rgba_f32();
+    let b = to.as_rgba_f32();
+    Some(Color::rgba(
+        a[0] + (b[0] - a[0]) * t,
+        a[1] + (b[1] - a[1]) * t,
+        a[2] + (b[2] - a[2]) * t,
+        a[3] + (b[3] - a[3]) * t,
+    ))
+}
+
+fn lerp_corner(from: &Corner<f32>, to: &Corner<f32>, t: f32) -> Option<Corner<f32>> {
+    Some(Corner::new(
+        from.top_left + (to.top_left - from.top_left) * t,
+        from.top_right + (to.top_right - from.top_right) * t,
+        from.bottom_left + (to.bottom_left - from.bottom_left) * t,
+        from.bottom_right + (to.bottom_right - from.bottom_right) * t,
+    ))
+}
+
+fn lerp_transform(from: &KTransform, to: &KTransform, t: f32) -> Option<KTransform> {
+    Some(KTransform {
+        translation: from.translation.lerp(to.translation, t),
+        scale: from.scale.lerp(to.scale, t),
+    })
+}
+
+fn lerp_units(from: &Units, to: &Units, t: f32) -> Option<Units> {
+    match (from, to) {
+        (Units::Pixels(a), Units::Pixels(b)) => Some(Units::Pixels(a + (b - a) * t)),
+        (Units::Percentage(a), Units::Percentage(b)) => Some(Units::Percentage(a + (b - a) * t)),
+        (Units::Stretch(a), Units::Stretch(b)) => Some(Units::Stretch(a + (b - a) * t)),
+        (Units::Rem(a), Units::Rem(b)) => Some(Units::Rem(a + (b - a) * t)),
+        (Units::ViewportWidth(a), Units::ViewportWidth(b)) => {
+            Some(Units::ViewportWidth(a + (b - a) * t))
+        }
+        (Units::ViewportHeight(a), Units::ViewportHeight(b)) => {
+            Some(Units::ViewportHeight(a + (b - a) * t))
+        }
+        (Units::Calc(a), Units::Calc(b)) => Some(Units::Calc(Calc::new(
+            a.percentage + (b.percentage - a.percentage) * t,
+            a.pixels + (b.pixels - a.pixels) * t,
+        ))),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Transition, TransitionEasing, TransitionProperty};
+    use crate::styles::{KStyle, StyleProp, Units};
+
+    #[test]
+    fn easing_should_start_and_end_at_bounds() {
+        for easing in [
+            TransitionEasing::Linear,
+            TransitionEasing::EaseIn,
+            TransitionEasing::EaseOut,
+            TransitionEasing::EaseInOut,
+        ] {
+            assert_eq!(0.0, easing.apply(0.0));
+            assert_eq!(1.0, easing.apply(1.0));
+        }
+    }
+
+    #[test]
+    fn transition_should_blend_pixels() {
+        let transition = Transition {
+            properties: vec![TransitionProperty::Width],
+            duration: 1.0,
+            easing: TransitionEasing::Linear,
+        };
+        let from = KStyle {
+            width: Units::Pixels(0.0).into(),
+            ..Default::default()
+        };
+        let to = KStyle {
+            width: Units::Pixels(100.0).into(),
+            ..Default::default()
+        };
+
+        let mut target = to.clone();
+        transition.blend(&from, &to, 0.25, &mut target);
+        assert_eq!(StyleProp::Value(Units::Pixels(25.0)), target.width);
+    }
+
+    #[test]
+    fn transition_should_snap_mismatched_units() {
+        let transition = Transition {
+            properties: vec![TransitionProperty::Width],
+            duration: 1.0,
+            easing: TransitionEasing::Linear,
+        };
+        let from = KStyle {
+            width: Units::Pixels(0.0).into(),
+            ..Default::default()
+        };
+        let to = KStyle {
+            width: Units::Stretch(1.0).into(),
+            ..Default::default()
+        };
+
+        let mut target = to.clone();
+        transition.blend(&from, &to, 0.5, &mut target);
+        assert_eq!(to.width, target.width);
+    }
+}
@@ -51,6 +51,23 @@ impl Into<morphorm::PositionType> for KPositionType {
     }
 }
 
+/// Determines how a widget interacts with the clip regions of its ancestors
+#[derive(Debug, Reflect, FromReflect, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// The widget and its descendants are drawn outside of any ancestor clip regions
+    Visible,
+    /// The widget and its descendants are clipped by the nearest ancestor clip region
+    Hidden,
+    /// Same as [`Overflow::Hidden`], used for content offset by a [`scroll`](crate::styles::KStyle::scroll)
+    Scroll,
+}
+
+impl Default for Overflow {
+    fn default() -> Self {
+        Overflow::Hidden
+    }
+}
+
 /// Units which describe spacing and size
 #[derive(Debug, FromReflect, Reflect, Clone, Copy, PartialEq)]
 pub enum Units {
@@ -62,6 +79,22 @@ pub enum Units {
     Stretch(f32),
     /// Automatically determine the value
     Auto,
+    /// A multiple of the root font size
+    ///
+    /// See [`KayakRootContext::set_root_font_size`](crate::prelude::KayakRootContext::set_root_font_size).
+    Rem(f32),
+    /// A percentage of the parent dimension plus a number of pixels, such as `100% - 48px`
+    ///
+    /// This is converted to pixels using the last known size of the parent, before layout.
+    Calc(Calc),
+    /// A percentage of the viewport width
+    ///
+    /// See [`KayakRootContext::set_viewport_size`](crate::prelude::KayakRootContext::set_viewport_size).
+    ViewportWidth(f32),
+    /// A percentage of the viewport height
+    ///
+    /// See [`KayakRootContext::set_viewport_size`](crate::prelude::KayakRootContext::set_viewport_size).
+    ViewportHeight(f32),
 }
 
 impl Default for Units {
@@ -70,6 +103,33 @@ impl Default for Units {
     }
 }
 
+/// A size derived from the size of the parent, used by [`Units::Calc`]
+#[derive(Debug, Default, FromReflect, Reflect, Clone, Copy, PartialEq)]
+pub struct Calc {
+    /// The percentage of the parent dimension
+    pub percentage: f32,
+    /// The number of pixels added, which is negative to subtract
+    pub pixels: f32,
+}
+
+impl Calc {
+    /// Creates a size of `percentage`% of the parent dimension plus `pixels`
+    ///
+    /// ```
+    /// # use kayak_ui::prelude::{Calc, Units};
+    /// // Fills whatever is left under a 48px header
+    /// let height = Units::Calc(Calc::new(100.0, -48.0));
+    /// ```
+    pub fn new(percentage: f32, pixels: f32) -> Self {
+        Self { percentage, pixels }
+    }
+
+    /// Converts the size to pixels within a parent of the given size
+    pub fn resolve(&self, parent_value: f32) -> f32 {
+        (self.percentage / 100.0) * parent_value + self.pixels
+    }
+}
+
 impl Into<morphorm::Units> for Units {
     fn into(self) -> morphorm::Units {
         match self {
@@ -77,6 +137,14 @@ impl Into<morphorm::Units> for Units {
             Self::Percentage(value) => morphorm::Units::Percentage(value),
             Self::Stretch(value) => morphorm::Units::Stretch(value),
             Self::Auto => morphorm::Units::Auto,
+            // Rem units are converted to pixels before layout, so this is just a fallback
+            Self::Rem(value) => morphorm::Units::Pixels(value * crate::DEFAULT_ROOT_FONT_SIZE),
+            // As are calculated units
+            Self::Calc(calc) => morphorm::Units::Pixels(calc.pixels),
+            // As are viewport units, which fall back to being relative to the parent
+            Self::ViewportWidth(value) | Self::ViewportHeight(value) => {
+                morphorm::Units::Percentage(value)
+            }
         }
     }
 }
@@ -89,6 +157,39 @@ impl Units {
             &Units::Percentage(percentage) => (percentage / 100.0) * parent_value,
             &Units::Stretch(_) => auto,
             &Units::Auto => auto,
+            &Units::Rem(rem) => rem * crate::DEFAULT_ROOT_FONT_SIZE,
+            &Units::Calc(calc) => calc.resolve(parent_value),
+            &Units::ViewportWidth(percentage) | &Units::ViewportHeight(percentage) => {
+                (percentage / 100.0) * parent_value
+            }
+        }
+    }
+
+    /// Converts [`Units::Calc`] to pixels within a parent of the given size
+    pub fn resolve_calc(self, parent_value: f32) -> Self {
+        match self {
+            Units::Calc(calc) => Units::Pixels(calc.resolve(parent_value)),
+            units => units,
+        }
+    }
+
+    /// Converts [`Units::Rem`] to pixels using the given root font size
+    pub fn resolve_rem(self, root_font_size: f32) -> Self {
+        match self {
+            Units::Rem(rem) => Units::Pixels(rem * root_font_size),
+            units => units,
+        }
+    }
+
+    /// Converts [`Units::ViewportWidth`] and [`Units::ViewportHeight`] to pixels using the given
+    /// viewport size
+    pub fn resolve_viewport(self, viewport_size: (f32, f32)) -> Self {
+        match self {
+            Units::ViewportWidth(percentage) => Units::Pixels(percentage / 100.0 * viewport_size.0),
+            Units::ViewportHeight(percentage) => {
+                Units::Pixels(percentage / 100.0 * viewport_size.1)
+            }
+            units => units,
         }
     }
 
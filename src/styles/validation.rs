@@ -0,0 +1,170 @@
+use std::fmt::{Display, Formatter};
+
+use super::{KStyle, StyleProp, Units};
+
+/// A combination of style properties that is likely to lay out differently than intended
+#[derive(Debug, Clone, PartialEq)]
+pub enum StyleWarning {
+    /// A minimum size is larger than the matching maximum size, so the maximum is ignored
+    MinGreaterThanMax {
+        property: &'static str,
+        min: f32,
+        max: f32,
+    },
+    /// A size is negative, which is treated as zero
+    NegativeSize { property: &'static str, value: f32 },
+    /// Both the width and the height are fixed, so the aspect ratio has no effect
+    ConflictingAspectRatio { aspect_ratio: f32 },
+    /// A percentage size is relative to a parent that is sized to fit its content
+    PercentageOfContentSizedParent { property: &'static str },
+}
+
+impl Display for StyleWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StyleWarning::MinGreaterThanMax { property, min, max } => write!(
+                f,
+                "min_{} ({}px) is larger than max_{} ({}px)",
+                property, min, property, max
+            ),
+            StyleWarning::NegativeSize { property, value } => {
+                write!(f, "{} is negative ({}px)", property, value)
+            }
+            StyleWarning::ConflictingAspectRatio { aspect_ratio } => write!(
+                f,
+                "aspect_ratio ({}) is ignored because both width and height are set",
+                aspect_ratio
+            ),
+            StyleWarning::PercentageOfContentSizedParent { property } => write!(
+                f,
+                "{} is a percentage of a parent that is sized to fit its content",
+                property
+            ),
+        }
+    }
+}
+
+impl KStyle {
+    /// Checks these (resolved) styles for conflicting or nonsensical property combinations
+    ///
+    /// The parent's styles are needed to catch sizes that are relative to the parent. Nothing
+    /// is fixed up, since the layout is still well defined; the warnings only point at likely
+    /// mistakes.
+    pub fn validate(&self, parent: Option<&KStyle>) -> Vec<StyleWarning> {
+        let mut warnings = Vec::new();
+        let pixels = |units: &StyleProp<Units>| match units {
+            StyleProp::Value(Units::Pixels(value)) => Some(*value),
+            _ => None,
+        };
+
+        let sizes = [
+            (
+                "width",
+                "min_width",
+                &self.width,
+                &self.min_width,
+                &self.max_width,
+            ),
+            (
+                "height",
+                "min_height",
+                &self.height,
+                &self.min_height,
+                &self.max_height,
+            ),
+        ];
+        for (property, min_property, size, min, max) in sizes {
+            for (property, value) in [(property, size), (min_property, min)] {
+                if let Some(value) = pixels(value).filter(|value| *value < 0.0) {
+                    warnings.push(StyleWarning::NegativeSize { property, value });
+                }
+            }
+
+            if let (Some(min), Some(max)) = (pixels(min), pixels(max)) {
+                if min > max {
+                    warnings.push(StyleWarning::MinGreaterThanMax { property, min, max });
+                }
+            }
+
+            let parent_size = parent.map(|parent| match property {
+                "width" => &parent.width,
+                _ => &parent.height,
+            });
+            let percentage = matches!(size, StyleProp::Value(Units::Percentage(..)));
+            if percentage && matches!(parent_size, Some(StyleProp::Value(Units::Auto))) {
+                warnings.push(StyleWarning::PercentageOfContentSizedParent { property });
+            }
+        }
+
+        if let StyleProp::Value(aspect_ratio) = self.aspect_ratio {
+            let is_set = |units: &StyleProp<Units>| matches!(units, StyleProp::Value(units) if !matches!(units, Units::Auto));
+            if is_set(&self.width) && is_set(&self.height) {
+                warnings.push(StyleWarning::ConflictingAspectRatio { aspect_ratio });
+            }
+        }
+
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StyleWarning;
+    use crate::styles::{KStyle, StyleProp, Units};
+
+    #[test]
+    fn min_larger_than_max_should_warn() {
+        let styles = KStyle {
+            min_width: StyleProp::Value(Units::Pixels(200.0)),
+            max_width: StyleProp::Value(Units::Pixels(100.0)),
+            min_height: StyleProp::Value(Units::Pixels(10.0)),
+            max_height: StyleProp::Value(Units::Pixels(100.0)),
+            ..KStyle::initial()
+        };
+
+        assert_eq!(
+            vec![StyleWarning::MinGreaterThanMax {
+                property: "width",
+                min: 200.0,
+                max: 100.0
+            }],
+            styles.validate(None)
+        );
+    }
+
+    #[test]
+    fn percentage_of_content_sized_parent_should_warn() {
+        let styles = KStyle {
+            width: StyleProp::Value(Units::Percentage(50.0)),
+            height: StyleProp::Value(Units::Percentage(50.0)),
+            ..KStyle::initial()
+        };
+        let parent = KStyle {
+            width: StyleProp::Value(Units::Auto),
+            height: StyleProp::Value(Units::Pixels(100.0)),
+            ..KStyle::initial()
+        };
+
+        assert_eq!(
+            vec![StyleWarning::PercentageOfContentSizedParent { property: "width" }],
+            styles.validate(Some(&parent))
+        );
+        // The root is sized by the window
+        assert!(styles.validate(None).is_empty());
+    }
+
+    #[test]
+    fn fixed_size_with_aspect_ratio_should_warn() {
+        let styles = KStyle {
+            width: StyleProp::Value(Units::Pixels(100.0)),
+            height: StyleProp::Value(Units::Pixels(100.0)),
+            aspect_ratio: StyleProp::Value(2.0),
+            ..KStyle::initial()
+        };
+
+        assert_eq!(
+            vec![StyleWarning::ConflictingAspectRatio { aspect_ratio: 2.0 }],
+            styles.validate(None)
+        );
+    }
+}
@@ -0,0 +1,586 @@
+//! Helpers for building widget trees in tests
+
+use bevy::{
+    ecs::system::{IntoSystem, System, SystemState},
+    prelude::{Commands, Entity, Query, World},
+    time::Time,
+    utils::HashMap,
+};
+
+use crate::{
+    calculate_nodes::{calculate_layout, calculate_nodes},
+    context::{KayakRootContext, WidgetName},
+    layout::Rect,
+    node::{Node, NodeBuilder, WrappedIndex},
+    render::font::HeadlessAssets,
+    render_primitive::RenderPrimitive,
+    snapshot::TreeSnapshot,
+    styles::{ComputedStyles, KStyle, StyleProp},
+};
+
+/// Declaratively builds a widget tree for tests
+///
+/// Widgets are added to a real [`KayakRootContext`] using [`KayakRootContext::add_widget`], and
+/// each one gets a [`Node`] whose resolved styles are exactly the given styles. Widgets are
+/// referred to by name, so every name has to be unique.
+///
+/// ```ignore
+/// let tree = TreeBuilder::new()
+///     .node("root", root_styles)
+///     .child("panel", panel_styles, |panel| panel.leaf("label", label_styles))
+///     .build();
+/// let label = tree.entity("label");
+/// ```
+pub(crate) struct TreeBuilder {
+    world: World,
+    context: KayakRootContext,
+    names: HashMap<String, Entity>,
+    /// The widget that new widgets are added under
+    parent: Option<Entity>,
+}
+
+impl TreeBuilder {
+    pub fn new() -> Self {
+        Self {
+            world: World::new(),
+            context: KayakRootContext::new(),
+            names: HashMap::default(),
+            parent: None,
+        }
+    }
+
+    /// Adds the root widget, which all other widgets are added under
+    pub fn node(mut self, name: &str, styles: KStyle) -> Self {
+        assert!(self.names.is_empty(), "The root has to be added first");
+        self.parent = Some(self.spawn(name, styles));
+        self
+    }
+
+    /// Adds a widget under the current one, then uses `children` to add widgets under it
+    pub fn child(
+        mut self,
+        name: &str,
+        styles: KStyle,
+        children: impl FnOnce(Self) -> Self,
+    ) -> Self {
+        let parent = self.parent;
+        assert!(parent.is_some(), "The root has to be added first");
+        self.parent = Some(self.spawn(name, styles));
+        let mut builder = children(self);
+        builder.parent = parent;
+        builder
+    }
+
+    /// Adds a widget without children under the current one
+    pub fn leaf(self, name: &str, styles: KStyle) -> Self {
+        self.child(name, styles, |builder| builder)
+    }
+
+    /// Adds another root widget, drawn above the root, then uses `children` to add widgets under it
+    pub fn layer(
+        mut self,
+        name: &str,
+        styles: KStyle,
+        children: impl FnOnce(Self) -> Self,
+    ) -> Self {
+        let parent = self.parent.take();
+        assert!(parent.is_some(), "The root has to be added first");
+        self.parent = Some(self.spawn(name, styles));
+        let mut builder = children(self);
+        builder.parent = parent;
+        builder
+    }
+
+    pub fn build(mut self) -> TestTree {
+        if let Ok(tree) = self.context.tree.try_read() {
+            for (parent, children) in tree.children.iter() {
+                if let Some(mut node) = self.world.get_mut::<Node>(parent.0) {
+                    node.children = children.clone();
+                }
+            }
+        }
+
+        TestTree {
+            world: self.world,
+            context: self.context,
+            names: self.names,
+        }
+    }
+
+    fn spawn(&mut self, name: &str, styles: KStyle) -> Entity {
+        let entity = self.world.spawn_empty().id();
+        if self.parent.is_none() && !self.names.is_empty() {
+            self.context.add_root(entity);
+        } else {
+            self.context.add_widget(self.parent, entity);
+        }
+        let primitive = RenderPrimitive::from(&styles);
+        let mut node = NodeBuilder::empty()
+            .with_id(WrappedIndex(entity))
+            .with_styles(styles.clone(), None)
+            .with_primitive(primitive)
+            .build();
+        // Mirrors `calculate_nodes`, where widgets without a z-index are drawn in tree order
+        node.z = match styles.z_index {
+            StyleProp::Value(z_index) => z_index as f32,
+            _ => -1.0,
+        };
+        self.world.entity_mut(entity).insert((
+            node,
+            ComputedStyles(styles),
+            WidgetName(String::from(name)),
+        ));
+        assert!(
+            self.names.insert(String::from(name), entity).is_none(),
+            "A widget named {:?} already exists",
+            name
+        );
+        entity
+    }
+}
+
+/// A widget tree created by a [`TreeBuilder`]
+pub(crate) struct TestTree {
+    pub world: World,
+    pub context: KayakRootContext,
+    names: HashMap<String, Entity>,
+}
+
+impl TestTree {
+    /// Returns the entity of the widget with the given name
+    pub fn entity(&self, name: &str) -> Entity {
+        *self
+            .names
+            .get(name)
+            .unwrap_or_else(|| panic!("No widget named {:?}", name))
+    }
+
+    /// Resolves the styles of every widget and rebuilds their nodes, like a render would
+    ///
+    /// Text is measured with [`HeadlessAssets`], unless other headless fonts were already added
+    /// to the world.
+    pub fn calculate_nodes(&mut self) {
+        if !self.world.contains_resource::<HeadlessAssets>() {
+            self.world.insert_resource(HeadlessAssets::default());
+        }
+        self.world.init_resource::<Time>();
+        self.context.default_styles_changed = true;
+
+        let mut system = IntoSystem::into_system(calculate_nodes);
+        system.initialize(&mut self.world);
+        let context = std::mem::replace(&mut self.context, KayakRootContext::new());
+        self.context = system.run(context, &mut self.world);
+        system.apply_buffers(&mut self.world);
+    }
+
+    /// Lays out the whole tree using the context's layout engine
+    ///
+    /// The root is sized using its `width` and `height` styles, as if it were the window.
+    pub fn layout(&mut self) {
+        let root = self
+            .context
+            .tree
+            .try_read()
+            .ok()
+            .and_then(|tree| tree.root_node);
+        let root = root.expect("The tree has no root");
+        if let Some(node) = self.world.get::<Node>(root.0) {
+            let layout = Rect {
+                width: node.resolved_styles.width.resolve().value_or(0.0, 0.0),
+                height: node.resolved_styles.height.resolve().value_or(0.0, 0.0),
+                ..Default::default()
+            };
+            if let Ok(mut layout_cache) = self.context.layout_cache.try_write() {
+                layout_cache.rect.insert(root, layout);
+            }
+        }
+        self.context.dirty_layout_nodes.insert(root);
+        self.relayout();
+    }
+
+    /// Lays out only the widgets around those marked as needing layout, like a frame would
+    pub fn relayout(&mut self) {
+        let mut system = IntoSystem::into_system(calculate_layout);
+        system.initialize(&mut self.world);
+        let context = std::mem::replace(&mut self.context, KayakRootContext::new());
+        self.context = system.run(context, &mut self.world);
+        system.apply_buffers(&mut self.world);
+    }
+
+    /// Overrides the layout of the widget with the given name
+    pub fn set_layout(&mut self, name: &str, layout: Rect) {
+        let index = WrappedIndex(self.entity(name));
+        if let Ok(mut layout_cache) = self.context.layout_cache.try_write() {
+            layout_cache.rect.insert(index, layout);
+        }
+    }
+
+    /// Returns the layout of the widget with the given name
+    pub fn layout_of(&self, name: &str) -> Rect {
+        let index = WrappedIndex(self.entity(name));
+        self.context
+            .layout_cache
+            .try_read()
+            .ok()
+            .and_then(|layout_cache| layout_cache.rect.get(&index).copied())
+            .unwrap_or_else(|| panic!("The widget named {:?} has no layout", name))
+    }
+
+    /// Runs `f` with access to the nodes of the tree, then applies any commands it queued
+    pub fn with_nodes<R>(
+        &mut self,
+        f: impl FnOnce(&mut KayakRootContext, &mut Commands, &Query<&Node>) -> R,
+    ) -> R {
+        let mut state = SystemState::<(Commands, Query<&Node>)>::new(&mut self.world);
+        let (mut commands, nodes) = state.get_mut(&mut self.world);
+        let result = f(&mut self.context, &mut commands, &nodes);
+        state.apply(&mut self.world);
+        result
+    }
+
+    /// Builds the render primitives of the tree, in paint order
+    pub fn render_primitives(&mut self) -> Vec<RenderPrimitive> {
+        let mut state = SystemState::<(Query<&Node>, Query<&WidgetName>)>::new(&mut self.world);
+        let (nodes, widget_names) = state.get(&self.world);
+        self.context.build_render_primitives(&nodes, &widget_names)
+    }
+
+    /// Snapshots the tree and its layout
+    pub fn dump_tree(&mut self) -> TreeSnapshot {
+        let mut state = SystemState::<(Query<&Node>, Query<&WidgetName>)>::new(&mut self.world);
+        let (nodes, widget_names) = state.get(&self.world);
+        self.context.dump_tree(&nodes, &widget_names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::Color;
+    use kayak_font::Alignment;
+
+    use super::{TestTree, TreeBuilder};
+    use crate::{
+        layout::{GeometryChanged, IntrinsicSize},
+        node::{Node, WrappedIndex},
+        render_primitive::RenderPrimitive,
+        styles::{AlignItems, ComputedStyles, KStyle, LayoutType, RenderCommand, StyleProp, Units},
+    };
+
+    fn sized(width: f32, height: f32) -> KStyle {
+        KStyle {
+            width: StyleProp::Value(Units::Pixels(width)),
+            height: StyleProp::Value(Units::Pixels(height)),
+            ..KStyle::initial()
+        }
+    }
+
+    #[test]
+    fn tree_builder_should_nest_children() {
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(800.0, 600.0))
+            .child("panel", sized(200.0, 100.0), |panel| {
+                panel.leaf("label", sized(50.0, 20.0))
+            })
+            .leaf("footer", sized(800.0, 40.0))
+            .build();
+
+        let (root, panel, label, footer) = (
+            tree.entity("root"),
+            tree.entity("panel"),
+            tree.entity("label"),
+            tree.entity("footer"),
+        );
+        if let Ok(node_tree) = tree.context.tree.try_read() {
+            assert_eq!(Some(root), node_tree.root_node.map(|index| index.0));
+            assert_eq!(
+                Some(WrappedIndex(panel)),
+                node_tree.get_parent(WrappedIndex(label))
+            );
+            assert_eq!(
+                Some(WrappedIndex(root)),
+                node_tree.get_parent(WrappedIndex(footer))
+            );
+        }
+
+        tree.layout();
+        assert_eq!(200.0, tree.layout_of("panel").width);
+        assert_eq!(50.0, tree.layout_of("label").width);
+        assert_eq!(40.0, tree.layout_of("footer").height);
+    }
+
+    #[test]
+    fn headless_assets_should_measure_text_without_fonts() {
+        let label = KStyle {
+            font_size: StyleProp::Value(20.0),
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Hello"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            ..KStyle::default()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(800.0, 600.0))
+            .leaf("label", label)
+            .build();
+
+        tree.calculate_nodes();
+        tree.layout();
+
+        // Five glyphs, each half as wide as the font size
+        assert_eq!(50.0, tree.layout_of("label").width);
+        assert!(tree.render_primitives().iter().any(|primitive| matches!(
+            primitive,
+            RenderPrimitive::Text { content, .. } if content == "Hello"
+        )));
+    }
+
+    #[test]
+    fn initial_color_should_break_inheritance() {
+        let colored = |color| KStyle {
+            color,
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", colored(StyleProp::Value(Color::RED)))
+            .child("panel", colored(StyleProp::Initial), |panel| {
+                panel.leaf("label", KStyle::initial())
+            })
+            .leaf("footer", KStyle::initial())
+            .build();
+        tree.context.set_default_style(KStyle {
+            color: StyleProp::Value(Color::BLACK),
+            ..Default::default()
+        });
+
+        tree.calculate_nodes();
+
+        let color = |tree: &TestTree, name| {
+            tree.world
+                .get::<Node>(tree.entity(name))
+                .map(|node| node.resolved_styles.color.clone())
+        };
+        assert_eq!(Some(StyleProp::Value(Color::BLACK)), color(&tree, "panel"));
+        assert_eq!(Some(StyleProp::Value(Color::BLACK)), color(&tree, "label"));
+        assert_eq!(Some(StyleProp::Value(Color::RED)), color(&tree, "footer"));
+    }
+
+    #[test]
+    fn intrinsic_size_should_floor_squeezed_widgets() {
+        let stretched = |content: Option<(&str, bool)>| KStyle {
+            width: StyleProp::Value(Units::Stretch(1.0)),
+            font_size: StyleProp::Value(20.0),
+            render_command: StyleProp::Value(match content {
+                Some((content, word_wrap)) => RenderCommand::Text {
+                    content: String::from(content),
+                    alignment: Alignment::Start,
+                    word_wrap,
+                    subpixel: false,
+                },
+                None => RenderCommand::Layout,
+            }),
+            ..KStyle::initial()
+        };
+        // Text is measured within its closest parent that renders something
+        let column = KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            ..sized(30.0, 600.0)
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(800.0, 600.0))
+            .child("column", column, |column| {
+                column
+                    .leaf("label", stretched(Some(("Hello", false))))
+                    .leaf("paragraph", stretched(Some(("Hello there", true))))
+                    .leaf("button", stretched(None))
+            })
+            .build();
+        let button = tree.entity("button");
+        tree.world
+            .entity_mut(button)
+            .insert(IntrinsicSize((80.0, 40.0)));
+
+        // Text is measured once its parent has been laid out
+        for _ in 0..2 {
+            tree.calculate_nodes();
+            tree.layout();
+        }
+
+        // The unwrapped label is five glyphs, each half as wide as the font size
+        assert_eq!(50.0, tree.layout_of("label").width);
+        // Wrapped text fits any width, so it's left to be squeezed
+        assert_eq!(30.0, tree.layout_of("paragraph").width);
+        assert_eq!(80.0, tree.layout_of("button").width);
+    }
+
+    #[test]
+    fn content_sized_button_should_fit_its_text() {
+        let button = KStyle {
+            width: StyleProp::Value(Units::Auto),
+            height: StyleProp::Value(Units::Auto),
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            ..KStyle::initial()
+        };
+        let label = KStyle {
+            font_size: StyleProp::Value(20.0),
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("Hello"),
+                alignment: Alignment::Start,
+                word_wrap: true,
+                subpixel: false,
+            }),
+            ..KStyle::initial()
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", sized(800.0, 600.0))
+            .child("button", button, |button| button.leaf("label", label))
+            .build();
+
+        // The button is laid out before the text in it can be measured
+        for _ in 0..2 {
+            tree.calculate_nodes();
+            tree.layout();
+        }
+
+        // The text is measured against the root, since the button has no width of its own
+        assert_eq!(50.0, tree.layout_of("label").width);
+        assert_eq!(50.0, tree.layout_of("button").width);
+    }
+
+    #[test]
+    fn hidden_subtree_should_not_be_laid_out_or_rendered() {
+        let quad = |width, height| KStyle {
+            render_command: StyleProp::Value(RenderCommand::Quad),
+            background_color: StyleProp::Value(Color::WHITE),
+            ..sized(width, height)
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", quad(800.0, 600.0))
+            .child("panel", quad(200.0, 100.0), |panel| {
+                panel.leaf("field", quad(50.0, 20.0))
+            })
+            .leaf("footer", quad(800.0, 40.0))
+            .build();
+        let (panel, field) = (tree.entity("panel"), tree.entity("field"));
+
+        tree.with_nodes(|context, commands, _| {
+            context.set_hidden(commands, panel, true);
+            assert!(context.is_hidden(field));
+        });
+        tree.calculate_nodes();
+        tree.layout();
+
+        let quads = |tree: &mut TestTree| {
+            tree.render_primitives()
+                .iter()
+                .filter(|primitive| matches!(primitive, RenderPrimitive::Quad { .. }))
+                .count()
+        };
+        assert_eq!(2, quads(&mut tree));
+        assert_eq!(2, tree.context.last_stats().layout_nodes);
+        let field_layout = tree
+            .context
+            .layout_cache
+            .try_read()
+            .ok()
+            .and_then(|layout_cache| layout_cache.rect.get(&WrappedIndex(field)).copied());
+        assert_eq!(None, field_layout);
+
+        tree.with_nodes(|context, commands, _| context.set_hidden(commands, panel, false));
+        tree.calculate_nodes();
+        tree.layout();
+        assert_eq!(4, quads(&mut tree));
+        assert_eq!(4, tree.context.last_stats().layout_nodes);
+    }
+
+    #[test]
+    fn baseline_row_should_align_text_baselines() {
+        let label = |font_size| KStyle {
+            font_size: StyleProp::Value(font_size),
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("42"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            ..KStyle::default()
+        };
+        let mut tree = TreeBuilder::new()
+            .node(
+                "root",
+                KStyle {
+                    layout_type: StyleProp::Value(LayoutType::Row),
+                    align_items: StyleProp::Value(AlignItems::Baseline),
+                    ..sized(800.0, 600.0)
+                },
+            )
+            .leaf("small", label(12.0))
+            .leaf("large", label(24.0))
+            .build();
+
+        tree.calculate_nodes();
+        tree.layout();
+
+        let (small, large) = (tree.layout_of("small"), tree.layout_of("large"));
+        assert_eq!(small.posy + 12.0, large.posy + 24.0);
+        assert!(tree.render_primitives().iter().any(|primitive| matches!(
+            primitive,
+            RenderPrimitive::Text { layout, .. } if layout.posy == small.posy
+        )));
+    }
+
+    #[test]
+    fn switching_to_baseline_alignment_should_realign_the_row() {
+        let label = |font_size| KStyle {
+            font_size: StyleProp::Value(font_size),
+            render_command: StyleProp::Value(RenderCommand::Text {
+                content: String::from("42"),
+                alignment: Alignment::Start,
+                word_wrap: false,
+                subpixel: false,
+            }),
+            ..KStyle::default()
+        };
+        let row = |align_items| KStyle {
+            layout_type: StyleProp::Value(LayoutType::Row),
+            align_items: StyleProp::Value(align_items),
+            ..sized(800.0, 600.0)
+        };
+        let mut tree = TreeBuilder::new()
+            .node("root", row(AlignItems::Normal))
+            .leaf("small", label(12.0))
+            .leaf("large", label(24.0))
+            .build();
+        tree.calculate_nodes();
+        tree.layout();
+        assert_eq!(tree.layout_of("small").posy, tree.layout_of("large").posy);
+
+        let (root, small) = (tree.entity("root"), tree.entity("small"));
+        tree.world
+            .entity_mut(root)
+            .insert(ComputedStyles(row(AlignItems::Baseline)));
+        tree.calculate_nodes();
+        assert!(tree
+            .context
+            .dirty_layout_nodes
+            .contains(&WrappedIndex(root)));
+        tree.relayout();
+
+        let (small_layout, large_layout) = (tree.layout_of("small"), tree.layout_of("large"));
+        assert_eq!(small_layout.posy + 12.0, large_layout.posy + 24.0);
+        let moved = tree
+            .context
+            .layout_cache
+            .try_read()
+            .ok()
+            .and_then(|layout_cache| {
+                layout_cache
+                    .geometry_changed
+                    .get(&WrappedIndex(small))
+                    .copied()
+            })
+            .unwrap_or_default();
+        assert!(moved.contains(GeometryChanged::POSY_CHANGED));
+    }
+}
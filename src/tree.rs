@@ -1,6 +1,7 @@
 use bevy::prelude::Entity;
-use bevy::utils::HashMap;
+use bevy::utils::{HashMap, HashSet};
 use morphorm::Hierarchy;
+use std::cmp::Ordering;
 use std::iter::Rev;
 
 use crate::node::WrappedIndex;
@@ -10,6 +11,10 @@ pub struct Tree {
     pub children: HashMap<WrappedIndex, Vec<WrappedIndex>>,
     pub parents: HashMap<WrappedIndex, WrappedIndex>,
     pub root_node: Option<WrappedIndex>,
+    /// Additional roots, each drawn above the root node and the layers added before it
+    pub layers: Vec<WrappedIndex>,
+    /// The roots of subtrees that are left out of layout and rendering
+    pub hidden: HashSet<WrappedIndex>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -59,8 +64,69 @@ impl Tree {
         }
     }
 
+    /// Adds the given node as a new root, or as the root node if there isn't one yet
+    ///
+    /// Every root after the first is a layer with its own subtree, drawn above the roots added
+    /// before it.
+    pub fn add_root(&mut self, index: WrappedIndex) {
+        if self.root_node.is_none() {
+            self.root_node = Some(index);
+        } else if self.root_node != Some(index) && !self.layers.contains(&index) {
+            self.layers.push(index);
+        }
+    }
+
+    /// Iterates over the root node followed by every layer, in the order they were added
+    pub fn roots(&self) -> impl Iterator<Item = WrappedIndex> + '_ {
+        self.root_node
+            .into_iter()
+            .chain(self.layers.iter().copied())
+    }
+
+    /// Iterates over the roots that aren't hidden
+    pub fn visible_roots(&self) -> impl Iterator<Item = WrappedIndex> + '_ {
+        self.roots().filter(|root| !self.hidden.contains(root))
+    }
+
+    /// Iterates over the children of the given node that aren't hidden
+    pub fn visible_children(&self, index: WrappedIndex) -> impl Iterator<Item = WrappedIndex> + '_ {
+        self.children
+            .get(&index)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|child| !self.hidden.contains(child))
+    }
+
+    /// Lists the given node and its descendants depth first, leaving out hidden subtrees
+    pub fn visible_nodes_at(&self, index: WrappedIndex) -> Vec<WrappedIndex> {
+        let mut nodes = Vec::new();
+        if self.hidden.contains(&index) {
+            return nodes;
+        }
+        let mut stack = vec![index];
+        while let Some(current) = stack.pop() {
+            nodes.push(current);
+            if let Some(children) = self.children.get(&current) {
+                stack.extend(
+                    children
+                        .iter()
+                        .rev()
+                        .filter(|child| !self.hidden.contains(child)),
+                );
+            }
+        }
+        nodes
+    }
+
+    /// Returns true if the given node is the root node or the root of a layer
+    pub fn is_root(&self, index: WrappedIndex) -> bool {
+        self.root_node == Some(index) || self.layers.contains(&index)
+    }
+
     /// Remove the given node and recursively removes its descendants
     pub fn remove(&mut self, index: WrappedIndex) -> Vec<WrappedIndex> {
+        self.hidden.remove(&index);
         let parent = self.parents.remove(&index);
         if let Some(parent) = parent {
             let children = self
@@ -75,6 +141,14 @@ impl Tree {
             }
 
             children
+        } else if self.layers.contains(&index) {
+            self.layers.retain(|layer| *layer != index);
+            self.children
+                .remove(&index)
+                .unwrap_or_default()
+                .into_iter()
+                .flat_map(|child| self.remove(child))
+                .collect()
         } else {
             // Is root node
             if let Some(root_node) = self.root_node {
@@ -108,19 +182,50 @@ impl Tree {
             }
 
             // === Reparent Children === //
-            if let Some(children) = self.children.remove(&index) {
-                for child in children.iter() {
-                    self.parents.insert(*child, parent);
-                }
-                if let Some(siblings) = self.children.get_mut(&parent) {
-                    siblings.splice(insertion_index..insertion_index + 1, children);
-                }
+            let children = self.children.remove(&index).unwrap_or_default();
+            for child in children.iter() {
+                self.parents.insert(*child, parent);
+            }
+            if let Some(siblings) = self.children.get_mut(&parent) {
+                siblings.splice(insertion_index..insertion_index + 1, children);
             }
         } else {
             panic!("Cannot reparent a root node's children")
         }
     }
 
+    /// Moves a node, along with its descendants, under a new parent at the given position
+    /// among its children
+    ///
+    /// The position is clamped to the number of children. Returns false, leaving the tree
+    /// untouched, if either node isn't in the tree, the node is a root, or the new parent is
+    /// the node itself or one of its descendants.
+    pub fn reparent(
+        &mut self,
+        index: WrappedIndex,
+        new_parent: WrappedIndex,
+        position: usize,
+    ) -> bool {
+        if !self.contains(new_parent)
+            || new_parent == index
+            || self.is_descendant(new_parent, index)
+        {
+            return false;
+        }
+        let old_parent = match self.parents.get(&index) {
+            Some(old_parent) => *old_parent,
+            None => return false,
+        };
+
+        if let Some(siblings) = self.children.get_mut(&old_parent) {
+            siblings.retain(|node| *node != index);
+        }
+        let siblings = self.children.entry(new_parent).or_default();
+        siblings.insert(position.min(siblings.len()), index);
+        self.parents.insert(index, new_parent);
+        true
+    }
+
     /// Replace the given node with another, transferring the parent and child relationships over to the replacement node
     pub fn replace(&mut self, index: WrappedIndex, replace_with: WrappedIndex) {
         // === Update Parent === //
@@ -145,7 +250,7 @@ impl Tree {
 
     /// Returns true if the given node is in this tree
     pub fn contains(&self, index: WrappedIndex) -> bool {
-        Some(index) == self.root_node
+        self.is_root(index)
             || self.parents.contains_key(&index)
             || self.children.contains_key(&index)
     }
@@ -153,7 +258,7 @@ impl Tree {
     /// Get the number of nodes in this tree
     pub fn len(&self) -> usize {
         if self.root_node.is_some() {
-            self.parents.len() + 1
+            self.parents.len() + 1 + self.layers.len()
         } else {
             0
         }
@@ -161,19 +266,60 @@ impl Tree {
 
     /// Returns true if this tree has no nodes
     pub fn is_empty(&self) -> bool {
-        self.root_node.is_none() && self.parents.is_empty() && self.children.is_empty()
+        self.root_node.is_none()
+            && self.layers.is_empty()
+            && self.parents.is_empty()
+            && self.children.is_empty()
     }
 
     /// Returns true if the given node is a descendant of another node
     pub fn is_descendant(&self, descendant: WrappedIndex, of_node: WrappedIndex) -> bool {
-        let mut index = descendant;
-        while let Some(parent) = self.get_parent(index) {
-            index = parent;
-            if parent == of_node {
-                return true;
+        UpwardIterator::new(self, Some(descendant), false).any(|parent| parent == of_node)
+    }
+
+    /// Compares the positions of two nodes in a depth-first traversal of the tree
+    ///
+    /// Ancestors are ordered before their descendants, and nodes in a layer are ordered after
+    /// those in the roots added before it. Nodes that don't share a root with the tree are
+    /// considered equal.
+    pub fn cmp_order(&self, a: WrappedIndex, b: WrappedIndex) -> Ordering {
+        if a == b {
+            return Ordering::Equal;
+        }
+
+        let path_a = self.path_to(a);
+        let path_b = self.path_to(b);
+        if path_a.first() != path_b.first() {
+            let root_position = |path: &[WrappedIndex]| {
+                path.first()
+                    .and_then(|first| self.roots().position(|root| root == *first))
+            };
+            return match (root_position(&path_a), root_position(&path_b)) {
+                (Some(root_a), Some(root_b)) => root_a.cmp(&root_b),
+                _ => Ordering::Equal,
+            };
+        }
+
+        for (depth, (node_a, node_b)) in path_a.iter().zip(path_b.iter()).enumerate() {
+            if node_a != node_b {
+                // Both nodes share the parent at the previous depth
+                let siblings = self.children.get(&path_a[depth - 1]);
+                let position = |node: &WrappedIndex| {
+                    siblings.and_then(|siblings| siblings.iter().position(|child| child == node))
+                };
+                return position(node_a).cmp(&position(node_b));
             }
         }
-        false
+
+        // One node is an ancestor of the other
+        path_a.len().cmp(&path_b.len())
+    }
+
+    /// Returns the list of nodes from the topmost ancestor down to the given node
+    fn path_to(&self, index: WrappedIndex) -> Vec<WrappedIndex> {
+        let mut path = UpwardIterator::new(self, Some(index), true).collect::<Vec<_>>();
+        path.reverse();
+        path
     }
 
     pub fn flatten(&self) -> Vec<WrappedIndex> {
@@ -184,6 +330,21 @@ impl Tree {
         DownwardIterator::new(self, Some(self.root_node.unwrap()), true).collect::<Vec<_>>()
     }
 
+    /// Copies the given node and all of its visible descendants into a new tree, rooted at that
+    /// node
+    pub fn subtree(&self, root_node: WrappedIndex) -> Tree {
+        let mut subtree = Tree::default();
+        subtree.add(root_node, None);
+        let mut stack = vec![root_node];
+        while let Some(parent) = stack.pop() {
+            for child in self.visible_children(parent) {
+                subtree.add(child, Some(parent));
+                stack.push(child);
+            }
+        }
+        subtree
+    }
+
     pub fn flatten_node(&self, root_node: WrappedIndex) -> Vec<WrappedIndex> {
         if self.root_node.is_none() {
             return Vec::new();
@@ -671,6 +832,8 @@ pub struct DownwardIterator<'a> {
     pub starting_node: Option<WrappedIndex>,
     pub current_node: Option<WrappedIndex>,
     pub include_self: bool,
+    /// The roots to continue with once the current subtree is done
+    pub next_roots: &'a [WrappedIndex],
 }
 
 impl<'a> DownwardIterator<'a> {
@@ -691,14 +854,17 @@ impl<'a> DownwardIterator<'a> {
             starting_node,
             current_node: starting_node,
             include_self,
+            next_roots: &[],
         }
     }
-}
 
-impl<'a> Iterator for DownwardIterator<'a> {
-    type Item = WrappedIndex;
+    /// Traverses the subtree of each of the given roots after the starting node's own
+    pub fn then_roots(mut self, roots: &'a [WrappedIndex]) -> Self {
+        self.next_roots = roots;
+        self
+    }
 
-    fn next(&mut self) -> Option<Self::Item> {
+    fn next_in_subtree(&mut self) -> Option<WrappedIndex> {
         if self.include_self {
             self.include_self = false;
             return self.current_node;
@@ -742,12 +908,32 @@ impl<'a> Iterator for DownwardIterator<'a> {
     }
 }
 
+impl<'a> Iterator for DownwardIterator<'a> {
+    type Item = WrappedIndex;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(next) = self.next_in_subtree() {
+                return Some(next);
+            }
+
+            let (root, next_roots) = self.next_roots.split_first()?;
+            self.next_roots = next_roots;
+            self.starting_node = Some(*root);
+            self.current_node = Some(*root);
+            self.include_self = true;
+        }
+    }
+}
+
 /// An iterator that performs a single-path traversal up a tree starting
 /// from a given node.
 pub struct UpwardIterator<'a> {
     tree: &'a Tree,
     current_node: Option<WrappedIndex>,
     include_self: bool,
+    /// How many more parents can be visited before the parents must have formed a cycle
+    remaining_depth: usize,
 }
 
 impl<'a> UpwardIterator<'a> {
@@ -759,6 +945,8 @@ impl<'a> UpwardIterator<'a> {
     /// * `starting_node`: The node to start iterating from.
     /// * `include_self`: Whether or not to include the starting node in the output.
     ///
+    /// No node has more ancestors than the tree has parents, so if the parents of a malformed
+    /// tree form a cycle, iteration stops once it has gone up that many times.
     ///
     /// [tree]: Tree
     /// [node]: WrappedIndex
@@ -767,6 +955,7 @@ impl<'a> UpwardIterator<'a> {
             tree,
             current_node: starting_node,
             include_self,
+            remaining_depth: tree.parents.len(),
         }
     }
 }
@@ -780,7 +969,18 @@ impl<'a> Iterator for UpwardIterator<'a> {
             return self.current_node;
         }
 
-        self.current_node = self.tree.get_parent(self.current_node?);
+        let parent = self.tree.get_parent(self.current_node?)?;
+        if self.remaining_depth == 0 {
+            log::error!(
+                "Detected a cycle in the parents of node: {}",
+                parent.0.index()
+            );
+            self.current_node = None;
+            return None;
+        }
+        self.remaining_depth -= 1;
+
+        self.current_node = Some(parent);
         self.current_node
     }
 }
@@ -815,7 +1015,7 @@ impl<'a> Hierarchy<'a> for Tree {
     }
 
     fn down_iter(&'a self) -> Self::DownIter {
-        DownwardIterator::new(self, self.root_node, true)
+        DownwardIterator::new(self, self.root_node, true).then_roots(&self.layers)
     }
 
     fn child_iter(&'a self, node: WrappedIndex) -> Self::ChildIter {
@@ -857,11 +1057,80 @@ impl<'a> Hierarchy<'a> for Tree {
     }
 }
 
+/// A view of a tree that leaves out its hidden subtrees, which is the hierarchy that gets laid out
+#[derive(Debug, Clone, Copy)]
+pub struct VisibleTree<'a>(pub &'a Tree);
+
+/// Iterates over the children of a node that aren't hidden
+pub struct VisibleChildIterator<'a> {
+    children: std::slice::Iter<'a, WrappedIndex>,
+    hidden: &'a HashSet<WrappedIndex>,
+}
+
+impl<'a> Iterator for VisibleChildIterator<'a> {
+    type Item = WrappedIndex;
+    fn next(&mut self) -> Option<Self::Item> {
+        let hidden = self.hidden;
+        self.children
+            .find(|child| !hidden.contains(*child))
+            .copied()
+    }
+}
+
+impl<'a, 't: 'a> Hierarchy<'a> for VisibleTree<'t> {
+    type DownIter = std::vec::IntoIter<WrappedIndex>;
+    type UpIter = Rev<std::vec::IntoIter<WrappedIndex>>;
+    type Item = WrappedIndex;
+    type ChildIter = VisibleChildIterator<'a>;
+
+    fn up_iter(&'a self) -> Self::UpIter {
+        // Morphorm expects the iteration to be the same as Self::DownIter but "in reverse".
+        self.down_iter().rev()
+    }
+
+    fn down_iter(&'a self) -> Self::DownIter {
+        self.0
+            .visible_roots()
+            .flat_map(|root| self.0.visible_nodes_at(root))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    fn child_iter(&'a self, node: WrappedIndex) -> Self::ChildIter {
+        VisibleChildIterator {
+            children: self
+                .0
+                .children
+                .get(&node)
+                .map(|children| children.iter())
+                .unwrap_or_else(|| [].iter()),
+            hidden: &self.0.hidden,
+        }
+    }
+
+    fn parent(&self, node: WrappedIndex) -> Option<WrappedIndex> {
+        self.0.get_parent(node)
+    }
+
+    fn is_first_child(&self, node: WrappedIndex) -> bool {
+        self.parent(node)
+            .and_then(|parent| self.0.visible_children(parent).next())
+            == Some(node)
+    }
+
+    fn is_last_child(&self, node: WrappedIndex) -> bool {
+        self.parent(node)
+            .and_then(|parent| self.0.visible_children(parent).last())
+            == Some(node)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::tree::{DownwardIterator, UpwardIterator};
     use crate::tree::{Tree, WrappedIndex};
     use bevy::prelude::Entity;
+    use morphorm::Hierarchy;
 
     #[test]
     fn should_descend_tree() {
@@ -989,6 +1258,57 @@ mod tests {
         assert_ascent!("G": g -> [d, b, a]);
     }
 
+    #[test]
+    fn root_should_survive_removing_and_re_adding_widgets() {
+        let mut tree = Tree::default();
+
+        let a = WrappedIndex(Entity::from_raw(0));
+        let b = WrappedIndex(Entity::from_raw(1));
+        let c = WrappedIndex(Entity::from_raw(2));
+
+        tree.add(a, None);
+        tree.add(b, Some(a));
+        tree.add(c, Some(a));
+
+        tree.remove(b);
+        tree.remove(c);
+        assert_eq!(Some(a), tree.root_node);
+
+        // Re-adding in a different order (as happens when entity slots are reused)
+        tree.add(c, Some(a));
+        tree.add(b, Some(c));
+        assert_eq!(Some(a), tree.root_node);
+        assert_eq!(Some(a), tree.get_parent(c));
+        assert_eq!(Some(c), tree.get_parent(b));
+        assert_eq!(vec![a, c, b], tree.down_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn subtree_should_only_contain_descendants() {
+        let mut tree = Tree::default();
+        let root = WrappedIndex(Entity::from_raw(0));
+        let child_a = WrappedIndex(Entity::from_raw(1));
+        let child_b = WrappedIndex(Entity::from_raw(2));
+        let grandchild_a = WrappedIndex(Entity::from_raw(3));
+        let grandchild_b = WrappedIndex(Entity::from_raw(4));
+        tree.add(root, None);
+        tree.add(child_a, Some(root));
+        tree.add(child_b, Some(root));
+        tree.add(grandchild_a, Some(child_a));
+        tree.add(grandchild_b, Some(child_a));
+
+        let subtree = tree.subtree(child_a);
+        assert_eq!(Some(child_a), subtree.root_node);
+        assert_eq!(None, subtree.get_parent(child_a));
+        assert_eq!(
+            vec![child_a, grandchild_a, grandchild_b],
+            subtree.down_iter().collect::<Vec<_>>()
+        );
+
+        let leaf = tree.subtree(child_b);
+        assert_eq!(1, leaf.len());
+    }
+
     #[test]
     fn should_replace() {
         let mut tree = Tree::default();
@@ -1197,4 +1517,61 @@ mod tests {
         tree.add(grandchild, Some(child));
         assert_eq!(3, tree.len());
     }
+
+    #[test]
+    fn should_stop_at_parent_cycle() {
+        let mut tree = Tree::default();
+
+        let a = WrappedIndex(Entity::from_raw(0));
+        let b = WrappedIndex(Entity::from_raw(1));
+        let c = WrappedIndex(Entity::from_raw(2));
+
+        // A malformed tree where A and B are each other's parent
+        tree.parents.insert(a, b);
+        tree.parents.insert(b, a);
+
+        // Iteration stops after going up once for each parent in the tree
+        let ancestors = UpwardIterator::new(&tree, Some(a), true).collect::<Vec<_>>();
+        assert_eq!(vec![a, b, a], ancestors);
+        assert!(tree.is_descendant(a, b));
+        assert!(!tree.is_descendant(a, c));
+        assert_eq!(std::cmp::Ordering::Equal, tree.cmp_order(a, c));
+    }
+
+    #[test]
+    fn layers_should_follow_the_root() {
+        let mut tree = Tree::default();
+
+        let root = WrappedIndex(Entity::from_raw(0));
+        let root_child = WrappedIndex(Entity::from_raw(1));
+        let hud = WrappedIndex(Entity::from_raw(2));
+        let hud_child = WrappedIndex(Entity::from_raw(3));
+        let tooltip = WrappedIndex(Entity::from_raw(4));
+
+        tree.add_root(root);
+        tree.add(root_child, Some(root));
+        tree.add_root(hud);
+        tree.add(hud_child, Some(hud));
+        tree.add_root(tooltip);
+
+        assert_eq!(vec![root, hud, tooltip], tree.roots().collect::<Vec<_>>());
+        assert_eq!(
+            vec![root, root_child, hud, hud_child, tooltip],
+            tree.down_iter().collect::<Vec<_>>()
+        );
+        assert_eq!(5, tree.len());
+        assert!(tree.contains(tooltip));
+        assert_eq!(
+            std::cmp::Ordering::Less,
+            tree.cmp_order(root_child, hud_child)
+        );
+
+        tree.remove(hud);
+        assert!(!tree.contains(hud));
+        assert!(!tree.contains(hud_child));
+        assert_eq!(
+            vec![root, root_child, tooltip],
+            tree.down_iter().collect::<Vec<_>>()
+        );
+    }
 }
@@ -72,7 +72,7 @@ impl<'w, 's, Props: PartialEq + Component, State: PartialEq + Component>
         current_entity: Entity,
         previous_entity: Entity,
     ) -> bool {
-        if !self.mounted_query.is_empty() {
+        if self.mounted_query.contains(current_entity) {
             log::trace!(
                 "Entity was mounted! {}-{}",
                 self.widget_names.get(current_entity).unwrap().0,
@@ -168,3 +168,69 @@ impl<'w, 's, Props: PartialEq + Component, State: PartialEq + Component>
         false
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        ecs::system::System,
+        prelude::{Component, IntoSystem, World},
+    };
+
+    use super::{widget_update, EmptyState};
+    use crate::{
+        context::{Mounted, WidgetName},
+        context_entities::ContextEntities,
+        prelude::KayakWidgetContext,
+    };
+
+    #[derive(Component, Clone, PartialEq)]
+    struct TestProps(u32);
+
+    fn widget_context() -> KayakWidgetContext {
+        KayakWidgetContext::new(
+            Default::default(),
+            ContextEntities::new(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            None,
+        )
+    }
+
+    #[test]
+    fn unchanged_props_should_not_rerender() {
+        let mut world = World::new();
+        let current = world
+            .spawn((TestProps(1), WidgetName(String::from("TestWidget"))))
+            .id();
+        let previous = world.spawn(TestProps(1)).id();
+        // Other widgets being mounted shouldn't cause this one to re-render
+        world.spawn(Mounted);
+
+        let mut system = IntoSystem::into_system(widget_update::<TestProps, EmptyState>);
+        system.initialize(&mut world);
+        assert!(!system.run((widget_context(), current, previous), &mut world));
+
+        world.entity_mut(current).insert(TestProps(2));
+        assert!(system.run((widget_context(), current, previous), &mut world));
+    }
+
+    #[test]
+    fn mounted_widget_should_rerender() {
+        let mut world = World::new();
+        let current = world
+            .spawn((
+                TestProps(1),
+                WidgetName(String::from("TestWidget")),
+                Mounted,
+            ))
+            .id();
+        let previous = world.spawn(TestProps(1)).id();
+
+        let mut system = IntoSystem::into_system(widget_update::<TestProps, EmptyState>);
+        system.initialize(&mut world);
+        assert!(system.run((widget_context(), current, previous), &mut world));
+    }
+}
@@ -1,14 +1,14 @@
 use std::sync::{Arc, RwLock};
 
 use bevy::{
-    prelude::{Commands, Component, Entity},
+    prelude::{Commands, Component, Entity, World},
     utils::HashMap,
 };
 use morphorm::Hierarchy;
 
 use crate::{
-    context_entities::ContextEntities, layout::LayoutCache, node::WrappedIndex, prelude::Tree,
-    widget_state::WidgetState,
+    context_entities::ContextEntities, layout::LayoutCache, node::WrappedIndex,
+    on_unmount::UnmountCallbacks, prelude::Tree, widget_state::WidgetState,
 };
 
 /// KayakWidgetContext manages tree, state, and context updates within a single widget.
@@ -26,6 +26,7 @@ pub struct KayakWidgetContext {
     pub(crate) index: Arc<RwLock<HashMap<Entity, usize>>>,
     widget_state: WidgetState,
     order_tree: Arc<RwLock<Tree>>,
+    unmount_callbacks: UnmountCallbacks,
     pub camera_entity: Option<Entity>,
 }
 
@@ -37,6 +38,7 @@ impl KayakWidgetContext {
         widget_state: WidgetState,
         order_tree: Arc<RwLock<Tree>>,
         index: Arc<RwLock<HashMap<Entity, usize>>>,
+        unmount_callbacks: UnmountCallbacks,
         camera_entity: Option<Entity>,
     ) -> Self {
         Self {
@@ -47,6 +49,7 @@ impl KayakWidgetContext {
             index,
             widget_state,
             order_tree,
+            unmount_callbacks,
             camera_entity,
         }
     }
@@ -171,6 +174,16 @@ impl KayakWidgetContext {
         self.widget_state.get(widget_entity)
     }
 
+    /// Registers a callback that is ran when the given widget is removed from the tree.
+    /// This is useful for releasing external resources tied to a widget's lifetime.
+    pub fn on_unmount(
+        &self,
+        widget_entity: Entity,
+        callback: impl Fn(&mut World, Entity) + Send + Sync + 'static,
+    ) {
+        self.unmount_callbacks.add(widget_entity, callback);
+    }
+
     /// Returns a new/existing widget entity.
     /// Because a re-render can potentially spawn new entities it's advised to use this
     /// to avoid creating a new entity.
@@ -45,4 +45,13 @@ impl WidgetState {
 
         None
     }
+
+    /// Removes the mapping for the given widget, returning its state entity if one existed
+    pub fn remove(&self, widget_entity: Entity) -> Option<Entity> {
+        if let Ok(mut mapping) = self.mapping.try_write() {
+            return mapping.remove(&widget_entity);
+        }
+
+        None
+    }
 }
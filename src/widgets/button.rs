@@ -11,7 +11,7 @@ use crate::{
     event_dispatcher::EventDispatcherContext,
     on_event::OnEvent,
     prelude::{KChildren, KayakWidgetContext, Units},
-    styles::{ComputedStyles, Corner, Edge, KCursorIcon, KStyle, RenderCommand, StyleProp},
+    styles::{ComputedStyles, Corner, Edge, KCursorIcon, KStyle, RenderCommand, Role, StyleProp},
     widget::Widget,
     widget_state::WidgetState,
 };
@@ -82,6 +82,7 @@ pub fn button_render(
                     border: Edge::all(2.0).into(),
                     border_radius: StyleProp::Value(Corner::all(10.0)),
                     height: StyleProp::Value(Units::Pixels(28.0)),
+                    role: StyleProp::Value(Role::Button),
                     width: Units::Stretch(1.0).into(),
                     cursor: StyleProp::Value(KCursorIcon(CursorIcon::Hand)),
                     ..Default::default()
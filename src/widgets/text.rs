@@ -4,7 +4,7 @@ use kayak_font::Alignment;
 use crate::{
     context::WidgetName,
     prelude::KayakWidgetContext,
-    styles::{ComputedStyles, KCursorIcon, KStyle, RenderCommand, StyleProp},
+    styles::{ComputedStyles, KCursorIcon, KStyle, LineHeight, RenderCommand, StyleProp},
     widget::Widget,
 };
 
@@ -103,7 +103,7 @@ pub fn text_render(
                     StyleProp::default()
                 },
                 line_height: if let Some(line_height) = text.line_height {
-                    StyleProp::Value(line_height)
+                    StyleProp::Value(LineHeight::Pixels(line_height))
                 } else {
                     StyleProp::default()
                 },
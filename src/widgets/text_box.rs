@@ -12,7 +12,7 @@ use crate::{
     on_layout::OnLayout,
     prelude::{KChildren, KayakWidgetContext, OnChange},
     render::font::FontMapping,
-    styles::{ComputedStyles, Edge, KPositionType, KStyle, RenderCommand, StyleProp, Units},
+    styles::{ComputedStyles, Edge, KPositionType, KStyle, RenderCommand, Role, StyleProp, Units},
     widget::Widget,
     widget_state::WidgetState,
     widgets::{
@@ -159,6 +159,7 @@ pub fn text_box_render(
                     top: Units::Pixels(0.0).into(),
                     bottom: Units::Pixels(0.0).into(),
                     height: Units::Pixels(26.0).into(),
+                    role: StyleProp::Value(Role::TextInput),
                     // cursor: CursorIcon::Text.into(),
                     ..Default::default()
                 })
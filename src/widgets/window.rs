@@ -12,8 +12,8 @@ use crate::{
     on_event::OnEvent,
     prelude::KayakWidgetContext,
     styles::{
-        ComputedStyles, Corner, Edge, KCursorIcon, KPositionType, KStyle, RenderCommand, StyleProp,
-        Units,
+        ComputedStyles, Corner, Edge, KCursorIcon, KPositionType, KStyle, RenderCommand, Role,
+        StyleProp, Units,
     },
     widget::Widget,
     widget_state::WidgetState,
@@ -171,6 +171,7 @@ pub fn window_render(
                         border: StyleProp::Value(Edge::all(2.0)),
                         border_radius: StyleProp::Value(Corner::all(10.0)),
                         render_command: StyleProp::Value(RenderCommand::Quad),
+                        role: StyleProp::Value(Role::Window),
                         position_type: StyleProp::Value(KPositionType::SelfDirected),
                         left: StyleProp::Value(Units::Pixels(state.position.x)),
                         top: StyleProp::Value(Units::Pixels(state.position.y)),
@@ -3,6 +3,8 @@ use bevy::{
     window::{WindowCreated, WindowResized},
 };
 
+use crate::prelude::KayakRootContext;
+
 /// Tracks the bevy window size.
 #[derive(Resource, Default, Debug, Clone, Copy, PartialEq)]
 pub struct WindowSize(pub f32, pub f32);
@@ -12,6 +14,7 @@ pub fn update_window_size(
     mut window_created_events: EventReader<WindowCreated>,
     windows: Res<Windows>,
     mut window_size: ResMut<WindowSize>,
+    mut contexts: Query<&mut KayakRootContext>,
 ) {
     let mut changed_window_ids = Vec::new();
     // handle resize events. latest events are handled first because we only want to resize each
@@ -39,6 +42,18 @@ pub fn update_window_size(
             let width = window.width();
             let height = window.height();
             *window_size = WindowSize(width, height);
+            for mut context in contexts.iter_mut() {
+                context.set_viewport_size((width, height));
+            }
+        }
+    }
+
+    // Contexts added after the window was created start out at the current size
+    if window_size.0 > 0.0 && window_size.1 > 0.0 {
+        for mut context in contexts.iter_mut() {
+            if context.viewport_size() == (0.0, 0.0) {
+                context.set_viewport_size((window_size.0, window_size.1));
+            }
         }
     }
 }